@@ -0,0 +1,115 @@
+//! Animated GIF and WebP detection. Frames are counted by walking the container's block
+//! structure, so no pixel data is decoded; a file with more than one frame is animated.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Buffered so skipping small GIF sub-blocks stays within the buffer
+type Reader = BufReader<File>;
+
+/// Whether a path has an extension that can hold an animation
+pub fn is_animatable_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["gif", "webp"].contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Frames in a GIF or WebP file (1 for a still image); None when it's neither or unreadable
+pub fn frame_count(path: &Path) -> Option<u32> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut magic = [0u8; 12];
+    reader.read_exact(&mut magic).ok()?;
+    reader.seek(SeekFrom::Start(0)).ok()?;
+    if magic.starts_with(b"GIF8") {
+        gif_frames(&mut reader)
+    } else if &magic[0..4] == b"RIFF" && &magic[8..12] == b"WEBP" {
+        webp_frames(&mut reader)
+    } else {
+        None
+    }
+}
+
+/// Image descriptors in a GIF, stopping at the trailer
+fn gif_frames(r: &mut Reader) -> Option<u32> {
+    // Signature/version, then the logical screen descriptor
+    let mut header = [0u8; 13];
+    r.read_exact(&mut header).ok()?;
+    skip_color_table(r, header[10])?;
+    let mut frames = 0;
+    loop {
+        match read_u8(r) {
+            // Image descriptor: position, size and flags, optional local palette, LZW data
+            Some(0x2C) => {
+                let mut descriptor = [0u8; 9];
+                r.read_exact(&mut descriptor).ok()?;
+                skip_color_table(r, descriptor[8])?;
+                read_u8(r)?;
+                skip_sub_blocks(r)?;
+                frames += 1;
+            }
+            // Extension: label byte, then data sub-blocks
+            Some(0x21) => {
+                read_u8(r)?;
+                skip_sub_blocks(r)?;
+            }
+            // Trailer, or a truncated file: count what was seen
+            _ => break,
+        }
+    }
+    (frames > 0).then_some(frames)
+}
+
+/// Skip the palette a GIF descriptor's packed flags announce
+fn skip_color_table(r: &mut Reader, flags: u8) -> Option<()> {
+    if flags & 0x80 != 0 {
+        let entries = 2i64 << (flags & 0x07);
+        r.seek_relative(entries * 3).ok()?;
+    }
+    Some(())
+}
+
+/// Skip length-prefixed sub-blocks up to the zero-length terminator
+fn skip_sub_blocks(r: &mut Reader) -> Option<()> {
+    loop {
+        let len = read_u8(r)?;
+        if len == 0 {
+            return Some(());
+        }
+        r.seek_relative(len as i64).ok()?;
+    }
+}
+
+/// `ANMF` chunks in an animated WebP; 1 for a still one
+fn webp_frames(r: &mut Reader) -> Option<u32> {
+    r.seek(SeekFrom::Start(12)).ok()?;
+    let mut frames = 0;
+    let mut animated = false;
+    let mut chunk = [0u8; 8];
+    while r.read_exact(&mut chunk).is_ok() {
+        let size = u32::from_le_bytes(chunk[4..8].try_into().ok()?) as i64;
+        let mut skip = size + (size & 1);
+        match &chunk[0..4] {
+            b"VP8X" => {
+                // Flags byte: bit 1 marks an animation
+                animated = read_u8(r)? & 0x02 != 0;
+                skip -= 1;
+                if !animated {
+                    return Some(1);
+                }
+            }
+            b"ANMF" => frames += 1,
+            // A plain lossy/lossless bitstream means a simple still file
+            b"VP8 " | b"VP8L" if !animated => return Some(1),
+            _ => {}
+        }
+        r.seek_relative(skip).ok()?;
+    }
+    Some(frames.max(1))
+}
+
+fn read_u8(r: &mut Reader) -> Option<u8> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b).ok()?;
+    Some(b[0])
+}
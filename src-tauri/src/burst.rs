@@ -0,0 +1,153 @@
+//! Burst detection: near-identical frames shot seconds apart, with the sharpest one
+//! suggested as the keeper. Frames are compared by a 64-bit difference hash and ranked by
+//! variance of the Laplacian, both computed from the grid thumbnail and cached on the row.
+
+use serde::Serialize;
+use std::path::Path;
+
+/// Frames further apart than this start a new burst
+pub const BURST_WINDOW_SECS: i64 = 3;
+
+/// Max differing hash bits between consecutive frames of the same burst
+const MAX_HASH_DISTANCE: u32 = 10;
+
+/// A dated photo considered for burst grouping, in capture order
+#[derive(Debug, Clone)]
+pub struct BurstCandidate {
+    pub id: i64,
+    pub path: String,
+    pub taken_at: String,
+    /// Capture time as Unix seconds
+    pub taken_unix: i64,
+    pub burst_hash: Option<i64>,
+    pub sharpness: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurstFrame {
+    pub id: i64,
+    pub path: String,
+    pub taken_at: String,
+    pub sharpness: f64,
+}
+
+/// One burst, frames in capture order
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurstGroup {
+    pub frames: Vec<BurstFrame>,
+    /// The sharpest frame
+    pub suggested_id: i64,
+}
+
+/// Runs of at least two candidates each shot within `BURST_WINDOW_SECS` of the previous one.
+/// `candidates` must be in capture order.
+pub fn time_runs(candidates: &[BurstCandidate]) -> Vec<&[BurstCandidate]> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=candidates.len() {
+        let breaks = i == candidates.len()
+            || candidates[i].taken_unix - candidates[i - 1].taken_unix > BURST_WINDOW_SECS;
+        if breaks {
+            if i - start > 1 {
+                runs.push(&candidates[start..i]);
+            }
+            start = i;
+        }
+    }
+    runs
+}
+
+/// Split a time run into bursts of visually near-identical consecutive frames.
+/// Frames without a cached signature are left out.
+pub fn group_run(run: &[BurstCandidate]) -> Vec<BurstGroup> {
+    let mut groups = Vec::new();
+    let mut current: Vec<&BurstCandidate> = Vec::new();
+    let mut last_hash: Option<i64> = None;
+    for c in run {
+        let (Some(hash), Some(_)) = (c.burst_hash, c.sharpness) else {
+            continue;
+        };
+        let similar = last_hash
+            .map(|prev| (prev ^ hash).count_ones() <= MAX_HASH_DISTANCE)
+            .unwrap_or(false);
+        if !similar {
+            groups.extend(finish_group(&current));
+            current.clear();
+        }
+        current.push(c);
+        last_hash = Some(hash);
+    }
+    groups.extend(finish_group(&current));
+    groups
+}
+
+fn finish_group(frames: &[&BurstCandidate]) -> Option<BurstGroup> {
+    if frames.len() < 2 {
+        return None;
+    }
+    let frames: Vec<BurstFrame> = frames
+        .iter()
+        .map(|c| BurstFrame {
+            id: c.id,
+            path: c.path.clone(),
+            taken_at: c.taken_at.clone(),
+            sharpness: c.sharpness.unwrap_or(0.0),
+        })
+        .collect();
+    // Ties go to the earliest frame
+    let suggested_id = frames
+        .iter()
+        .fold(None::<&BurstFrame>, |best, f| match best {
+            Some(b) if b.sharpness >= f.sharpness => Some(b),
+            _ => Some(f),
+        })
+        .map(|f| f.id)?;
+    Some(BurstGroup { frames, suggested_id })
+}
+
+/// Difference hash and sharpness of an image (meant for the small grid thumbnail)
+pub fn compute_signature(path: &Path) -> Option<(i64, f64)> {
+    let img = image::open(path).ok()?;
+    let gray = img.to_luma8();
+    Some((difference_hash(&gray), laplacian_variance(&gray)))
+}
+
+/// 64-bit dHash: each bit says whether a pixel of a 9×8 downscale is brighter than its
+/// right-hand neighbour
+fn difference_hash(gray: &image::GrayImage) -> i64 {
+    let small = image::imageops::resize(gray, 9, 8, image::imageops::FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash as i64
+}
+
+/// Variance of the 4-neighbour Laplacian; blurred frames have weak edges and score low
+fn laplacian_variance(gray: &image::GrayImage) -> f64 {
+    let (w, h) = gray.dimensions();
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+    let px = |x: u32, y: u32| gray.get_pixel(x, y)[0] as f64;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    let mut n = 0.0;
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let lap = 4.0 * px(x, y) - px(x - 1, y) - px(x + 1, y) - px(x, y - 1) - px(x, y + 1);
+            sum += lap;
+            sum_sq += lap * lap;
+            n += 1.0;
+        }
+    }
+    let mean = sum / n;
+    sum_sq / n - mean * mean
+}
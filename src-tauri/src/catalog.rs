@@ -0,0 +1,297 @@
+//! Catalog export and import. Export writes every photo record plus its tag and album
+//! membership row by row, so large libraries never have to fit in memory; import merges
+//! a JSON export back into another database.
+
+use crate::db::{CatalogEntry, Database, PhotoRecord};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Bumped whenever the exported layout changes incompatibly
+pub const CATALOG_VERSION: i64 = 1;
+
+/// CSV column order — the camelCase keys of `CatalogEntry`. Keep new columns at the end.
+const CSV_COLUMNS: &[&str] = &[
+    "id", "source", "path", "filename", "folderRel", "takenAt", "modifiedAt", "mediaType",
+    "sizeBytes", "width", "height", "isFavorite", "isDeleted", "deletedAt", "cameraMake",
+    "cameraModel", "lens", "iso", "shutterSpeed", "aperture", "focalLength", "gpsLat", "gpsLon",
+    "orientation", "exposureBias", "utcOffsetMinutes", "tags", "albums", "blurhash",
+    "category", "dateIsEstimated", "motionPath",
+    "rawPath", "jpegPath", "shutterSeconds", "fNumber", "isMissing", "colorSpace", "isHdr",
+    "title", "caption", "frameCount", "dateSource", "isPlaceholder", "isAnimated",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CatalogFormat {
+    Json,
+    Csv,
+}
+
+impl CatalogFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(CatalogFormat::Json),
+            "csv" => Ok(CatalogFormat::Csv),
+            other => Err(format!("Unsupported catalog format: {}", other)),
+        }
+    }
+}
+
+/// Write the whole catalog to `out_path`; returns the number of photos exported
+pub fn export_catalog(db: &Database, format: CatalogFormat, out_path: &Path) -> Result<i64, String> {
+    let file = File::create(out_path).map_err(|e| e.to_string())?;
+    let mut w = BufWriter::new(file);
+    let count = match format {
+        CatalogFormat::Json => write_json(db, &mut w)?,
+        CatalogFormat::Csv => write_csv(db, &mut w)?,
+    };
+    w.flush().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// `{"version", "exportedAt", "tags", "albums", "photos": [...]}` — the small tag and
+/// album lists go first so an importer can recreate them before touching photos
+fn write_json(db: &Database, w: &mut impl Write) -> Result<i64, String> {
+    let tags = db.get_tags().map_err(|e| e.to_string())?;
+    let albums = db.get_albums().map_err(|e| e.to_string())?;
+    write!(
+        w,
+        "{{\"version\":{},\"exportedAt\":{},\"tags\":{},\"albums\":{},\"photos\":[",
+        CATALOG_VERSION,
+        serde_json::to_string(&Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()).map_err(|e| e.to_string())?,
+        serde_json::to_string(&tags).map_err(|e| e.to_string())?,
+        serde_json::to_string(&albums).map_err(|e| e.to_string())?,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut first = true;
+    let count = db.for_each_catalog_entry(|entry| {
+        if !first {
+            w.write_all(b",")?;
+        }
+        first = false;
+        w.write_all(b"\n")?;
+        serde_json::to_writer(&mut *w, &entry)?;
+        Ok(())
+    })?;
+
+    w.write_all(b"\n]}\n").map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+fn write_csv(db: &Database, w: &mut impl Write) -> Result<i64, String> {
+    writeln!(w, "{}", CSV_COLUMNS.join(",")).map_err(|e| e.to_string())?;
+    db.for_each_catalog_entry(|entry| {
+        let line = csv_row(&entry)?;
+        writeln!(w, "{}", line)
+    })
+}
+
+fn csv_row(entry: &CatalogEntry) -> std::io::Result<String> {
+    let value = serde_json::to_value(entry)?;
+    let cells: Vec<String> = CSV_COLUMNS
+        .iter()
+        .map(|col| csv_escape(&csv_cell(value.get(col))))
+        .collect();
+    Ok(cells.join(","))
+}
+
+/// Nulls become empty cells; tag/album lists are joined with "; "
+fn csv_cell(value: Option<&serde_json::Value>) -> String {
+    use serde_json::Value;
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_string()).unwrap_or_else(|| v.to_string()))
+            .collect::<Vec<_>>()
+            .join("; "),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// RFC 4180 quoting: wrap in quotes when needed, doubling embedded quotes
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+// ── Import ──
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CatalogFile {
+    version: i64,
+    #[serde(default)]
+    tags: Vec<CatalogTag>,
+    #[serde(default)]
+    albums: Vec<CatalogAlbum>,
+    #[serde(default)]
+    photos: Vec<CatalogPhoto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogTag {
+    name: String,
+    color: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogAlbum {
+    name: String,
+}
+
+/// The subset of an exported `CatalogEntry` that import needs
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CatalogPhoto {
+    path: String,
+    filename: String,
+    size_bytes: i64,
+    taken_at: Option<String>,
+    #[serde(default)]
+    is_favorite: bool,
+    #[serde(default)]
+    is_deleted: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    albums: Vec<String>,
+}
+
+/// A field whose local value differs from the imported one; the local value is kept
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportConflict {
+    pub path: String,
+    pub field: String,
+    pub local: String,
+    pub imported: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub matched: i64,
+    /// Catalog photos with no counterpart in this database (not indexed here yet)
+    pub unmatched: i64,
+    pub tags_created: i64,
+    pub albums_created: i64,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+/// Merge a JSON catalog written by `export_catalog` into this database.
+///
+/// Photos are matched by path, falling back to a unique filename + size match so a library
+/// moved to another mount point still lines up. Tags and albums are recreated by name and
+/// memberships are added (never removed). Scalar metadata is never overwritten: differences
+/// are returned as conflicts for the user to resolve.
+pub fn import_catalog(db: &Database, src_path: &Path) -> Result<ImportSummary, String> {
+    let file = File::open(src_path).map_err(|e| e.to_string())?;
+    let catalog: CatalogFile =
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("Invalid catalog: {}", e))?;
+    if catalog.version > CATALOG_VERSION {
+        return Err(format!(
+            "Catalog version {} is newer than supported ({})",
+            catalog.version, CATALOG_VERSION
+        ));
+    }
+
+    let mut summary = ImportSummary::default();
+
+    // Tags and albums by name → local id, creating whatever is missing
+    let mut tag_ids: HashMap<String, i64> = db
+        .get_tags()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|t| (t.name, t.id))
+        .collect();
+    for tag in &catalog.tags {
+        if !tag_ids.contains_key(&tag.name) {
+            let created = db.create_tag(&tag.name, &tag.color).map_err(|e| e.to_string())?;
+            tag_ids.insert(created.name, created.id);
+            summary.tags_created += 1;
+        }
+    }
+    let mut album_ids: HashMap<String, i64> = HashMap::new();
+    for album in db.get_albums().map_err(|e| e.to_string())? {
+        album_ids.entry(album.name).or_insert(album.id);
+    }
+    for album in &catalog.albums {
+        if !album_ids.contains_key(&album.name) {
+            let created = db.create_album(&album.name, None).map_err(|e| e.to_string())?;
+            album_ids.insert(created.name, created.id);
+            summary.albums_created += 1;
+        }
+    }
+
+    for photo in &catalog.photos {
+        let local = match find_local_photo(db, photo)? {
+            Some(p) => p,
+            None => {
+                summary.unmatched += 1;
+                continue;
+            }
+        };
+        summary.matched += 1;
+
+        let mut conflict = |field: &str, ours: String, theirs: String| {
+            if ours != theirs {
+                summary.conflicts.push(ImportConflict {
+                    path: local.path.clone(),
+                    field: field.to_string(),
+                    local: ours,
+                    imported: theirs,
+                });
+            }
+        };
+        let fmt = |v: &Option<String>| v.clone().unwrap_or_default();
+        conflict("takenAt", fmt(&local.taken_at), fmt(&photo.taken_at));
+        conflict("isFavorite", local.is_favorite.to_string(), photo.is_favorite.to_string());
+        conflict("isDeleted", local.is_deleted.to_string(), photo.is_deleted.to_string());
+
+        for name in &photo.tags {
+            // Tags referenced by photos but missing from the tag list get the default color
+            let tag_id = match tag_ids.get(name) {
+                Some(id) => *id,
+                None => {
+                    let created = db.create_tag(name, "#0071e3").map_err(|e| e.to_string())?;
+                    summary.tags_created += 1;
+                    *tag_ids.entry(created.name).or_insert(created.id)
+                }
+            };
+            db.tag_photos(&[local.id], tag_id).map_err(|e| e.to_string())?;
+        }
+        for name in &photo.albums {
+            let album_id = match album_ids.get(name) {
+                Some(id) => *id,
+                None => {
+                    let created = db.create_album(name, None).map_err(|e| e.to_string())?;
+                    summary.albums_created += 1;
+                    *album_ids.entry(created.name).or_insert(created.id)
+                }
+            };
+            db.add_photos_to_album(album_id, &[local.id]).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn find_local_photo(db: &Database, photo: &CatalogPhoto) -> Result<Option<PhotoRecord>, String> {
+    if let Some(p) = db.get_photo_by_path(&photo.path).map_err(|e| e.to_string())? {
+        return Ok(Some(p));
+    }
+    let mut candidates = db
+        .find_photos_by_name_and_size(&photo.filename, photo.size_bytes)
+        .map_err(|e| e.to_string())?;
+    // Ambiguous matches are skipped rather than guessed
+    Ok(if candidates.len() == 1 { candidates.pop() } else { None })
+}
@@ -1,84 +1,464 @@
 use crate::db::Database;
+use crate::error::AppError;
 use crate::scan;
 use crate::thumb;
+use crate::undo::{FileMove, UndoLog, UndoOp};
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::mpsc;
 
 pub struct AppState {
+    /// Where the catalog lives; opened lazily by `ensure_db` when a command needs it.
+    /// Changes only through `set_database_location`.
+    db_path: Mutex<std::path::PathBuf>,
     db: Mutex<Option<Database>>,
-    library_root: Mutex<Option<String>>,
-    library_roots: Mutex<Vec<(i64, String)>>,
+    libraries: Mutex<OpenLibraries>,
+    /// Library root → id, filled on first resolution so repeat lookups skip the upsert.
+    /// Entries are dropped when their library is removed.
+    library_ids: Mutex<std::collections::HashMap<String, i64>>,
+    /// Latest index-progress snapshot, so a window reopened mid-scan can render immediately
+    index_progress: Mutex<Option<IndexProgress>>,
+    undo_log: Mutex<UndoLog>,
+    /// Index runs in flight (see `ScanGuard`)
+    active_scans: AtomicUsize,
+    /// Held by the index run currently writing the catalog; later runs queue on it
+    index_turn: std::sync::Arc<tokio::sync::Mutex<()>>,
+    /// Set by `cancel_reparse_exif`; checked between chunks
+    reparse_cancel: AtomicBool,
 }
 
-#[derive(Clone, Serialize)]
+/// The libraries open this session — the single source of truth for which libraries exist.
+/// "All libraries" views use every entry; single-library views use the active one.
+#[derive(Default)]
+struct OpenLibraries {
+    entries: Vec<(i64, String)>,
+    active: Option<i64>,
+}
+
+impl OpenLibraries {
+    fn ids(&self) -> Vec<i64> {
+        self.entries.iter().map(|(id, _)| *id).collect()
+    }
+
+    fn active_root(&self) -> Option<&str> {
+        let active = self.active?;
+        self.entries.iter().find(|(id, _)| *id == active).map(|(_, root)| root.as_str())
+    }
+
+    /// Add a library if it isn't open yet and make it the active one
+    fn open(&mut self, id: i64, root: String) {
+        self.add(id, root);
+        self.active = Some(id);
+    }
+
+    /// Add a library if it isn't open yet; it only becomes active when none is
+    fn add(&mut self, id: i64, root: String) {
+        if !self.entries.iter().any(|(existing, _)| *existing == id) {
+            self.entries.push((id, root));
+        }
+        if self.active.is_none() {
+            self.active = Some(id);
+        }
+    }
+
+    /// Point an open library at its new root
+    fn relocate(&mut self, id: i64, root: String) {
+        if let Some(entry) = self.entries.iter_mut().find(|(existing, _)| *existing == id) {
+            entry.1 = root;
+        }
+    }
+
+    fn close(&mut self, id: i64) {
+        self.entries.retain(|(existing, _)| *existing != id);
+        if self.active == Some(id) {
+            self.active = self.entries.first().map(|(id, _)| *id);
+        }
+    }
+
+    /// Add any of `entries` not open yet, keeping the active library
+    fn merge(&mut self, entries: Vec<(i64, String)>) {
+        for (id, root) in entries {
+            if !self.entries.iter().any(|(existing, _)| *existing == id) {
+                self.entries.push((id, root));
+            }
+        }
+        if self.active.is_none() {
+            self.active = self.entries.first().map(|(id, _)| *id);
+        }
+    }
+
+    fn replace(&mut self, entries: Vec<(i64, String)>) {
+        self.entries = entries;
+        if !self.active.is_some_and(|a| self.entries.iter().any(|(id, _)| *id == a)) {
+            self.active = self.entries.first().map(|(id, _)| *id);
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IndexProgress {
     pub phase: String,
     pub current: u64,
     pub total: Option<u64>,
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+    /// Remaining time extrapolated from byte throughput so far
+    pub eta_seconds: Option<u64>,
+    /// Set when only one folder subtree is being re-indexed
+    pub folder: Option<String>,
+}
+
+/// Lock an AppState field, recovering from poisoning: a panic in one command must not
+/// fail every later command that touches the same state
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Record the progress snapshot in AppState and emit the `index-progress` event
+fn emit_progress(app: &AppHandle, progress: IndexProgress) {
+    if let Some(state) = app.try_state::<AppState>() {
+        *lock(&state.index_progress) = Some(progress.clone());
+    }
+    app.emit("index-progress", progress).ok();
+}
+
+/// Broadcast a finished index run's summary as `index-complete`, so windows that didn't
+/// start the run (and aren't awaiting its result) still learn it finished
+fn emit_index_complete(app: &AppHandle, summary: &serde_json::Value) {
+    app.emit("index-complete", summary).ok();
+}
+
+/// Counts an index run as in flight for as long as it's alive, so operations that can't
+/// overlap a scan (moving the catalog) can refuse. Runs also take turns: `begin` waits for
+/// the run ahead to finish, so two scans never write the catalog or app state at once.
+struct ScanGuard {
+    app: AppHandle,
+    _turn: Option<tokio::sync::OwnedMutexGuard<()>>,
+}
+
+impl ScanGuard {
+    async fn begin(app: &AppHandle) -> Self {
+        let turn = app.try_state::<AppState>().map(|state| {
+            state.active_scans.fetch_add(1, Ordering::SeqCst);
+            state.index_turn.clone()
+        });
+        // Built before waiting, so a caller dropped while queued still uncounts itself
+        let mut guard = ScanGuard { app: app.clone(), _turn: None };
+        if let Some(turn) = turn {
+            guard._turn = Some(turn.lock_owned().await);
+        }
+        guard
+    }
+}
+
+impl Drop for ScanGuard {
+    fn drop(&mut self) {
+        if let Some(state) = self.app.try_state::<AppState>() {
+            state.active_scans.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Progress message sent from the blocking indexer to the event emitter
+#[derive(Clone, Copy)]
+struct ScanTick {
+    current: u64,
+    total: u64,
+    bytes_done: u64,
+    bytes_total: u64,
+    /// Time spent parsing since path collection finished
+    elapsed: std::time::Duration,
+}
+
+impl IndexProgress {
+    fn from_tick(phase: String, tick: ScanTick) -> Self {
+        IndexProgress {
+            phase,
+            current: tick.current,
+            total: Some(tick.total),
+            bytes_done: tick.bytes_done,
+            bytes_total: Some(tick.bytes_total),
+            eta_seconds: estimate_eta(tick.elapsed, tick.bytes_done, tick.bytes_total),
+            folder: None,
+        }
+    }
+}
+
+/// Bytes are a better proxy for work than file count — a 50MB RAW costs far more than a small JPEG
+fn estimate_eta(elapsed: std::time::Duration, bytes_done: u64, bytes_total: u64) -> Option<u64> {
+    if bytes_done == 0 || bytes_total < bytes_done {
+        return None;
+    }
+    let rate = bytes_done as f64 / elapsed.as_secs_f64().max(0.001);
+    Some(((bytes_total - bytes_done) as f64 / rate).ceil() as u64)
+}
+
+/// Throughput of a finished index run (walk, parse and insert), for spotting slow drives
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanMetrics {
+    pub files: u64,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub files_per_sec: f64,
+    pub mb_per_sec: f64,
+}
+
+impl ScanMetrics {
+    fn new(files: u64, bytes: u64, elapsed: std::time::Duration) -> Self {
+        let secs = elapsed.as_secs_f64().max(0.001);
+        ScanMetrics {
+            files,
+            bytes,
+            duration_ms: elapsed.as_millis() as u64,
+            files_per_sec: files as f64 / secs,
+            mb_per_sec: bytes as f64 / (1024.0 * 1024.0) / secs,
+        }
+    }
+
+    /// Combined throughput of several runs
+    fn sum(runs: &[ScanMetrics]) -> Self {
+        let files = runs.iter().map(|m| m.files).sum();
+        let bytes = runs.iter().map(|m| m.bytes).sum();
+        let ms = runs.iter().map(|m| m.duration_ms).sum();
+        Self::new(files, bytes, std::time::Duration::from_millis(ms))
+    }
+}
+
+/// Result of indexing one directory tree
+struct IndexRun {
+    scanned: Vec<scan::ScannedFile>,
+    /// Total size of the files parsed
+    bytes: u64,
+    /// Files left out because their path isn't valid UTF-8 (lossy, for display)
+    skipped_paths: Vec<String>,
+    /// Files that overran the per-file parse timeout
+    timed_out: Vec<String>,
+}
+
+/// Collect and parse all media under `path` in chunks, reporting progress on `tx`.
+/// Blocking — run inside spawn_blocking.
+fn index_paths_blocking(
+    path: &std::path::Path,
+    opts: &scan::ScanOptions,
+    tx: mpsc::UnboundedSender<ScanTick>,
+) -> IndexRun {
+    let root = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let (files, skipped_paths) = scan::collect_media_files_reporting(path, opts);
+    let bytes = files.iter().map(|(_, size)| size).sum();
+    let (scanned, timed_out) = parse_files_blocking(&files, &root, opts, tx);
+    IndexRun { scanned, bytes, skipped_paths, timed_out }
+}
+
+/// Parse already-collected `(path, size)` files in chunks, with folder_rel relative to
+/// `root`, reporting progress on `tx`. Also returns the paths that timed out.
+/// Blocking — run inside spawn_blocking.
+fn parse_files_blocking(
+    files: &[(std::path::PathBuf, u64)],
+    root: &std::path::Path,
+    opts: &scan::ScanOptions,
+    tx: mpsc::UnboundedSender<ScanTick>,
+) -> (Vec<scan::ScannedFile>, Vec<String>) {
+    let total = files.len() as u64;
+    let bytes_total: u64 = files.iter().map(|(_, size)| size).sum();
+    let started = std::time::Instant::now();
+    let mut tick = ScanTick {
+        current: 0,
+        total,
+        bytes_done: 0,
+        bytes_total,
+        elapsed: std::time::Duration::ZERO,
+    };
+    let _ = tx.send(tick);
+    let mut all: Vec<scan::ScannedFile> = Vec::new();
+    let mut timed_out: Vec<String> = Vec::new();
+    const CHUNK: usize = 50;
+    for chunk in files.chunks(CHUNK) {
+        let paths: Vec<std::path::PathBuf> = chunk.iter().map(|(p, _)| p.clone()).collect();
+        let (batch, batch_timed_out) = scan::process_paths_batch(&paths, root, opts);
+        all.extend(batch);
+        timed_out.extend(batch_timed_out);
+        tick.current = all.len() as u64;
+        tick.bytes_done += chunk.iter().map(|(_, size)| size).sum::<u64>();
+        tick.elapsed = started.elapsed();
+        let _ = tx.send(tick);
+    }
+    scan::pair_live_photos(&mut all);
+    scan::pair_raw_jpeg(&mut all);
+    (all, timed_out)
 }
 
+const DB_FILE_NAME: &str = "photo_sorter.db";
+
+/// Small file in the app data dir naming a user-chosen catalog path. It can't live in the
+/// catalog itself: it's needed to find the catalog.
+const DB_LOCATION_FILE: &str = "db_location";
+
+/// The catalog path in use: the session's (possibly moved) location once state exists
 fn db_path(app: &AppHandle) -> std::path::PathBuf {
-    app.path()
-        .app_data_dir()
-        .expect("app data dir")
-        .join("photo_sorter.db")
+    match app.try_state::<AppState>() {
+        Some(state) => lock(&state.db_path).clone(),
+        None => configured_db_path(app),
+    }
+}
+
+/// The persisted catalog location, falling back to `photo_sorter.db` in the app data dir
+fn configured_db_path(app: &AppHandle) -> std::path::PathBuf {
+    let data_dir = app.path().app_data_dir().expect("app data dir");
+    std::fs::read_to_string(data_dir.join(DB_LOCATION_FILE))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| data_dir.join(DB_FILE_NAME))
+}
+
+// Settings keys (values are JSON-encoded)
+const SETTING_PHOTO_EXTENSIONS: &str = "photo_extensions";
+const SETTING_VIDEO_EXTENSIONS: &str = "video_extensions";
+const SETTING_IGNORE_PATTERNS: &str = "ignore_patterns";
+const SETTING_RAW_PREFERENCE: &str = "raw_preference";
+const SETTING_SCAN_THREADS: &str = "scan_threads";
+const SETTING_THUMB_CONCURRENCY: &str = "thumb_concurrency";
+const SETTING_THUMB_QUALITY: &str = "thumb_quality";
+const SETTING_ANIMATED_THUMBS: &str = "animated_thumbnails";
+const SETTING_DEFAULT_SCAN_DIRS: &str = "default_scan_dirs";
+const SETTING_MTIME_DATE_FALLBACK: &str = "mtime_date_fallback";
+
+/// Keys owned by dedicated commands, which validate them and apply them at runtime;
+/// the generic `set_setting` refuses them
+const MANAGED_SETTINGS: &[&str] = &[
+    SETTING_PHOTO_EXTENSIONS,
+    SETTING_VIDEO_EXTENSIONS,
+    SETTING_IGNORE_PATTERNS,
+    SETTING_RAW_PREFERENCE,
+    SETTING_SCAN_THREADS,
+    SETTING_THUMB_CONCURRENCY,
+    SETTING_THUMB_QUALITY,
+    SETTING_ANIMATED_THUMBS,
+    SETTING_DEFAULT_SCAN_DIRS,
+    SETTING_MTIME_DATE_FALLBACK,
+];
+
+/// Longest key accepted by `set_setting`
+const MAX_SETTING_KEY_LEN: usize = 128;
+
+/// Upper bound for the performance settings, to catch typos like 1000
+const MAX_PARALLELISM: usize = 256;
+
+/// Which member of a RAW+JPEG pair collapsed listings show ("jpeg" unless set)
+fn raw_preference(db: &Database) -> String {
+    db.get_setting_json::<String>(SETTING_RAW_PREFERENCE).unwrap_or_else(|| "jpeg".to_string())
+}
+
+/// Push persisted scanner settings into the runtime scan configuration
+fn apply_scan_settings(db: &Database) {
+    scan::set_media_extensions(
+        db.get_setting_json(SETTING_PHOTO_EXTENSIONS),
+        db.get_setting_json(SETTING_VIDEO_EXTENSIONS),
+    );
+    scan::set_ignore_patterns(db.get_setting_json(SETTING_IGNORE_PATTERNS));
+    scan::set_scan_threads(db.get_setting_json(SETTING_SCAN_THREADS));
+    thumb::set_thumb_concurrency(db.get_setting_json(SETTING_THUMB_CONCURRENCY));
+    thumb::set_thumb_quality(db.get_setting_json(SETTING_THUMB_QUALITY));
+    thumb::set_animated_thumbs(db.get_setting_json(SETTING_ANIMATED_THUMBS));
+    scan::set_mtime_date_fallback(db.get_setting_json(SETTING_MTIME_DATE_FALLBACK));
+}
+
+/// Open (creating if needed) the on-disk database and apply persisted settings
+fn open_database(app: &AppHandle) -> Result<Database, AppError> {
+    let db_path = db_path(app);
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let db = Database::new(&db_path)?;
+    apply_scan_settings(&db);
+    Ok(db)
+}
+
+/// Scan options carrying a library's stored per-library settings
+fn library_scan_options(db: &Database, library_id: i64) -> scan::ScanOptions {
+    scan::ScanOptions {
+        flatten_folders: db.library_flattens_folders(library_id).unwrap_or(false),
+        ..Default::default()
+    }
 }
 
 #[tauri::command]
-pub async fn select_and_index(app: AppHandle, path: String) -> Result<serde_json::Value, String> {
+pub async fn select_and_index(
+    app: AppHandle,
+    path: String,
+    follow_links: Option<bool>,
+    blurhash: Option<bool>,
+    takeout: Option<bool>,
+    flatten: Option<bool>,
+) -> Result<serde_json::Value, AppError> {
+    index_library(app, path, follow_links, blurhash, takeout, flatten, true).await
+}
+
+/// Index `path` as a library (updating its rows in place) and open it. `activate`
+/// makes it the active library; otherwise it only joins the open set.
+async fn index_library(
+    app: AppHandle,
+    path: String,
+    follow_links: Option<bool>,
+    blurhash: Option<bool>,
+    takeout: Option<bool>,
+    flatten: Option<bool>,
+    activate: bool,
+) -> Result<serde_json::Value, AppError> {
     let path = std::path::PathBuf::from(&path);
     if !path.exists() || !path.is_dir() {
-        return Err("Invalid or missing directory".to_string());
+        return Err(AppError::InvalidInput("Invalid or missing directory".to_string()));
     }
     let root_str = path.to_string_lossy().to_string();
+    let _scan = ScanGuard::begin(&app).await;
 
-    let db_path = db_path(&app);
-    if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let db = open_database(&app)?;
+    let library_id = match app.try_state::<AppState>() {
+        Some(state) => library_id_for_root(&db, &state, &root_str)?,
+        None => db.get_or_create_library(&root_str)?,
+    };
+    // Stored on the library so later rescans of it group folders the same way
+    if let Some(flatten) = flatten {
+        db.set_library_flatten_folders(library_id, flatten)?;
     }
+    // Rows are updated in place rather than cleared, so favorites, tags and albums survive;
+    // this snapshot finds the files that have since disappeared, which are flagged missing
+    let existing = db.get_folder_index_state(library_id, "")?;
 
-    let db = Database::new(&db_path).map_err(|e| e.to_string())?;
-    let library_id = db.get_or_create_library(&root_str).map_err(|e| e.to_string())?;
-    db.clear_photos_for_library(library_id).map_err(|e| e.to_string())?;
-
-    app.emit("index-progress", IndexProgress {
+    emit_progress(&app, IndexProgress {
         phase: "scanning".to_string(),
         current: 0,
         total: None,
-    })
-    .ok();
+        ..Default::default()
+    });
+
+    let scan_opts = scan::ScanOptions {
+        follow_links: follow_links.unwrap_or(false),
+        blurhash: blurhash.unwrap_or(false),
+        takeout_sidecars: takeout.unwrap_or(false),
+        ..library_scan_options(&db, library_id)
+    };
 
-    let (tx, mut rx) = mpsc::unbounded_channel::<(u64, u64)>();
+    let (tx, mut rx) = mpsc::unbounded_channel::<ScanTick>();
     let path_clone = path.clone();
     let app_handle = app.clone();
     let recv_handle = tauri::async_runtime::spawn(async move {
-        while let Some((current, total)) = rx.recv().await {
-            app_handle
-                .emit("index-progress", IndexProgress {
-                    phase: "indexing".to_string(),
-                    current,
-                    total: Some(total),
-                })
-                .ok();
+        while let Some(tick) = rx.recv().await {
+            emit_progress(&app_handle, IndexProgress::from_tick("indexing".to_string(), tick));
         }
     });
 
-    let scanned = tauri::async_runtime::spawn_blocking(move || {
-        let root = path_clone.canonicalize().unwrap_or_else(|_| path_clone.clone());
-        let paths = scan::collect_media_paths(&path_clone);
-        let total = paths.len() as u64;
-        let _ = tx.send((0, total));
-        let mut all: Vec<scan::ScannedFile> = Vec::new();
-        const CHUNK: usize = 50;
-        for chunk in paths.chunks(CHUNK) {
-            let batch = scan::process_paths_batch(chunk, &root);
-            all.extend(batch);
-            let _ = tx.send((all.len() as u64, total));
-        }
-        all
+    let started = std::time::Instant::now();
+    let IndexRun { scanned, bytes, skipped_paths, timed_out } = tauri::async_runtime::spawn_blocking(move || {
+        index_paths_blocking(&path_clone, &scan_opts, tx)
     })
     .await
     .map_err(|e| e.to_string())?;
@@ -87,31 +467,59 @@ pub async fn select_and_index(app: AppHandle, path: String) -> Result<serde_json
 
     let total = scanned.len();
     // Use batch insert with transaction — ~50x faster
-    db.batch_insert_photos(library_id, &scanned).map_err(|e| e.to_string())?;
+    db.insert_photos_batch(library_id, &scanned)?;
+    // Timed-out files are still on disk, so their rows stay
+    let present: std::collections::HashSet<&str> = scanned
+        .iter()
+        .map(|f| f.path.as_str())
+        .chain(timed_out.iter().map(String::as_str))
+        .collect();
+    // A file the scan skipped (read error, ignore pattern) may still be there; only rows whose
+    // file is really gone are flagged, and `purge_missing_photos` decides when to drop them
+    let gone: Vec<i64> = existing
+        .iter()
+        .filter(|(path, _)| !present.contains(path.as_str()) && !std::path::Path::new(path).exists())
+        .map(|(_, f)| f.id)
+        .collect();
+    db.set_missing(&gone, true)?;
+    db.clear_scan_timeouts(library_id, None)?;
+    db.record_scan_timeouts(library_id, &timed_out)?;
+    let metrics = ScanMetrics::new(total as u64, bytes, started.elapsed());
+    eprintln!(
+        "  ⏱ Indexed {} files in {} ms ({:.1} files/s, {:.1} MB/s)",
+        total, metrics.duration_ms, metrics.files_per_sec, metrics.mb_per_sec
+    );
 
-    app.emit("index-progress", IndexProgress {
+    emit_progress(&app, IndexProgress {
         phase: "done".to_string(),
         current: total as u64,
         total: Some(total as u64),
-    })
-    .ok();
+        ..Default::default()
+    });
 
     if let Some(state) = app.try_state::<AppState>() {
-        *state.db.lock().unwrap() = Some(db);
-        *state.library_root.lock().unwrap() = Some(root_str.clone());
-        // Add to library_roots if not already present
-        let mut roots = state.library_roots.lock().unwrap();
-        if !roots.iter().any(|(id, _)| *id == library_id) {
-            roots.push((library_id, root_str.clone()));
+        // Keep a connection another command already opened; both point at the same catalog
+        lock(&state.db).get_or_insert(db);
+        let mut libraries = lock(&state.libraries);
+        if activate {
+            libraries.open(library_id, root_str.clone());
+        } else {
+            libraries.add(library_id, root_str.clone());
         }
-        eprintln!("✓ App state set: library_root = {}, library_id = {}, total photos = {}", root_str, library_id, total);
+        eprintln!("✓ App state set: library = {}, library_id = {}, total photos = {}", root_str, library_id, total);
     }
 
-    Ok(serde_json::json!({
+    let summary = serde_json::json!({
         "libraryPath": root_str,
         "totalPhotos": total,
-        "libraryId": library_id
-    }))
+        "libraryId": library_id,
+        "durationMs": metrics.duration_ms,
+        "metrics": metrics,
+        "skippedPaths": skipped_paths,
+        "timedOutPaths": timed_out
+    });
+    emit_index_complete(&app, &summary);
+    Ok(summary)
 }
 
 
@@ -120,579 +528,2910 @@ pub async fn select_and_index(app: AppHandle, path: String) -> Result<serde_json
 pub async fn get_categories(
     _app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<serde_json::Value, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    let root_guard = state.library_root.lock().unwrap();
-    let root = root_guard.as_ref().ok_or("No library path")?;
-
-    let library_id = db.get_or_create_library(root).map_err(|e| e.to_string())?;
+    include_undated: Option<bool>,
+) -> Result<serde_json::Value, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, None)?;
 
-    let years = db.get_years(library_id).map_err(|e| e.to_string())?;
-    let folders = db.get_folders_flat(library_id).map_err(|e| e.to_string())?;
-    let types = db.get_media_type_counts(library_id).map_err(|e| e.to_string())?;
+    let years = db.get_years(library_id)?;
+    let folders = db.get_folders_flat(library_id)?;
+    let types = db.get_media_type_counts(library_id)?;
 
-    Ok(serde_json::json!({
+    let mut categories = serde_json::json!({
         "years": years,
         "folders": folders,
         "mediaTypes": types
-    }))
+    });
+    // Photos without a capture date sit outside every year; report them as their own bucket
+    if include_undated.unwrap_or(false) {
+        categories["undated"] = db.count_undated(library_id)?.into();
+    }
+    Ok(categories)
 }
 
 #[tauri::command]
 pub async fn get_months(
     state: State<'_, AppState>,
     year: i32,
-) -> Result<Vec<crate::db::CategoryMonth>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    let root_guard = state.library_root.lock().unwrap();
-    let root = root_guard.as_ref().ok_or("No library path")?;
-    let library_id = db.get_or_create_library(root).map_err(|e| e.to_string())?;
-    db.get_months(library_id, year).map_err(|e| e.to_string())
+) -> Result<Vec<crate::db::CategoryMonth>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, None)?;
+    db.get_months(library_id, year).map_err(AppError::from)
 }
 
-#[derive(Deserialize)]
-pub struct GetPhotosParams {
-    limit: Option<i64>,
-    offset: Option<i64>,
-    year: Option<i32>,
-    month: Option<i32>,
-    folder: Option<String>,
-    #[serde(rename = "mediaType")]
-    media_type: Option<String>,
+/// A (year, month) timeline bucket named by the caller
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct MonthBucket {
+    pub year: i32,
+    pub month: i32,
 }
 
+/// After a date edit (e.g. `shift_dates`), fresh year/month counts for the
+/// buckets `photoIds` are in now and the `previous` buckets they were shown under, so the
+/// sidebar can be patched without refetching every category
 #[tauri::command]
-pub async fn get_photos(
+pub async fn get_category_deltas(
     state: State<'_, AppState>,
-    params: Option<GetPhotosParams>,
-) -> Result<Vec<crate::db::PhotoRecord>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    let root_guard = state.library_root.lock().unwrap();
-    let root = root_guard.as_ref().ok_or("No library path")?;
-
-    let library_id = db.get_or_create_library(root).map_err(|e| e.to_string())?;
-
-    let limit = params.as_ref().and_then(|p| p.limit).unwrap_or(100);
-    let offset = params.as_ref().and_then(|p| p.offset).unwrap_or(0);
-    let year = params.as_ref().and_then(|p| p.year);
-    let month = params.as_ref().and_then(|p| p.month);
-    let folder = params.as_ref().and_then(|p| p.folder.as_deref());
-    let media_type = params.as_ref().and_then(|p| p.media_type.as_deref());
-
-    db.get_photos(library_id, limit, offset, year, month, folder, media_type)
-        .map_err(|e| e.to_string())
+    photo_ids: Vec<i64>,
+    previous: Option<Vec<MonthBucket>>,
+) -> Result<crate::db::CategoryDeltas, AppError> {
+    let previous: Vec<(i32, i32)> = previous
+        .unwrap_or_default()
+        .into_iter()
+        .map(|b| (b.year, b.month))
+        .collect();
+    if previous.iter().any(|(_, m)| !(1..=12).contains(m)) {
+        return Err(AppError::InvalidInput("Month must be between 1 and 12".to_string()));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, None)?;
+    db.get_category_deltas(library_id, &photo_ids, &previous)
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn search_photos(
+pub async fn get_days(
     state: State<'_, AppState>,
-    query: String,
-    limit: Option<i64>,
-) -> Result<Vec<crate::db::PhotoRecord>, String> {
-    // Security: limit query length to prevent abuse
-    if query.len() > 500 {
-        return Err("Search query too long (max 500 characters)".to_string());
+    year: i32,
+    month: i32,
+) -> Result<Vec<crate::db::CategoryDay>, AppError> {
+    if !(1..=12).contains(&month) {
+        return Err(AppError::InvalidInput("Month must be between 1 and 12".to_string()));
     }
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    let root_guard = state.library_root.lock().unwrap();
-    let root = root_guard.as_ref().ok_or("No library path")?;
-
-    let library_id = db.get_or_create_library(root).map_err(|e| e.to_string())?;
-    let limit = limit.unwrap_or(100).min(500); // cap at 500
-
-    db.search_photos(library_id, &query, limit)
-        .map_err(|e| e.to_string())
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, None)?;
+    db.get_days(library_id, year, month).map_err(AppError::from)
 }
 
+/// Representative photo for one folder (see `get_folder_covers`); None for an empty folder
 #[tauri::command]
-pub async fn get_thumbnail_path(source_path: String) -> Result<String, String> {
-    let path = thumb::get_or_create_thumbnail(&source_path).await?;
-    Ok(path.to_string_lossy().to_string())
+pub async fn get_folder_cover(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+    folder_rel: String,
+) -> Result<Option<crate::db::CoverPhoto>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    let covers = db.get_folder_covers(library_id, Some(&folder_rel))?;
+    Ok(covers.into_values().next())
 }
 
+/// Cover photo for every folder of a library, keyed by `folder_rel`: the most recent
+/// non-deleted photo, preferring one whose thumbnail already exists
 #[tauri::command]
-pub async fn get_index_progress() -> Result<Option<IndexProgress>, String> {
-    Ok(None)
+pub async fn get_folder_covers(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+) -> Result<std::collections::BTreeMap<String, crate::db::CoverPhoto>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    db.get_folder_covers(library_id, None).map_err(AppError::from)
 }
 
+/// Cover photo per year, and per month with `include_months`, for the timeline navigation.
+/// Favorites win, then photos with a thumbnail, then the most recent (there are no ratings).
 #[tauri::command]
-pub async fn get_current_library_path(state: State<'_, AppState>) -> Result<Option<String>, String> {
-    let root = state.library_root.lock().unwrap();
-    Ok(root.clone())
+pub async fn get_timeline_covers(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+    include_months: Option<bool>,
+) -> Result<crate::db::TimelineCovers, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    db.get_timeline_covers(library_id, include_months.unwrap_or(false))
+        .map_err(AppError::from)
 }
 
-pub fn setup_state(app: &tauri::AppHandle) {
-    app.manage(AppState {
-        db: Mutex::new(None),
-        library_root: Mutex::new(None),
-        library_roots: Mutex::new(Vec::new()),
-    });
+/// "Places visited": geotagged photos grouped on a lat/lon grid. `cell_degrees` sets the
+/// grid size (default 0.1° ≈ 11 km); zoomed-out maps can pass larger cells.
+#[tauri::command]
+pub async fn get_location_clusters(
+    state: State<'_, AppState>,
+    cell_degrees: Option<f64>,
+) -> Result<Vec<crate::db::LocationCluster>, AppError> {
+    let cell = cell_degrees.unwrap_or(0.1);
+    if !(cell > 0.0 && cell <= 180.0) {
+        return Err(AppError::InvalidInput("cell_degrees must be in (0, 180]".to_string()));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, None)?;
+    db.get_location_clusters(library_id, cell).map_err(AppError::from)
 }
 
-/// Restore session from persisted DB — no filesystem scanning, instant startup
+/// Photos whose GPS falls inside the map viewport (pan/zoom marker loading)
 #[tauri::command]
-pub async fn restore_session(
-    app: AppHandle,
+pub async fn get_photos_in_bounds(
     state: State<'_, AppState>,
-) -> Result<Vec<crate::db::LibraryInfo>, String> {
-    let db_path = db_path(&app);
-    if !db_path.exists() {
-        return Ok(Vec::new());
+    min_lat: f64,
+    min_lon: f64,
+    max_lat: f64,
+    max_lon: f64,
+    limit: Option<i64>,
+) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    if min_lat > max_lat {
+        return Err(AppError::InvalidInput("min_lat must not exceed max_lat".to_string()));
     }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, None)?;
+    let limit = limit.unwrap_or(500).min(2000);
+    db.get_photos_in_bounds(library_id, min_lat, min_lon, max_lat, max_lon, limit)
+        .map_err(AppError::from)
+}
 
-    let db = Database::new(&db_path).map_err(|e| e.to_string())?;
-    let libraries = db.get_all_libraries().map_err(|e| e.to_string())?;
+/// Photos without a real capture date (no date, the file mtime, or an estimate), by path,
+/// so they can be triaged and fixed
+#[tauri::command]
+pub async fn get_undated_photos(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    let limit = limit.unwrap_or(500).min(2000);
+    db.get_undated_photos(library_id, limit, offset.unwrap_or(0).max(0))
+        .map_err(AppError::from)
+}
 
-    if libraries.is_empty() {
-        return Ok(Vec::new());
+/// The library a request targets: `requested` when it names a known library, otherwise
+/// the active library
+fn resolve_library_id(db: &Database, state: &AppState, requested: Option<i64>) -> Result<i64, AppError> {
+    match requested {
+        Some(id) => {
+            let cached = lock(&state.library_ids).values().any(|&known| known == id);
+            if cached || db.library_exists(id)? {
+                Ok(id)
+            } else {
+                Err(AppError::NotFound(format!("Unknown library id {}", id)))
+            }
+        }
+        None => lock(&state.libraries).active.ok_or(AppError::NoLibrary),
     }
+}
 
-    // Populate library_roots from DB
-    let roots: Vec<(i64, String)> = libraries.iter().map(|l| (l.id, l.root_path.clone())).collect();
-    if let Some(first) = roots.first() {
-        *state.library_root.lock().unwrap() = Some(first.1.clone());
+/// Library id for `root`, creating the library on first use. Cached in `AppState`.
+fn library_id_for_root(db: &Database, state: &AppState, root: &str) -> Result<i64, AppError> {
+    if let Some(&id) = lock(&state.library_ids).get(root) {
+        return Ok(id);
     }
-    *state.library_roots.lock().unwrap() = roots;
-    *state.db.lock().unwrap() = Some(db);
-
-    Ok(libraries)
+    let id = match db.get_library_id(root)? {
+        Some(id) => id,
+        None => db.get_or_create_library(root)?,
+    };
+    lock(&state.library_ids).insert(root.to_string(), id);
+    Ok(id)
 }
 
-/// Auto-scan default user directories for photos
-#[tauri::command]
-pub async fn scan_default_directories(
-    app: AppHandle,
-    state: State<'_, AppState>,
-) -> Result<serde_json::Value, String> {
-    let home = std::env::var("HOME").map_err(|_| "Could not determine HOME directory".to_string())?;
-    let dirs_to_scan: Vec<(&str, String)> = vec![
-        ("Pictures", format!("{}/Pictures", home)),
-        ("Downloads", format!("{}/Downloads", home)),
-        ("Documents", format!("{}/Documents", home)),
-    ];
+#[derive(Deserialize)]
+pub struct GetPhotosParams {
+    /// Browse one specific library instead of the primary one
+    #[serde(rename = "libraryId")]
+    library_id: Option<i64>,
+    /// Browse every open library at once (ignores `libraryId`)
+    #[serde(rename = "allLibraries")]
+    all_libraries: Option<bool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    year: Option<i32>,
+    month: Option<i32>,
+    folder: Option<String>,
+    #[serde(rename = "mediaType")]
+    media_type: Option<String>,
+    category: Option<String>,
+    /// List both halves of RAW+JPEG pairs instead of the preferred one
+    #[serde(rename = "expandPairs")]
+    expand_pairs: Option<bool>,
+    /// "portrait", "landscape" or "square", by displayed (rotation-corrected) dimensions
+    orientation: Option<String>,
+    #[serde(rename = "minMegapixels")]
+    min_megapixels: Option<f64>,
+    iso: Option<crate::db::NumericRange>,
+    /// Exposure time bounds in seconds
+    #[serde(rename = "shutterSeconds")]
+    shutter_seconds: Option<crate::db::NumericRange>,
+    #[serde(rename = "fNumber")]
+    f_number: Option<crate::db::NumericRange>,
+    /// Quick filter: "high_iso" (ISO 3200+), "long_exposure" (1 s or longer) or
+    /// "wide_aperture" (f/2 or wider). Explicit ranges above take precedence.
+    #[serde(rename = "exposurePreset")]
+    exposure_preset: Option<String>,
+    /// "srgb", "display_p3", "adobe_rgb", "prophoto", "rec2020", "other", or "wide" for any
+    /// gamut wider than sRGB
+    #[serde(rename = "colorSpace")]
+    color_space: Option<String>,
+    hdr: Option<bool>,
+    /// true for only cloud placeholders, false to hide them
+    placeholder: Option<bool>,
+}
 
-    let db_path = db_path(&app);
-    if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+/// Ranges behind `GetPhotosParams::exposure_preset`, as (iso, shutter_seconds, f_number)
+fn exposure_preset(
+    name: &str,
+) -> Option<(Option<crate::db::NumericRange>, Option<crate::db::NumericRange>, Option<crate::db::NumericRange>)> {
+    use crate::db::NumericRange;
+    match name {
+        "high_iso" => Some((Some(NumericRange { min: Some(3200.0), max: None }), None, None)),
+        "long_exposure" => Some((None, Some(NumericRange { min: Some(1.0), max: None }), None)),
+        "wide_aperture" => Some((None, None, Some(NumericRange { min: None, max: Some(2.0) }))),
+        _ => None,
     }
-    let db = Database::new(&db_path).map_err(|e| e.to_string())?;
-
-    let mut results = Vec::new();
-    let mut all_library_roots: Vec<(i64, String)> = Vec::new();
+}
 
-    for (name, dir_path) in &dirs_to_scan {
-        let path = std::path::PathBuf::from(dir_path);
-        if !path.exists() || !path.is_dir() {
-            eprintln!("⏭ Skipping {}: directory not found", dir_path);
-            continue;
+impl GetPhotosParams {
+    fn filter(&self) -> Result<crate::db::PhotoFilter<'_>, AppError> {
+        if let Some(o) = self.orientation.as_deref() {
+            if !matches!(o, "portrait" | "landscape" | "square") {
+                return Err(AppError::InvalidInput(format!(
+                    "Unknown orientation '{}' (expected portrait, landscape or square)",
+                    o
+                )));
+            }
         }
-
-        let root_str = path.to_string_lossy().to_string();
-        let library_id = db.get_or_create_library(&root_str).map_err(|e| e.to_string())?;
-
-        app.emit("index-progress", IndexProgress {
-            phase: format!("scanning-{}", name.to_lowercase()),
-            current: 0,
-            total: None,
-        }).ok();
-
-        // Check if already indexed (has photos) — skip if so for speed
-        let existing_count = db.count_photos_for_library(library_id).unwrap_or(0);
-        if existing_count > 0 {
-            eprintln!("✓ {} already indexed ({} photos), skipping", name, existing_count);
-            all_library_roots.push((library_id, root_str.clone()));
-            results.push(serde_json::json!({
-                "name": name,
-                "path": root_str,
-                "libraryId": library_id,
-                "photoCount": existing_count,
-                "skipped": true
-            }));
-            continue;
+        if let Some(mp) = self.min_megapixels {
+            if !mp.is_finite() || mp < 0.0 {
+                return Err(AppError::InvalidInput(format!("Invalid minMegapixels {}", mp)));
+            }
         }
-
-        let (tx, mut rx) = mpsc::unbounded_channel::<(u64, u64)>();
-        let app_handle = app.clone();
-        let scan_name = name.to_string();
-        let recv_handle = tauri::async_runtime::spawn(async move {
-            while let Some((current, total)) = rx.recv().await {
-                app_handle
-                    .emit("index-progress", IndexProgress {
-                        phase: format!("indexing-{}", scan_name.to_lowercase()),
-                        current,
-                        total: Some(total),
-                    })
-                    .ok();
+        let (preset_iso, preset_shutter, preset_f) = match self.exposure_preset.as_deref() {
+            None => (None, None, None),
+            Some(name) => exposure_preset(name).ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "Unknown exposure preset '{}' (expected high_iso, long_exposure or wide_aperture)",
+                    name
+                ))
+            })?,
+        };
+        if let Some(space) = self.color_space.as_deref() {
+            let known = matches!(space, "wide" | "srgb" | "other")
+                || crate::scan::WIDE_GAMUT_SPACES.contains(&space);
+            if !known {
+                return Err(AppError::InvalidInput(format!("Unknown colorSpace '{}'", space)));
             }
-        });
-
-        let path_clone = path.clone();
-        let scanned = tauri::async_runtime::spawn_blocking(move || {
-            let root = path_clone.canonicalize().unwrap_or_else(|_| path_clone.clone());
-            let paths = scan::collect_media_paths(&path_clone);
-            let total = paths.len() as u64;
-            let _ = tx.send((0, total));
-            let mut all: Vec<scan::ScannedFile> = Vec::new();
-            const CHUNK: usize = 50;
-            for chunk in paths.chunks(CHUNK) {
-                let batch = scan::process_paths_batch(chunk, &root);
-                all.extend(batch);
-                let _ = tx.send((all.len() as u64, total));
+        }
+        for (name, range) in [("iso", self.iso), ("shutterSeconds", self.shutter_seconds), ("fNumber", self.f_number)] {
+            if range.is_some_and(|r| !r.is_valid()) {
+                return Err(AppError::InvalidInput(format!("Invalid {} range", name)));
             }
-            all
+        }
+        Ok(crate::db::PhotoFilter {
+            year: self.year,
+            month: self.month,
+            folder_rel: self.folder.as_deref(),
+            media_type: self.media_type.as_deref(),
+            category: self.category.as_deref(),
+            pair_preference: None,
+            shape: self.orientation.as_deref(),
+            min_megapixels: self.min_megapixels,
+            iso: self.iso.or(preset_iso),
+            shutter_seconds: self.shutter_seconds.or(preset_shutter),
+            f_number: self.f_number.or(preset_f),
+            color_space: self.color_space.as_deref(),
+            hdr: self.hdr,
+            placeholder: self.placeholder,
         })
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let _ = recv_handle.await;
+    }
+}
 
-        let photo_count = scanned.len();
-        // Use batch insert with transaction — ~50x faster
-        db.batch_insert_photos(library_id, &scanned).map_err(|e| e.to_string())?;
+#[tauri::command]
+pub async fn get_photos(
+    state: State<'_, AppState>,
+    params: Option<GetPhotosParams>,
+) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = if params.as_ref().and_then(|p| p.all_libraries).unwrap_or(false) {
+        lock(&state.libraries).ids()
+    } else {
+        vec![resolve_library_id(db, &state, params.as_ref().and_then(|p| p.library_id))?]
+    };
 
-        eprintln!("✓ Indexed {} ({} photos)", name, photo_count);
-        all_library_roots.push((library_id, root_str.clone()));
-        results.push(serde_json::json!({
-            "name": name,
-            "path": root_str,
-            "libraryId": library_id,
-            "photoCount": photo_count,
-            "skipped": false
-        }));
+    let limit = params.as_ref().and_then(|p| p.limit).unwrap_or(100);
+    let offset = params.as_ref().and_then(|p| p.offset).unwrap_or(0);
+    let mut filter = params.as_ref().map(|p| p.filter()).transpose()?.unwrap_or_default();
+    let preference = raw_preference(db);
+    if !params.as_ref().and_then(|p| p.expand_pairs).unwrap_or(false) {
+        filter.pair_preference = Some(&preference);
     }
 
-    app.emit("index-progress", IndexProgress {
-        phase: "done".to_string(),
-        current: 0,
-        total: Some(0),
-    }).ok();
+    let mut photos = db.get_photos(&library_ids, limit, offset, &filter)?;
+    mark_cached_thumbnails(db, &mut photos)?;
+    Ok(photos)
+}
 
-    // Store in state
-    *state.db.lock().unwrap() = Some(db);
-    *state.library_roots.lock().unwrap() = all_library_roots.clone();
-    // Also set first library as primary for backward compatibility
-    if let Some((_, ref path)) = all_library_roots.first() {
-        *state.library_root.lock().unwrap() = Some(path.clone());
+/// Set `thumbnail_exists` on a page of photos from the stored `thumb_path`, so listing
+/// never reads the source files. Placeholders have nothing on disk to thumbnail.
+fn mark_cached_thumbnails(db: &Database, photos: &mut [crate::db::PhotoRecord]) -> Result<(), AppError> {
+    let ids: Vec<i64> = photos.iter().filter(|p| !p.is_placeholder).map(|p| p.id).collect();
+    let thumb_paths = db.get_thumb_paths(&ids)?;
+    for photo in photos {
+        photo.thumbnail_exists = !photo.is_placeholder
+            && thumb_paths.get(&photo.id).is_some_and(|p| std::path::Path::new(p).exists());
     }
+    Ok(())
+}
 
-    Ok(serde_json::json!({ "sources": results }))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdjacentPhotos {
+    pub previous: Option<i64>,
+    pub next: Option<i64>,
 }
 
-/// Get photos from all indexed libraries
+/// The photos before and after `current_id` in the grid's order for the same `context`
+/// (the `get_photos` params, limit/offset ignored), for stepping through results in the
+/// viewer. `wrap` continues from the other end past the first or last photo.
 #[tauri::command]
-pub async fn get_all_photos(
+pub async fn get_adjacent_photos(
     state: State<'_, AppState>,
-    params: Option<GetPhotosParams>,
-) -> Result<Vec<crate::db::PhotoRecord>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    let roots = state.library_roots.lock().unwrap();
-    let library_ids: Vec<i64> = roots.iter().map(|(id, _)| *id).collect();
+    current_id: i64,
+    context: Option<GetPhotosParams>,
+    wrap: Option<bool>,
+) -> Result<AdjacentPhotos, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = if context.as_ref().and_then(|p| p.all_libraries).unwrap_or(false) {
+        lock(&state.libraries).ids()
+    } else {
+        vec![resolve_library_id(db, &state, context.as_ref().and_then(|p| p.library_id))?]
+    };
 
-    if library_ids.is_empty() {
-        return Ok(Vec::new());
+    let mut filter = context.as_ref().map(|p| p.filter()).transpose()?.unwrap_or_default();
+    let preference = raw_preference(db);
+    if !context.as_ref().and_then(|p| p.expand_pairs).unwrap_or(false) {
+        filter.pair_preference = Some(&preference);
     }
 
-    let limit = params.as_ref().and_then(|p| p.limit).unwrap_or(200).min(500);
-    let offset = params.as_ref().and_then(|p| p.offset).unwrap_or(0);
+    let (previous, next) = db.get_adjacent_photos(&library_ids, current_id, &filter, wrap.unwrap_or(false))?;
+    Ok(AdjacentPhotos { previous, next })
+}
 
-    db.get_photos_all_libraries(&library_ids, limit, offset)
-        .map_err(|e| e.to_string())
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlideshowItem {
+    pub id: i64,
+    pub path: String,
 }
 
-/// Get total photo count across all libraries (for pagination without loading all data)
+/// Full, stable slideshow order for the current filters so the UI can step forward/back
+/// without re-querying. A `seed` makes the shuffle reproducible.
 #[tauri::command]
-pub async fn get_photo_count(
+pub async fn get_slideshow(
     state: State<'_, AppState>,
-) -> Result<i64, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    let roots = state.library_roots.lock().unwrap();
-    let library_ids: Vec<i64> = roots.iter().map(|(id, _)| *id).collect();
-    db.count_all_photos(&library_ids).map_err(|e| e.to_string())
+    criteria: Option<GetPhotosParams>,
+    shuffle: bool,
+    seed: Option<u64>,
+    include_videos: Option<bool>,
+) -> Result<Vec<SlideshowItem>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, criteria.as_ref().and_then(|p| p.library_id))?;
+
+    let mut filter = criteria.as_ref().map(|p| p.filter()).transpose()?.unwrap_or_default();
+    let preference = raw_preference(db);
+    if !criteria.as_ref().and_then(|p| p.expand_pairs).unwrap_or(false) {
+        filter.pair_preference = Some(&preference);
+    }
+
+    let mut items: Vec<SlideshowItem> = db
+        .get_slideshow_items(library_id, &filter, include_videos.unwrap_or(false))?
+        .into_iter()
+        .map(|(id, path)| SlideshowItem { id, path })
+        .collect();
+
+    if shuffle {
+        let seed = seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0)
+        });
+        seeded_shuffle(&mut items, seed);
+    }
+    Ok(items)
+}
+
+/// Fisher–Yates driven by SplitMix64, so the same seed always yields the same order
+fn seeded_shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    for i in (1..items.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[tauri::command]
+pub async fn search_photos(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<i64>,
+    album_id: Option<i64>,
+    folder: Option<String>,
+) -> Result<Vec<crate::db::SearchResult>, AppError> {
+    // Security: limit query length to prevent abuse
+    if query.len() > 500 {
+        return Err(AppError::InvalidInput("Search query too long (max 500 characters)".to_string()));
+    }
+    // Search within the open album or folder (and its subfolders) instead of the library
+    let scope = match (album_id, folder) {
+        (Some(_), Some(_)) => {
+            return Err(AppError::InvalidInput("Scope a search to an album or a folder, not both".to_string()));
+        }
+        (Some(id), None) => Some(crate::db::SearchScope::Album(id)),
+        (None, Some(folder)) => {
+            let folder = folder.trim_end_matches(['/', '\\']);
+            // The library root is the whole library
+            (!folder.is_empty()).then(|| crate::db::SearchScope::Folder(folder.to_string()))
+        }
+        (None, None) => None,
+    };
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, None)?;
+    let limit = limit.unwrap_or(100).min(500); // cap at 500
+
+    let parsed = crate::search::SearchQuery::parse(&query);
+    db.search_photos(&[library_id], &parsed, scope.as_ref(), limit)
+        .map_err(AppError::from)
+}
+
+/// Search across every indexed library (Pictures, Downloads, Documents, ...) at once
+#[tauri::command]
+pub async fn search_all_photos(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<crate::db::SearchResult>, AppError> {
+    // Security: limit query length to prevent abuse
+    if query.len() > 500 {
+        return Err(AppError::InvalidInput("Search query too long (max 500 characters)".to_string()));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = lock(&state.libraries).ids();
+    let limit = limit.unwrap_or(100).min(500); // cap at 500
+
+    let parsed = crate::search::SearchQuery::parse(&query);
+    db.search_photos(&library_ids, &parsed, None, limit)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_thumbnail_path(state: State<'_, AppState>, source_path: String) -> Result<String, AppError> {
+    let path = thumb::get_or_create_thumbnail(&source_path).await?;
+    let path = path.to_string_lossy().to_string();
+    remember_thumb_path(&state, &source_path, &path);
+    Ok(path)
+}
+
+/// Record a generated thumbnail on its photo row so listings can report `thumbnailExists`
+/// without re-keying the source. Best effort: the thumbnail is usable either way.
+fn remember_thumb_path(state: &AppState, source_path: &str, thumb_path: &str) {
+    let Ok(db_guard) = ensure_db(state) else { return };
+    if let Some(db) = db_guard.as_ref() {
+        if let Err(e) = db.set_thumb_paths(&[(source_path.to_string(), thumb_path.to_string())]) {
+            eprintln!("  ⚠ Failed to record thumbnail for {}: {}", source_path, e);
+        }
+    }
+}
+
+/// Whether the grid thumbnail is already cached; the UI shows the blurhash until it is
+#[tauri::command]
+pub async fn thumbnail_exists(source_path: String) -> Result<bool, AppError> {
+    Ok(thumb::thumbnail_exists(&source_path))
+}
+
+/// Grid thumbnail for a photo with `isAnimated`: a looping GIF when animated thumbnails
+/// are enabled, else the still thumbnail
+#[tauri::command]
+pub async fn get_animated_thumbnail(source_path: String) -> Result<String, AppError> {
+    let path = thumb::get_or_create_animated_thumbnail(&source_path).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Thumbnails are ~240px JPEGs; anything bigger than this is not a thumbnail we wrote
+const MAX_THUMB_DATA_URL_BYTES: u64 = 512 * 1024;
+
+/// The grid thumbnail as a `data:image/jpeg;base64,...` URL, for webviews that can't load
+/// filesystem paths through the asset protocol
+#[tauri::command]
+pub async fn get_thumbnail_data_url(state: State<'_, AppState>, source_path: String) -> Result<String, AppError> {
+    use base64::Engine;
+    let path = thumb::get_or_create_thumbnail(&source_path).await?;
+    remember_thumb_path(&state, &source_path, &path.to_string_lossy());
+    let size = std::fs::metadata(&path)?.len();
+    if size > MAX_THUMB_DATA_URL_BYTES {
+        return Err(AppError::InvalidInput(format!("Thumbnail too large to inline ({} bytes)", size)));
+    }
+    let bytes = std::fs::read(&path)?;
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// One side of a `get_comparison`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonSide {
+    pub photo: crate::db::PhotoRecord,
+    /// None for videos or when the preview couldn't be rendered
+    pub preview: Option<thumb::PreviewInfo>,
+    /// Variance of the Laplacian of the preview; higher is sharper. Both sides are measured
+    /// at the same preview size so the scores are comparable.
+    pub sharpness: Option<f64>,
+}
+
+/// A metadata field as shown in the compare view
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub a: Option<String>,
+    pub b: Option<String>,
+    pub differs: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+    pub a: ComparisonSide,
+    pub b: ComparisonSide,
+    /// Exposure and optics fields side by side
+    pub diff: Vec<FieldDiff>,
+    /// Id of the sharper photo; None when either score is unavailable
+    pub sharper_id: Option<i64>,
+}
+
+async fn comparison_side(photo: crate::db::PhotoRecord) -> ComparisonSide {
+    if photo.media_type != "photo" {
+        return ComparisonSide { photo, preview: None, sharpness: None };
+    }
+    let preview = thumb::get_or_create_preview(&photo.path, thumb::DEFAULT_PREVIEW_SIZE, photo.orientation)
+        .await
+        .ok();
+    let sharpness = match &preview {
+        Some(p) => {
+            let path = std::path::PathBuf::from(&p.path);
+            tauri::async_runtime::spawn_blocking(move || crate::burst::compute_signature(&path).map(|(_, s)| s))
+                .await
+                .ok()
+                .flatten()
+        }
+        None => None,
+    };
+    ComparisonSide { photo, preview, sharpness }
+}
+
+/// Two photos side by side for A/B culling: full records, upright previews, a diff of
+/// exposure fields and sharpness scores
+#[tauri::command]
+pub async fn get_comparison(
+    state: State<'_, AppState>,
+    photo_id_a: i64,
+    photo_id_b: i64,
+) -> Result<Comparison, AppError> {
+    let (photo_a, photo_b) = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        let fetch = |id: i64| -> Result<crate::db::PhotoRecord, AppError> {
+            db.get_photo_by_id(id)?
+                .ok_or_else(|| AppError::NotFound(format!("Photo {} not found", id)))
+        };
+        (fetch(photo_id_a)?, fetch(photo_id_b)?)
+    };
+
+    let fields = |p: &crate::db::PhotoRecord| -> [(&'static str, Option<String>); 7] {
+        [
+            ("shutterSpeed", p.shutter_speed.clone()),
+            ("iso", p.iso.map(|v| v.to_string())),
+            ("aperture", p.aperture.clone()),
+            ("focalLength", p.focal_length.clone()),
+            ("exposureBias", p.exposure_bias.map(|v| format!("{:+.1} EV", v))),
+            ("lens", p.lens.clone()),
+            ("cameraModel", p.camera_model.clone()),
+        ]
+    };
+    let diff = fields(&photo_a)
+        .into_iter()
+        .zip(fields(&photo_b))
+        .map(|((field, a), (_, b))| FieldDiff { field, differs: a != b, a, b })
+        .collect();
+
+    let a = comparison_side(photo_a).await;
+    let b = comparison_side(photo_b).await;
+    let sharper_id = match (a.sharpness, b.sharpness) {
+        (Some(sa), Some(sb)) => Some(if sa >= sb { a.photo.id } else { b.photo.id }),
+        _ => None,
+    };
+    Ok(Comparison { a, b, diff, sharper_id })
+}
+
+/// A medium-resolution, upright JPEG of a photo for the detail viewer (long edge
+/// `max_dimension`, default 2048), cached per size. The original is only needed on zoom.
+#[tauri::command]
+pub async fn get_preview(
+    state: State<'_, AppState>,
+    photo_id: i64,
+    max_dimension: Option<u32>,
+) -> Result<thumb::PreviewInfo, AppError> {
+    let max_dimension = max_dimension.unwrap_or(thumb::DEFAULT_PREVIEW_SIZE);
+    if !(256..=8192).contains(&max_dimension) {
+        return Err(AppError::InvalidInput("max_dimension must be between 256 and 8192".to_string()));
+    }
+    let photo = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.get_photo_by_id(photo_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Photo {} not found", photo_id)))?
+    };
+    if photo.media_type != "photo" {
+        return Err(AppError::InvalidInput("Previews are only available for photos".to_string()));
+    }
+    Ok(thumb::get_or_create_preview(&photo.path, max_dimension, photo.orientation).await?)
+}
+
+#[tauri::command]
+pub async fn get_thumbnail_cache_size() -> Result<thumb::CacheStats, AppError> {
+    tauri::async_runtime::spawn_blocking(thumb::thumbnail_cache_size)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(AppError::from)
+}
+
+/// Delete every cached thumbnail; returns what was removed
+#[tauri::command]
+pub async fn clear_thumbnail_cache() -> Result<thumb::CacheStats, AppError> {
+    let removed = tauri::async_runtime::spawn_blocking(thumb::clear_thumbnail_cache)
+        .await
+        .map_err(|e| e.to_string())??;
+    eprintln!("✓ Cleared thumbnail cache: {} files, {} bytes", removed.files, removed.bytes);
+    Ok(removed)
+}
+
+/// Rename thumbnails cached under the old path-based keys to content keys, so existing
+/// caches keep working (and survive renames) without regenerating. Returns how many moved.
+#[tauri::command]
+pub async fn migrate_thumbnail_cache(state: State<'_, AppState>) -> Result<usize, AppError> {
+    let paths = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.get_all_photo_paths()?
+    };
+    let updates: Vec<(String, String)> = tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter_map(|p| match thumb::migrate_legacy_thumbnail(&p) {
+                Ok(Some(new_thumb)) => Some((p, new_thumb.to_string_lossy().to_string())),
+                Ok(None) => None,
+                Err(e) => {
+                    eprintln!("  ⚠ Thumbnail migration failed for {}: {}", p, e);
+                    None
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.set_thumb_paths(&updates)?;
+    eprintln!("✓ Migrated {} thumbnails to content keys", updates.len());
+    Ok(updates.len())
+}
+
+/// Evict least-recently-used thumbnails until the cache is under `max_bytes`
+#[tauri::command]
+pub async fn prune_thumbnail_cache(max_bytes: u64) -> Result<thumb::CacheStats, AppError> {
+    let removed = tauri::async_runtime::spawn_blocking(move || thumb::prune_thumbnail_cache(max_bytes))
+        .await
+        .map_err(|e| e.to_string())??;
+    eprintln!("✓ Pruned thumbnail cache: {} files, {} bytes", removed.files, removed.bytes);
+    Ok(removed)
+}
+
+/// Latest progress snapshot (None if no scan has run this session)
+#[tauri::command]
+pub async fn get_index_progress(state: State<'_, AppState>) -> Result<Option<IndexProgress>, AppError> {
+    Ok(lock(&state.index_progress).clone())
+}
+
+#[tauri::command]
+pub async fn get_current_library_path(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    Ok(lock(&state.libraries).active_root().map(str::to_string))
+}
+
+pub fn setup_state(app: &tauri::AppHandle) {
+    app.manage(AppState {
+        db_path: Mutex::new(configured_db_path(app)),
+        db: Mutex::new(None),
+        libraries: Mutex::new(OpenLibraries::default()),
+        library_ids: Mutex::new(std::collections::HashMap::new()),
+        index_progress: Mutex::new(None),
+        undo_log: Mutex::new(UndoLog::default()),
+        active_scans: AtomicUsize::new(0),
+        index_turn: std::sync::Arc::new(tokio::sync::Mutex::new(())),
+        reparse_cancel: AtomicBool::new(false),
+    });
+}
+
+/// Lock the database, opening the persisted catalog first if nothing has loaded it this
+/// session (e.g. a command arriving before `restore_session`). The guard is still `None`
+/// when no catalog exists yet on disk.
+fn ensure_db(state: &AppState) -> Result<MutexGuard<'_, Option<Database>>, AppError> {
+    let mut db_guard = lock(&state.db);
+    let db_path = lock(&state.db_path).clone();
+    if db_guard.is_none() && db_path.exists() {
+        let db = Database::new(&db_path)?;
+        apply_scan_settings(&db);
+        let libraries = db.get_all_libraries()?;
+        let mut open = lock(&state.libraries);
+        if open.entries.is_empty() {
+            open.replace(libraries.iter().map(|l| (l.id, l.root_path.clone())).collect());
+        }
+        eprintln!("✓ Opened catalog on demand ({} libraries)", libraries.len());
+        *db_guard = Some(db);
+    }
+    Ok(db_guard)
+}
+
+/// Restore session from persisted DB — no filesystem scanning, instant startup
+#[tauri::command]
+pub async fn restore_session(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::db::LibraryInfo>, AppError> {
+    let db_path = db_path(&app);
+    if !db_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let db = Database::new(&db_path)?;
+    apply_scan_settings(&db);
+    let libraries = db.get_all_libraries()?;
+
+    if libraries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    lock(&state.libraries).replace(libraries.iter().map(|l| (l.id, l.root_path.clone())).collect());
+    *lock(&state.db) = Some(db);
+
+    Ok(libraries)
+}
+
+/// The OS's Pictures, Downloads and Documents folders, as (display name, path). Each is
+/// resolved independently through Tauri's path API, so one the platform doesn't define is
+/// simply left out.
+fn standard_scan_dirs(app: &AppHandle) -> Vec<(String, std::path::PathBuf)> {
+    let paths = app.path();
+    [
+        ("Pictures", paths.picture_dir()),
+        ("Downloads", paths.download_dir()),
+        ("Documents", paths.document_dir()),
+    ]
+    .into_iter()
+    .filter_map(|(name, dir)| dir.ok().map(|d| (name.to_string(), d)))
+    .collect()
+}
+
+/// Directories `scan_default_directories` indexes: the user's configured list if set,
+/// otherwise the standard folders
+fn default_scan_dirs(app: &AppHandle, db: &Database) -> Vec<(String, std::path::PathBuf)> {
+    match db.get_setting_json::<Vec<String>>(SETTING_DEFAULT_SCAN_DIRS) {
+        Some(dirs) if !dirs.is_empty() => dirs
+            .into_iter()
+            .map(|d| {
+                let path = std::path::PathBuf::from(&d);
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or(d);
+                (name, path)
+            })
+            .collect(),
+        _ => standard_scan_dirs(app),
+    }
+}
+
+/// Index one `scan_default_directories` source (or reuse it if already indexed).
+/// Returns its library id, metrics when it was freshly scanned, and its result entry.
+async fn index_default_dir(
+    app: &AppHandle,
+    db: &Database,
+    name: &str,
+    path: &std::path::Path,
+) -> Result<(i64, Option<ScanMetrics>, serde_json::Value), AppError> {
+    let root_str = path.to_string_lossy().to_string();
+    let library_id = library_id_for_root(db, &app.state::<AppState>(), &root_str)?;
+
+    emit_progress(app, IndexProgress {
+        phase: format!("scanning-{}", name.to_lowercase()),
+        current: 0,
+        total: None,
+        ..Default::default()
+    });
+
+    // Check if already indexed (has photos) — skip if so for speed
+    let existing_count = db.count_photos_for_library(library_id).unwrap_or(0);
+    if existing_count > 0 {
+        eprintln!("✓ {} already indexed ({} photos), skipping", name, existing_count);
+        return Ok((library_id, None, serde_json::json!({
+            "name": name,
+            "path": root_str,
+            "libraryId": library_id,
+            "photoCount": existing_count,
+            "skipped": true
+        })));
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<ScanTick>();
+    let app_handle = app.clone();
+    let scan_name = name.to_string();
+    let recv_handle = tauri::async_runtime::spawn(async move {
+        while let Some(tick) = rx.recv().await {
+            emit_progress(&app_handle, IndexProgress::from_tick(
+                format!("indexing-{}", scan_name.to_lowercase()),
+                tick,
+            ));
+        }
+    });
+
+    let scan_opts = library_scan_options(db, library_id);
+    let path_clone = path.to_path_buf();
+    let started = std::time::Instant::now();
+    let IndexRun { scanned, bytes, skipped_paths, timed_out } = tauri::async_runtime::spawn_blocking(move || {
+        index_paths_blocking(&path_clone, &scan_opts, tx)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let _ = recv_handle.await;
+
+    let photo_count = scanned.len();
+    // Use batch insert with transaction — ~50x faster
+    db.insert_photos_batch(library_id, &scanned)?;
+    db.clear_scan_timeouts(library_id, None)?;
+    db.record_scan_timeouts(library_id, &timed_out)?;
+    let metrics = ScanMetrics::new(photo_count as u64, bytes, started.elapsed());
+
+    eprintln!(
+        "✓ Indexed {} ({} photos in {} ms, {:.1} files/s, {:.1} MB/s)",
+        name, photo_count, metrics.duration_ms, metrics.files_per_sec, metrics.mb_per_sec
+    );
+    Ok((library_id, Some(metrics), serde_json::json!({
+        "name": name,
+        "path": root_str,
+        "libraryId": library_id,
+        "photoCount": photo_count,
+        "skipped": false,
+        "metrics": metrics,
+        "skippedPaths": skipped_paths,
+        "timedOutPaths": timed_out
+    })))
+}
+
+/// Index each of `dirs` with `index`, in order. A folder that doesn't exist is reported as
+/// missing and one that fails is reported with its error; neither stops the others.
+/// Returns the per-source results, the libraries indexed and their metrics.
+async fn index_each_dir<F, Fut>(
+    dirs: &[(String, std::path::PathBuf)],
+    mut index: F,
+) -> (Vec<serde_json::Value>, Vec<(i64, String)>, Vec<ScanMetrics>)
+where
+    F: FnMut(String, std::path::PathBuf) -> Fut,
+    Fut: std::future::Future<Output = Result<(i64, Option<ScanMetrics>, serde_json::Value), AppError>>,
+{
+    let mut results = Vec::new();
+    let mut all_library_roots: Vec<(i64, String)> = Vec::new();
+    let mut all_metrics: Vec<ScanMetrics> = Vec::new();
+
+    for (name, path) in dirs {
+        if !path.is_dir() {
+            eprintln!("⏭ Skipping {}: directory not found", path.display());
+            results.push(serde_json::json!({
+                "name": name,
+                "path": path.to_string_lossy(),
+                "missing": true
+            }));
+            continue;
+        }
+
+        match index(name.clone(), path.clone()).await {
+            Ok((library_id, metrics, entry)) => {
+                all_library_roots.push((library_id, path.to_string_lossy().to_string()));
+                all_metrics.extend(metrics);
+                results.push(entry);
+            }
+            // One unreadable folder shouldn't cost the user the others
+            Err(e) => {
+                eprintln!("✗ Failed to index {}: {}", path.display(), e);
+                results.push(serde_json::json!({
+                    "name": name,
+                    "path": path.to_string_lossy(),
+                    "error": e.to_string()
+                }));
+            }
+        }
+    }
+
+    (results, all_library_roots, all_metrics)
+}
+
+/// Auto-scan default user directories for photos (see `set_default_scan_dirs`).
+/// Folders that don't exist are reported with `missing: true` and ones that fail to index
+/// with an `error`; neither stops the remaining folders from being scanned.
+#[tauri::command]
+pub async fn scan_default_directories(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, AppError> {
+    let _scan = ScanGuard::begin(&app).await;
+    let db = open_database(&app)?;
+    let dirs_to_scan = default_scan_dirs(&app, &db);
+
+    let (app_ref, db_ref) = (&app, &db);
+    let (results, all_library_roots, all_metrics) = index_each_dir(&dirs_to_scan, |name, path| async move {
+        index_default_dir(app_ref, db_ref, &name, &path).await
+    })
+    .await;
+
+    emit_progress(&app, IndexProgress {
+        phase: "done".to_string(),
+        current: 0,
+        total: Some(0),
+        ..Default::default()
+    });
+
+    // Store in state, alongside libraries opened by other runs
+    lock(&state.db).get_or_insert(db);
+    lock(&state.libraries).merge(all_library_roots);
+
+    let metrics = ScanMetrics::sum(&all_metrics);
+    let summary = serde_json::json!({
+        "sources": results,
+        "durationMs": metrics.duration_ms,
+        "metrics": metrics
+    });
+    emit_index_complete(&app, &summary);
+    Ok(summary)
+}
+
+/// Get photos from all indexed libraries
+#[tauri::command]
+pub async fn get_all_photos(
+    state: State<'_, AppState>,
+    params: Option<GetPhotosParams>,
+) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = lock(&state.libraries).ids();
+
+    if library_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let limit = params.as_ref().and_then(|p| p.limit).unwrap_or(200).min(500);
+    let offset = params.as_ref().and_then(|p| p.offset).unwrap_or(0);
+
+    let mut photos = db.get_photos_all_libraries(&library_ids, limit, offset)?;
+    mark_cached_thumbnails(db, &mut photos)?;
+    Ok(photos)
+}
+
+/// Get total photo count across all libraries (for pagination without loading all data)
+#[tauri::command]
+pub async fn get_photo_count(
+    state: State<'_, AppState>,
+) -> Result<i64, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = lock(&state.libraries).ids();
+    db.count_all_photos(&library_ids).map_err(AppError::from)
+}
+
+/// All sidebar badge counts across indexed libraries in one round trip
+#[tauri::command]
+pub async fn get_library_stats(
+    state: State<'_, AppState>,
+) -> Result<crate::db::LibraryStats, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = lock(&state.libraries).ids();
+    db.get_library_stats(&library_ids).map_err(AppError::from)
+}
+
+/// The `get_library_stats` counts (photos, videos, favorites, trash, GPS) for one library,
+/// defaulting to the active one, for per-source stats when several are open
+#[tauri::command]
+pub async fn get_library_breakdown(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+) -> Result<crate::db::LibraryStats, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    db.get_library_stats(&[library_id]).map_err(AppError::from)
+}
+
+/// Get list of all indexed libraries/sources
+#[tauri::command]
+pub async fn get_libraries(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::db::LibraryInfo>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.get_all_libraries().map_err(AppError::from)
+}
+
+/// Index another folder alongside the open libraries. It shows up in the all-libraries
+/// views; the active library stays as it was (unless none was open yet).
+#[tauri::command]
+pub async fn add_library_path(app: AppHandle, path: String) -> Result<serde_json::Value, AppError> {
+    index_library(app, path, None, None, None, None, false).await
+}
+
+/// Outcome of re-indexing one folder subtree
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReindexResult {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Rows whose file is gone, flagged missing (see `purge_missing_photos`)
+    pub removed: usize,
+    /// Files skipped because parsing overran the per-file timeout (see `retry_scan_timeouts`)
+    pub timed_out: usize,
+}
+
+/// Rescan a single folder subtree of a library (defaults to the current one). Files whose
+/// size and mtime match the index are skipped; new and modified files are parsed together
+/// with their Live Photo / RAW+JPEG partners so pairing survives; rows for files no longer
+/// on disk are flagged missing. Photos outside the folder are untouched. Progress events carry `folder`.
+#[tauri::command]
+pub async fn reindex_folder(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+    folder_rel: String,
+) -> Result<ReindexResult, AppError> {
+    let folder_rel = folder_rel.trim_end_matches(['/', '\\']).to_string();
+    let _scan = ScanGuard::begin(&app).await;
+    let (library_id, root, existing, opts) = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        let library_id = resolve_library_id(db, &state, library_id)?;
+        let root = db
+            .get_all_libraries()?
+            .into_iter()
+            .find(|l| l.id == library_id)
+            .map(|l| l.root_path)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown library id {}", library_id)))?;
+        let opts = library_scan_options(db, library_id);
+        // Flattened libraries store only the leaf folder name, which can't be mapped back
+        // to a single subtree
+        if opts.flatten_folders && !folder_rel.is_empty() {
+            return Err(AppError::InvalidInput(
+                "Library stores flattened folder names; rescan the whole library instead".to_string(),
+            ));
+        }
+        let existing = db.get_folder_index_state(library_id, &folder_rel)?;
+        (library_id, root, existing, opts)
+    };
+
+    let root = std::path::Path::new(&root)
+        .canonicalize()
+        .map_err(|e| AppError::NotFound(format!("Library root unavailable: {}", e)))?;
+    let folder = root
+        .join(&folder_rel)
+        .canonicalize()
+        .map_err(|_| AppError::NotFound(format!("Folder not found: {}", folder_rel)))?;
+    if !folder.starts_with(&root) || !folder.is_dir() {
+        return Err(AppError::InvalidInput(format!("Not a folder inside the library: {}", folder_rel)));
+    }
+
+    emit_progress(&app, IndexProgress {
+        phase: "scanning".to_string(),
+        folder: Some(folder_rel.clone()),
+        ..Default::default()
+    });
+
+    let (changed, mut result, gone) = {
+        let folder = folder.clone();
+        let opts = opts.clone();
+        tauri::async_runtime::spawn_blocking(move || diff_folder(&folder, &opts, &existing))
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<ScanTick>();
+    let app_handle = app.clone();
+    let progress_folder = folder_rel.clone();
+    let recv_handle = tauri::async_runtime::spawn(async move {
+        while let Some(tick) = rx.recv().await {
+            let mut progress = IndexProgress::from_tick("indexing".to_string(), tick);
+            progress.folder = Some(progress_folder.clone());
+            emit_progress(&app_handle, progress);
+        }
+    });
+    let root_clone = root.clone();
+    let (scanned, timed_out) =
+        tauri::async_runtime::spawn_blocking(move || parse_files_blocking(&changed, &root_clone, &opts, tx))
+            .await
+            .map_err(|e| e.to_string())?;
+    let _ = recv_handle.await;
+
+    {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.insert_photos_batch(library_id, &scanned)?;
+        db.set_missing(&gone, true)?;
+        let parsed: Vec<String> = scanned.iter().map(|f| f.path.clone()).collect();
+        db.clear_scan_timeouts(library_id, Some(&parsed))?;
+        db.record_scan_timeouts(library_id, &timed_out)?;
+    }
+    result.removed = gone.len();
+    result.timed_out = timed_out.len();
+
+    emit_progress(&app, IndexProgress {
+        phase: "done".to_string(),
+        current: scanned.len() as u64,
+        total: Some(scanned.len() as u64),
+        folder: Some(folder_rel.clone()),
+        ..Default::default()
+    });
+    eprintln!(
+        "✓ Re-indexed {:?} in library {}: {} added, {} updated, {} unchanged, {} removed",
+        folder_rel, library_id, result.added, result.updated, result.unchanged, result.removed
+    );
+    Ok(result)
+}
+
+/// Compare a folder on disk with its index rows. Returns the files to (re)parse, the counts
+/// so far, and the ids of rows whose files are gone. Files the walk skipped (ignore patterns,
+/// placeholders, extensions) but that still exist are not counted as gone.
+fn diff_folder(
+    folder: &std::path::Path,
+    opts: &scan::ScanOptions,
+    existing: &std::collections::HashMap<String, crate::db::IndexedFile>,
+) -> (Vec<(std::path::PathBuf, u64)>, ReindexResult, Vec<i64>) {
+    // Pairs are matched on (directory, lowercase stem), so a changed file drags its partner along
+    let pair_key = |p: &std::path::Path| -> Option<(std::path::PathBuf, String)> {
+        Some((p.parent()?.to_path_buf(), p.file_stem()?.to_str()?.to_lowercase()))
+    };
+    let motion_paths: std::collections::HashSet<&str> =
+        existing.values().filter_map(|f| f.motion_path.as_deref()).collect();
+
+    let files = scan::collect_media_files(folder, opts);
+    let mut seen: std::collections::HashSet<&str> = std::collections::HashSet::new();
+    let mut dirty_keys = std::collections::HashSet::new();
+    for (path, size) in &files {
+        let path_str = path.to_string_lossy();
+        let unchanged = match existing.get_key_value(path_str.as_ref()) {
+            Some((key, indexed)) => {
+                seen.insert(key.as_str());
+                // A placeholder that has since been downloaded may keep its size and mtime
+                indexed.size_bytes as u64 == *size
+                    && indexed.modified_at == scan::modified_time_string(path)
+                    && !(indexed.is_placeholder && !scan::is_placeholder(path))
+            }
+            None => motion_paths.contains(path_str.as_ref()),
+        };
+        if !unchanged {
+            dirty_keys.extend(pair_key(path));
+        }
+    }
+
+    let mut result = ReindexResult::default();
+    let mut changed = Vec::new();
+    for (path, size) in files {
+        let path_str = path.to_string_lossy();
+        let known = existing.contains_key(path_str.as_ref()) || motion_paths.contains(path_str.as_ref());
+        if pair_key(&path).is_some_and(|k| dirty_keys.contains(&k)) {
+            if known {
+                result.updated += 1;
+            } else {
+                result.added += 1;
+            }
+            changed.push((path, size));
+        } else {
+            result.unchanged += 1;
+        }
+    }
+
+    let gone = existing
+        .iter()
+        .filter(|(path, _)| !seen.contains(path.as_str()) && !std::path::Path::new(path).exists())
+        .map(|(_, indexed)| indexed.id)
+        .collect();
+    (changed, result, gone)
+}
+
+/// Files of a library (defaults to the current one) skipped because parsing them timed out
+#[tauri::command]
+pub async fn get_scan_timeouts(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+) -> Result<Vec<String>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    db.get_scan_timeouts(library_id).map_err(AppError::from)
+}
+
+/// Outcome of retrying timed-out files
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeoutRetryResult {
+    pub indexed: usize,
+    /// No longer on disk; dropped from the retry list
+    pub missing: usize,
+    pub still_timed_out: Vec<String>,
+}
+
+/// Parse the files that timed out in earlier scans again (e.g. once the NAS responds).
+/// Successes are indexed and forgotten; files that time out again stay on the list.
+#[tauri::command]
+pub async fn retry_scan_timeouts(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+) -> Result<TimeoutRetryResult, AppError> {
+    let _scan = ScanGuard::begin(&app).await;
+    let (library_id, root, pending, opts) = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        let library_id = resolve_library_id(db, &state, library_id)?;
+        let root = db
+            .get_all_libraries()?
+            .into_iter()
+            .find(|l| l.id == library_id)
+            .map(|l| l.root_path)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown library id {}", library_id)))?;
+        let opts = library_scan_options(db, library_id);
+        (library_id, root, db.get_scan_timeouts(library_id)?, opts)
+    };
+
+    let mut result = TimeoutRetryResult::default();
+    let mut files: Vec<(std::path::PathBuf, u64)> = Vec::new();
+    let mut missing: Vec<String> = Vec::new();
+    for path in pending {
+        match std::fs::metadata(&path) {
+            Ok(meta) => files.push((std::path::PathBuf::from(path), meta.len())),
+            Err(_) => missing.push(path),
+        }
+    }
+    result.missing = missing.len();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<ScanTick>();
+    let app_handle = app.clone();
+    let recv_handle = tauri::async_runtime::spawn(async move {
+        while let Some(tick) = rx.recv().await {
+            emit_progress(&app_handle, IndexProgress::from_tick("indexing".to_string(), tick));
+        }
+    });
+    let root = std::path::PathBuf::from(root);
+    let (scanned, timed_out) = tauri::async_runtime::spawn_blocking(move || {
+        parse_files_blocking(&files, &root, &opts, tx)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let _ = recv_handle.await;
+
+    {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.insert_photos_batch(library_id, &scanned)?;
+        db.clear_scan_timeouts(library_id, None)?;
+        db.record_scan_timeouts(library_id, &timed_out)?;
+    }
+    emit_progress(&app, IndexProgress {
+        phase: "done".to_string(),
+        current: scanned.len() as u64,
+        total: Some(scanned.len() as u64),
+        ..Default::default()
+    });
+
+    result.indexed = scanned.len();
+    result.still_timed_out = timed_out;
+    eprintln!(
+        "✓ Retried timed-out files in library {}: {} indexed, {} missing, {} timed out again",
+        library_id, result.indexed, result.missing, result.still_timed_out.len()
+    );
+    Ok(result)
+}
+
+/// Outcome of checking a library's rows against disk
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileResult {
+    pub checked: usize,
+    /// Photos currently missing from disk (newly or still)
+    pub missing: usize,
+    /// Previously missing photos whose file is back
+    pub found: usize,
+}
+
+/// Flag photos whose file no longer exists (removed outside the app) as `is_missing`, and
+/// clear the flag on any that reappeared. Nothing is deleted; see `purge_missing_photos`.
+#[tauri::command]
+pub async fn reconcile_library(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+) -> Result<ReconcileResult, AppError> {
+    let (library_id, rows) = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        let library_id = resolve_library_id(db, &state, library_id)?;
+        (library_id, db.get_photo_paths(library_id)?)
+    };
+
+    // Existence checks can be slow on network drives; run them in parallel off the async runtime
+    let checked = rows.len();
+    let (now_missing, now_found): (Vec<i64>, Vec<i64>) = tauri::async_runtime::spawn_blocking(move || {
+        use rayon::prelude::*;
+        let changes: Vec<(i64, bool)> = scan::scan_pool().install(|| {
+            rows.par_iter()
+                .filter_map(|(id, path, was_missing)| {
+                    let missing = !std::path::Path::new(path).exists();
+                    (missing != *was_missing).then_some((*id, missing))
+                })
+                .collect()
+        });
+        let (missing, found): (Vec<_>, Vec<_>) = changes.into_iter().partition(|(_, missing)| *missing);
+        (
+            missing.into_iter().map(|(id, _)| id).collect(),
+            found.into_iter().map(|(id, _)| id).collect(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.set_missing(&now_missing, true)?;
+    db.set_missing(&now_found, false)?;
+    let result = ReconcileResult {
+        checked,
+        missing: db.get_missing_photos(library_id)?.len(),
+        found: now_found.len(),
+    };
+    eprintln!(
+        "✓ Reconciled library {}: {} checked, {} missing ({} new), {} found again",
+        library_id, result.checked, result.missing, now_missing.len(), result.found
+    );
+    Ok(result)
+}
+
+/// Drop the rows of photos flagged missing (with their tags, album links and cached
+/// thumbnails). Each file is checked again first, so one that came back since the reconcile
+/// is kept.
+/// Returns the ids removed.
+#[tauri::command]
+pub async fn purge_missing_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+) -> Result<Vec<i64>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    let (gone, back): (Vec<(i64, String)>, Vec<(i64, String)>) = db
+        .get_missing_photos(library_id)?
+        .into_iter()
+        .partition(|(_, path)| !std::path::Path::new(path).exists());
+    let gone: Vec<i64> = gone.into_iter().map(|(id, _)| id).collect();
+    let back: Vec<i64> = back.into_iter().map(|(id, _)| id).collect();
+    db.set_missing(&back, false)?;
+    let thumbs: Vec<String> = db.get_thumb_paths(&gone)?.into_values().collect();
+    let paths = db.hard_delete_photos(&gone)?;
+    drop(db_guard);
+    remove_cached_renditions(&paths, &thumbs);
+
+    if !gone.is_empty() {
+        emit_photos_removed(&app, &gone, true);
+    }
+    eprintln!("✓ Purged {} missing photos from library {}", gone.len(), library_id);
+    Ok(gone)
+}
+
+#[tauri::command]
+pub async fn remove_library_path(state: State<'_, AppState>, path: String) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    
+    // Simple string match for now, could be more robust with canonicalization
+    if let Some(library_id) = db.get_library_id(&path)? {
+        db.remove_library(library_id)?;
+        lock(&state.libraries).close(library_id);
+        lock(&state.library_ids).retain(|_, id| *id != library_id);
+    }
+    
+    Ok(())
+}
+
+/// Point a library at its folder's new location (moved, or a drive mounted elsewhere) without
+/// reindexing, so ids, tags and albums survive. Thumbnails are keyed by file content and carry
+/// over; the few keyed by path regenerate on demand. Returns the number of photos moved.
+#[tauri::command]
+pub async fn relocate_library(
+    state: State<'_, AppState>,
+    library_id: i64,
+    old_root: String,
+    new_root: String,
+) -> Result<usize, AppError> {
+    if !std::path::Path::new(&new_root).is_dir() {
+        return Err(AppError::InvalidInput(format!("Not a folder: {}", new_root)));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let current = db
+        .get_all_libraries()?
+        .into_iter()
+        .find(|l| l.id == library_id)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown library id {}", library_id)))?
+        .root_path;
+    if current != old_root {
+        return Err(AppError::Conflict(format!("Library {} is rooted at {}, not {}", library_id, current, old_root)));
+    }
+    if db.get_library_id(&new_root)?.is_some() {
+        return Err(AppError::Conflict(format!("{} is already a library", new_root)));
+    }
+
+    let moved = db.relocate_library(library_id, &old_root, &new_root)?;
+    {
+        let mut ids = lock(&state.library_ids);
+        ids.retain(|_, id| *id != library_id);
+        ids.insert(new_root.clone(), library_id);
+    }
+    lock(&state.libraries).relocate(library_id, new_root);
+    Ok(moved)
+}
+
+#[tauri::command]
+pub async fn get_library_paths(state: State<'_, AppState>) -> Result<Vec<String>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let libraries = db.get_all_libraries()?;
+    Ok(libraries.into_iter().map(|l| l.root_path).collect())
+}
+
+/// Toggle favorite status on a photo
+#[tauri::command]
+pub async fn toggle_favorite(
+    state: State<'_, AppState>,
+    photo_id: i64,
+) -> Result<bool, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.toggle_favorite(photo_id).map_err(AppError::from)
+}
+
+/// How many photos the "Recents" shelf remembers
+const MAX_RECENTLY_VIEWED: i64 = 200;
+
+#[tauri::command]
+pub async fn mark_viewed(state: State<'_, AppState>, photo_id: i64) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.mark_viewed(photo_id, MAX_RECENTLY_VIEWED).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_recently_viewed(state: State<'_, AppState>) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.get_recently_viewed().map_err(AppError::from)
+}
+
+/// Override the scan's photo/screenshot/document guess
+#[tauri::command]
+pub async fn set_photo_category(
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+    category: String,
+) -> Result<(), AppError> {
+    if !scan::PHOTO_CATEGORIES.contains(&category.as_str()) {
+        return Err(AppError::InvalidInput(format!("Unknown category: {}", category)));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.set_photo_category(&photo_ids, &category).map_err(AppError::from)
+}
+
+/// Outcome of a bulk metadata edit
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkEditResult {
+    pub updated: usize,
+    /// Files whose EXIF was rewritten (only with `write_exif`)
+    pub exif_written: usize,
+    /// Files whose EXIF couldn't be rewritten in place; their database rows are still updated
+    pub exif_failed: Vec<String>,
+}
+
+/// Apply an in-place EXIF rewrite to each file off the async runtime.
+/// With `require_tags`, a file lacking all of `tags` counts as a failure.
+async fn rewrite_exif_files<F>(paths: Vec<String>, tags: &'static [u16], require_tags: bool, edit: F) -> (usize, Vec<String>)
+where
+    F: Fn(u16, &str) -> Option<String> + Send + Sync + 'static,
+{
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut written = 0;
+        let mut failed = Vec::new();
+        for path in paths {
+            match crate::exif_write::rewrite_ascii_tags(std::path::Path::new(&path), tags, &edit) {
+                Ok(crate::exif_write::RewriteOutcome::Written) => written += 1,
+                Ok(crate::exif_write::RewriteOutcome::NoTags) if !require_tags => {}
+                Ok(_) => failed.push(path),
+                Err(e) => {
+                    eprintln!("EXIF rewrite failed for {}: {}", path, e);
+                    failed.push(path);
+                }
+            }
+        }
+        (written, failed)
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Shift the capture date of many photos by `offset_seconds` (e.g. 7200 for a camera clock two
+/// hours behind). With `write_exif`, the EXIF date tags of each file are shifted too.
+#[tauri::command]
+pub async fn shift_dates(
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+    offset_seconds: i64,
+    write_exif: Option<bool>,
+) -> Result<BulkEditResult, AppError> {
+    let shifted = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.shift_taken_at(&photo_ids, offset_seconds)?
+    };
+    let mut result = BulkEditResult { updated: shifted.len(), ..Default::default() };
+    if write_exif.unwrap_or(false) && offset_seconds != 0 {
+        let paths = shifted.into_iter().map(|(_, path)| path).collect();
+        // Files without EXIF dates have nothing to shift
+        (result.exif_written, result.exif_failed) = rewrite_exif_files(
+            paths,
+            crate::exif_write::DATE_TAGS,
+            false,
+            move |_, value| crate::exif_write::shift_exif_datetime(value, offset_seconds),
+        )
+        .await;
+    }
+    Ok(result)
+}
+
+/// Set one metadata field on many photos: `camera_make`, `camera_model`, `lens`, `title` or
+/// `caption` (a null value clears it). `write_exif` also rewrites the camera fields in each
+/// file, which only works where the new value fits the space the file reserved for the old one.
+#[tauri::command]
+pub async fn set_field(
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+    field: String,
+    value: Option<String>,
+    write_exif: Option<bool>,
+) -> Result<BulkEditResult, AppError> {
+    if !crate::db::BULK_EDIT_FIELDS.contains(&field.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "Field '{}' can't be edited (expected one of {})",
+            field,
+            crate::db::BULK_EDIT_FIELDS.join(", ")
+        )));
+    }
+    let value = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let exif_tag: Option<&'static [u16]> = match field.as_str() {
+        "camera_make" => Some(&[crate::exif_write::TAG_MAKE]),
+        "camera_model" => Some(&[crate::exif_write::TAG_MODEL]),
+        "lens" => Some(&[crate::exif_write::TAG_LENS_MODEL]),
+        _ => None,
+    };
+    let write_exif = write_exif.unwrap_or(false);
+    if write_exif && exif_tag.is_none() {
+        return Err(AppError::InvalidInput(format!("'{}' has no EXIF tag to write back", field)));
+    }
+    let updated = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.set_photo_field(&photo_ids, &field, value.as_deref())?
+    };
+    let mut result = BulkEditResult { updated: updated.len(), ..Default::default() };
+    if let (true, Some(tags)) = (write_exif, exif_tag) {
+        let paths = updated.into_iter().map(|(_, path)| path).collect();
+        let new_value = value.unwrap_or_default();
+        (result.exif_written, result.exif_failed) =
+            rewrite_exif_files(paths, tags, true, move |_, _| Some(new_value.clone())).await;
+    }
+    Ok(result)
+}
+
+/// Tell every window that photos left the grid (`permanent` once their rows are gone)
+fn emit_photos_removed(app: &AppHandle, photo_ids: &[i64], permanent: bool) {
+    app.emit("photos-removed", serde_json::json!({
+        "photoIds": photo_ids,
+        "permanent": permanent
+    }))
+    .ok();
+}
+
+/// Soft-delete photos (move to trash)
+#[tauri::command]
+pub async fn soft_delete_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.soft_delete(&photo_ids)?;
+    emit_photos_removed(&app, &photo_ids, false);
+    lock(&state.undo_log).push(UndoOp::SoftDelete { photo_ids });
+    Ok(())
+}
+
+/// Libraries a filter-driven bulk action covers: every open one with `allLibraries`,
+/// else `libraryId` or the active library
+fn filter_library_ids(db: &Database, state: &AppState, filter: &GetPhotosParams) -> Result<Vec<i64>, AppError> {
+    if filter.all_libraries.unwrap_or(false) {
+        Ok(lock(&state.libraries).ids())
+    } else {
+        Ok(vec![resolve_library_id(db, state, filter.library_id)?])
+    }
+}
+
+/// Trash every photo matching `filter` (the `get_photos` params; paging is ignored), e.g.
+/// all videos in one folder. Both halves of RAW+JPEG pairs are matched. Undoable as one
+/// step. Returns how many photos were trashed.
+#[tauri::command]
+pub async fn soft_delete_by_filter(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    filter: GetPhotosParams,
+) -> Result<usize, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = filter_library_ids(db, &state, &filter)?;
+    let photo_ids = db.soft_delete_by_filter(&library_ids, &filter.filter()?)?;
+    let count = photo_ids.len();
+    if count > 0 {
+        emit_photos_removed(&app, &photo_ids, false);
+        lock(&state.undo_log).push(UndoOp::SoftDelete { photo_ids });
+    }
+    Ok(count)
+}
+
+/// Remove a tag from every photo matching `filter` (see `soft_delete_by_filter`).
+/// Returns how many photos had the tag.
+#[tauri::command]
+pub async fn untag_by_filter(
+    state: State<'_, AppState>,
+    filter: GetPhotosParams,
+    tag_id: i64,
+) -> Result<usize, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_ids = filter_library_ids(db, &state, &filter)?;
+    db.untag_by_filter(&library_ids, &filter.filter()?, tag_id)
+        .map_err(AppError::from)
+}
+
+/// Restore photos from trash
+#[tauri::command]
+pub async fn restore_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.restore_from_trash(&photo_ids)?;
+    app.emit("photos-restored", serde_json::json!({ "photoIds": photo_ids })).ok();
+    lock(&state.undo_log).push(UndoOp::Restore { photo_ids });
+    Ok(())
+}
+
+/// Get detailed photo info by ID
+#[tauri::command]
+pub async fn get_photo_detail(
+    state: State<'_, AppState>,
+    photo_id: i64,
+) -> Result<Option<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.get_photo_by_id(photo_id).map_err(AppError::from)
+}
+
+/// Everything the detail panel shows for one photo
+#[derive(Debug, Serialize)]
+pub struct PhotoDetail {
+    pub photo: crate::db::PhotoRecord,
+    pub tags: Vec<crate::db::TagRecord>,
+    pub albums: Vec<crate::db::AlbumRecord>,
+}
+
+/// Photo records for a set of ids (e.g. a saved selection), in the order given.
+/// Ids that no longer exist are left out.
+#[tauri::command]
+pub async fn get_photos_by_ids(
+    state: State<'_, AppState>,
+    ids: Vec<i64>,
+) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    Ok(db.get_photos_by_ids(&ids)?)
+}
+
+/// `get_photo_detail` plus the photo's tags and albums in one call
+#[tauri::command]
+pub async fn get_photo_detail_full(
+    state: State<'_, AppState>,
+    photo_id: i64,
+) -> Result<Option<PhotoDetail>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let Some(photo) = db.get_photo_by_id(photo_id)? else {
+        return Ok(None);
+    };
+    Ok(Some(PhotoDetail {
+        photo,
+        tags: db.get_tags_for_photo(photo_id)?,
+        albums: db.get_albums_for_photo(photo_id)?,
+    }))
+}
+
+// ── File operations ──
+
+/// Outcome of a hard delete: which files went to the OS trash, were removed permanently,
+/// or were left on disk because trashing them failed
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteResult {
+    pub removed: u64,
+    pub trashed: Vec<String>,
+    pub deleted: Vec<String>,
+    pub failed: Vec<String>,
+    /// Cached thumbnail and preview files cleaned up
+    pub thumbnails_removed: u64,
+}
+
+/// Permanently delete photos from DB and optionally from disk.
+/// With `use_os_trash`, files go to the system Trash/Recycle Bin. Only on platforms without
+/// one are they permanently deleted instead (reported under `deleted`); any other trash error
+/// leaves the file in place and reports it under `failed`.
+#[tauri::command]
+pub async fn hard_delete_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+    delete_from_disk: bool,
+    use_os_trash: Option<bool>,
+) -> Result<DeleteResult, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+
+    // Security: validate paths belong to indexed libraries before disk deletion
+    let library_roots = db.get_library_root_paths().unwrap_or_default();
+    let thumbs: Vec<String> = db.get_thumb_paths(&photo_ids)?.into_values().collect();
+    let paths = db.hard_delete_photos(&photo_ids)?;
+    emit_photos_removed(&app, &photo_ids, true);
+    // Before any disk deletion: the cache key is derived from the file's content
+    let thumbnails_removed = remove_cached_renditions(&paths, &thumbs);
+    let mut result = DeleteResult {
+        removed: paths.len() as u64,
+        thumbnails_removed,
+        ..Default::default()
+    };
+    if delete_from_disk {
+        let use_os_trash = use_os_trash.unwrap_or(false);
+        for p in &paths {
+            let canonical = std::fs::canonicalize(p).unwrap_or_default();
+            let canonical_str = canonical.to_string_lossy();
+            let is_safe = library_roots.iter().any(|root| canonical_str.starts_with(root));
+            if !is_safe {
+                eprintln!("⚠ Blocked deletion of file outside library roots: {}", p);
+                continue;
+            }
+            if use_os_trash {
+                match move_to_trash(p) {
+                    Some(Ok(())) => {
+                        result.trashed.push(p.clone());
+                        continue;
+                    }
+                    Some(Err(e)) => {
+                        eprintln!("⚠ Could not move {} to the trash: {}", p, e);
+                        result.failed.push(p.clone());
+                        continue;
+                    }
+                    None => eprintln!("⚠ No trash on this platform, deleting {} permanently", p),
+                }
+            }
+            match std::fs::remove_file(p) {
+                Ok(()) => result.deleted.push(p.clone()),
+                Err(e) => {
+                    eprintln!("⚠ Could not delete {}: {}", p, e);
+                    result.failed.push(p.clone());
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Delete the cached thumbnails and previews of removed photos; returns how many files went
+fn remove_cached_renditions(paths: &[String], stored_thumbs: &[String]) -> u64 {
+    match thumb::remove_renditions_for(paths, stored_thumbs) {
+        Ok(stats) => stats.files,
+        Err(e) => {
+            eprintln!("⚠ Could not clean up thumbnails: {}", e);
+            0
+        }
+    }
+}
+
+/// Move a file to the OS trash; `None` on platforms the trash crate has no backend for
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn move_to_trash(path: &str) -> Option<Result<(), String>> {
+    Some(trash::delete(path).map_err(|e| e.to_string()))
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn move_to_trash(_path: &str) -> Option<Result<(), String>> {
+    None
+}
+
+/// Security: reject names that could escape the photo's folder or that filesystems refuse
+fn validate_filename(name: &str) -> Result<(), AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(msg.to_string()));
+    if name.contains('/') || name.contains('\\') || name == "." || name == ".." {
+        return invalid("Invalid filename: must not contain path separators or be '.' or '..'");
+    }
+    if name.is_empty() || name.len() > 255 {
+        return invalid("Invalid filename: must be 1-255 characters");
+    }
+    if name.chars().any(|c| c.is_control()) || name.trim().is_empty() {
+        return invalid("Invalid filename: must not be blank or contain control characters");
+    }
+    Ok(())
+}
+
+/// Keep the original extension when the new name omits one ("beach" → "beach.jpg")
+fn with_original_extension(new_name: &str, original: &std::path::Path) -> String {
+    if std::path::Path::new(new_name).extension().is_some() {
+        return new_name.to_string();
+    }
+    match original.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}", new_name, ext),
+        None => new_name.to_string(),
+    }
+}
+
+/// First "stem (n).ext" that doesn't exist in `dir`
+fn unique_filename(dir: &std::path::Path, filename: &str) -> String {
+    let as_path = std::path::Path::new(filename);
+    let stem = as_path.file_stem().and_then(|s| s.to_str()).unwrap_or(filename);
+    let ext = as_path.extension().and_then(|e| e.to_str());
+    (1..)
+        .map(|n| match ext {
+            Some(ext) => format!("{} ({}).{}", stem, n, ext),
+            None => format!("{} ({})", stem, n),
+        })
+        .find(|candidate| !dir.join(candidate).exists())
+        .unwrap_or_else(|| filename.to_string())
+}
+
+/// Whether two paths name the same file on disk, such as differently-cased names on a
+/// case-insensitive filesystem. False when either doesn't exist.
+fn same_file(a: &std::path::Path, b: &std::path::Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        // Resolves to the on-disk spelling, so two names for one file compare equal
+        match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
-/// Get list of all indexed libraries/sources
+/// Rename a photo file on disk and in DB.
+/// If the target exists, fails with `AppError::FileExists` unless `auto_suffix` is set,
+/// in which case a " (n)" suffix is appended. Returns the new path.
 #[tauri::command]
-pub async fn get_libraries(
+pub async fn rename_photo(
     state: State<'_, AppState>,
-) -> Result<Vec<crate::db::LibraryInfo>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.get_all_libraries().map_err(|e| e.to_string())
+    photo_id: i64,
+    new_filename: String,
+    auto_suffix: Option<bool>,
+) -> Result<String, AppError> {
+    validate_filename(&new_filename)?;
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    // Get old path first
+    let old_photo = db.get_photo_by_id(photo_id)?;
+    let old_photo = old_photo.ok_or_else(|| AppError::NotFound("Photo not found".to_string()))?;
+    let old_path = std::path::PathBuf::from(&old_photo.path);
+    let dir = old_path.parent().ok_or("Photo has no parent directory")?.to_path_buf();
+
+    let mut filename = with_original_extension(&new_filename, &old_path);
+    if filename == old_photo.filename {
+        return Ok(old_photo.path);
+    }
+    // Case-only renames on case-insensitive filesystems report the source as existing
+    let target = dir.join(&filename);
+    let target_exists = target.exists() && !same_file(&target, &old_path);
+    if target_exists {
+        let suggested = unique_filename(&dir, &filename);
+        if !auto_suffix.unwrap_or(false) {
+            return Err(AppError::FileExists {
+                existing_path: dir.join(&filename).to_string_lossy().to_string(),
+                suggested_name: suggested,
+            });
+        }
+        filename = suggested;
+    }
+
+    let new_path = dir.join(&filename);
+    let new_path_str = new_path.to_string_lossy().to_string();
+    // Rename on disk first so the DB never points at a file that wasn't moved
+    if old_path.exists() {
+        std::fs::rename(&old_path, &new_path)?;
+    }
+    db.move_photo(photo_id, &new_path_str, &filename)?;
+    thumb::forget_content_keys([old_photo.path.as_str()]);
+    lock(&state.undo_log).push(UndoOp::Move {
+        moves: vec![FileMove {
+            photo_id,
+            from_path: old_photo.path.clone(),
+            to_path: new_path_str.clone(),
+        }],
+    });
+    Ok(new_path_str)
+}
+
+/// One entry of a bulk rename result
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameMapping {
+    pub photo_id: i64,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// Expand a bulk-rename template for one photo.
+/// Tokens: {date} (YYYY-MM-DD), {camera}, {seq} (zero-padded), {original} (old stem), {ext}.
+fn render_rename_template(template: &str, photo: &crate::db::PhotoRecord, seq: &str) -> String {
+    let path = std::path::Path::new(&photo.path);
+    let original = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let date_src = photo.taken_at.as_deref().unwrap_or(&photo.modified_at);
+    let date = date_src.get(..10).unwrap_or(date_src);
+    let camera = photo
+        .camera_model
+        .as_deref()
+        .or(photo.camera_make.as_deref())
+        .unwrap_or("unknown")
+        .trim()
+        .replace(' ', "-");
+
+    let mut name = template
+        .replace("{date}", date)
+        .replace("{camera}", &camera)
+        .replace("{seq}", seq)
+        .replace("{original}", original)
+        .replace("{ext}", ext);
+    name = name.replace(['/', '\\'], "_");
+    if !template.contains("{ext}") && !ext.is_empty() {
+        name = format!("{}.{}", name, ext);
+    }
+    name
 }
 
+/// Rename photos using a template, in `taken_at` order. Names that collide within the batch
+/// get a `_<seq>` suffix. Files are renamed via temporary names (so swaps work) and every
+/// disk rename is rolled back if any step fails.
 #[tauri::command]
-pub async fn add_library_path(app: AppHandle, path: String) -> Result<serde_json::Value, String> {
-    select_and_index(app, path).await
+pub async fn bulk_rename(
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+    template: String,
+) -> Result<Vec<RenameMapping>, AppError> {
+    if template.trim().is_empty() {
+        return Err(AppError::InvalidInput("Rename template must not be empty".to_string()));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+
+    let mut photos = Vec::new();
+    for id in &photo_ids {
+        if let Some(p) = db.get_photo_by_id(*id)? {
+            photos.push(p);
+        }
+    }
+    photos.sort_by(|a, b| {
+        let ka = a.taken_at.as_deref().unwrap_or(&a.modified_at);
+        let kb = b.taken_at.as_deref().unwrap_or(&b.modified_at);
+        ka.cmp(kb).then(a.id.cmp(&b.id))
+    });
+
+    // Plan every target name before touching the disk
+    let width = photos.len().to_string().len().max(3);
+    let sources: std::collections::HashSet<std::path::PathBuf> =
+        photos.iter().map(|p| std::path::PathBuf::from(&p.path)).collect();
+    let mut taken: std::collections::HashSet<std::path::PathBuf> = std::collections::HashSet::new();
+    let mut plan: Vec<(crate::db::PhotoRecord, std::path::PathBuf, String)> = Vec::new();
+    for (i, photo) in photos.into_iter().enumerate() {
+        let seq = format!("{:0width$}", i + 1, width = width);
+        let old_path = std::path::PathBuf::from(&photo.path);
+        let dir = old_path.parent().ok_or("Photo has no parent directory")?.to_path_buf();
+        let mut name = render_rename_template(&template, &photo, &seq);
+        let key = |n: &str| dir.join(n.to_lowercase());
+        if taken.contains(&key(&name)) {
+            // "{stem}_{seq}", then "{stem}_{seq}_2", ... until neither this batch nor another
+            // file on disk holds the name
+            let as_path = std::path::Path::new(&name);
+            let stem = as_path.file_stem().and_then(|s| s.to_str()).unwrap_or(&name).to_string();
+            let ext = as_path.extension().and_then(|e| e.to_str()).map(str::to_string);
+            let free = |n: &str| {
+                let target = dir.join(n);
+                !taken.contains(&key(n)) && (!target.exists() || sources.contains(&target))
+            };
+            name = (1..)
+                .map(|n| {
+                    let stem = if n == 1 { format!("{}_{}", stem, seq) } else { format!("{}_{}_{}", stem, seq, n) };
+                    match &ext {
+                        Some(ext) => format!("{}.{}", stem, ext),
+                        None => stem,
+                    }
+                })
+                .find(|candidate| free(candidate))
+                .unwrap_or(name);
+        }
+        validate_filename(&name)?;
+        let target = dir.join(&name);
+        if target.exists() && !sources.contains(&target) {
+            return Err(AppError::Conflict(format!("Cannot rename: {} already exists", target.to_string_lossy())));
+        }
+        taken.insert(key(&name));
+        plan.push((photo, target, name));
+    }
+
+    // Phase 1: move to temporary names; phase 2: move to final names
+    let mut done: Vec<(std::path::PathBuf, std::path::PathBuf)> = Vec::new();
+    let rollback = |done: &[(std::path::PathBuf, std::path::PathBuf)]| {
+        for (from, to) in done.iter().rev() {
+            if let Err(e) = std::fs::rename(to, from) {
+                eprintln!("  ⚠ Bulk rename rollback failed for {}: {}", to.display(), e);
+            }
+        }
+    };
+    let mut temps = Vec::with_capacity(plan.len());
+    for (photo, _, _) in &plan {
+        let src = std::path::PathBuf::from(&photo.path);
+        let tmp = src.with_file_name(format!(".ifoto-rename-{}.tmp", photo.id));
+        if let Err(e) = std::fs::rename(&src, &tmp) {
+            rollback(&done);
+            return Err(AppError::Io(format!("Failed to rename {}: {}", photo.path, e)));
+        }
+        done.push((src, tmp.clone()));
+        temps.push(tmp);
+    }
+    for (tmp, (_, target, _)) in temps.iter().zip(&plan) {
+        if let Err(e) = std::fs::rename(tmp, target) {
+            rollback(&done);
+            return Err(AppError::Io(format!("Failed to rename to {}: {}", target.display(), e)));
+        }
+        done.push((tmp.clone(), target.clone()));
+    }
+
+    let db_moves: Vec<(i64, String, String)> = plan
+        .iter()
+        .map(|(photo, target, name)| (photo.id, target.to_string_lossy().to_string(), name.clone()))
+        .collect();
+    if let Err(e) = db.move_photos(&db_moves) {
+        rollback(&done);
+        return Err(e.into());
+    }
+    thumb::forget_content_keys(plan.iter().map(|(photo, _, _)| photo.path.as_str()));
+
+    lock(&state.undo_log).push(UndoOp::Move {
+        moves: plan
+            .iter()
+            .map(|(photo, target, _)| FileMove {
+                photo_id: photo.id,
+                from_path: photo.path.clone(),
+                to_path: target.to_string_lossy().to_string(),
+            })
+            .collect(),
+    });
+
+    Ok(plan
+        .into_iter()
+        .map(|(photo, _, name)| RenameMapping {
+            photo_id: photo.id,
+            old_name: photo.filename,
+            new_name: name,
+        })
+        .collect())
 }
 
+/// Reverse the most recent destructive operation. Returns the operation that was undone,
+/// or None if the log is empty. Moves are only reversed if each file is still where we left it.
 #[tauri::command]
-pub async fn remove_library_path(state: State<'_, AppState>, path: String) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    
-    // Find library by path
-    let libraries = db.get_all_libraries().map_err(|e| e.to_string())?;
-    // Simple string match for now, could be more robust with canonicalization
-    if let Some(lib) = libraries.iter().find(|l| l.root_path == path) {
-        db.remove_library(lib.id).map_err(|e| e.to_string())?;
+pub async fn undo_last_operation(
+    state: State<'_, AppState>,
+) -> Result<Option<UndoOp>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let op = match lock(&state.undo_log).pop() {
+        Some(op) => op,
+        None => return Ok(None),
+    };
+
+    match &op {
+        UndoOp::SoftDelete { photo_ids } => {
+            db.restore_from_trash(photo_ids)?;
+        }
+        UndoOp::Restore { photo_ids } => {
+            db.soft_delete(photo_ids)?;
+        }
+        UndoOp::Move { moves } => {
+            // Verify every file first so a partial undo can't leave the batch half-reversed
+            for m in moves {
+                let current = std::path::Path::new(&m.to_path);
+                let original = std::path::Path::new(&m.from_path);
+                if !current.exists() {
+                    return Err(AppError::NotFound(format!("Cannot undo: {} is no longer at its renamed location", m.to_path)));
+                }
+                if original.exists() {
+                    return Err(AppError::Conflict(format!("Cannot undo: a file already exists at {}", m.from_path)));
+                }
+            }
+            for m in moves.iter().rev() {
+                std::fs::rename(&m.to_path, &m.from_path)?;
+                let original_name = std::path::Path::new(&m.from_path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or_default();
+                db.move_photo(m.photo_id, &m.from_path, original_name)?;
+                thumb::forget_content_keys([m.to_path.as_str()]);
+            }
+        }
     }
-    
-    Ok(())
+    Ok(Some(op))
 }
 
+// ── Tags ──
+
 #[tauri::command]
-pub async fn get_library_paths(state: State<'_, AppState>) -> Result<Vec<String>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    let libraries = db.get_all_libraries().map_err(|e| e.to_string())?;
-    Ok(libraries.into_iter().map(|l| l.root_path).collect())
+pub async fn create_tag(
+    state: State<'_, AppState>,
+    name: String,
+    color: String,
+) -> Result<crate::db::TagRecord, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.create_tag(&name, &color).map_err(AppError::from)
 }
 
-/// Toggle favorite status on a photo
 #[tauri::command]
-pub async fn toggle_favorite(
+pub async fn delete_tag(
     state: State<'_, AppState>,
-    photo_id: i64,
-) -> Result<bool, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.toggle_favorite(photo_id).map_err(|e| e.to_string())
+    tag_id: i64,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.delete_tag(tag_id).map_err(AppError::from)
 }
 
-/// Soft-delete photos (move to trash)
+/// Merge one tag into another (e.g. "Beach" into "beach"): the source tag's photos are
+/// tagged with the target and the source tag is deleted. Returns how many photos moved
+/// (photos that already had both tags don't count).
 #[tauri::command]
-pub async fn soft_delete_photos(
+pub async fn merge_tags(
+    state: State<'_, AppState>,
+    source_tag_id: i64,
+    target_tag_id: i64,
+) -> Result<usize, AppError> {
+    if source_tag_id == target_tag_id {
+        return Err(AppError::InvalidInput("Cannot merge a tag into itself".to_string()));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.merge_tags(source_tag_id, target_tag_id)?
+        .ok_or_else(|| AppError::NotFound("Unknown tag id".to_string()))
+}
+
+#[tauri::command]
+pub async fn get_tags(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::db::TagRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.get_tags().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn tag_photos(
     state: State<'_, AppState>,
     photo_ids: Vec<i64>,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.soft_delete(&photo_ids).map_err(|e| e.to_string())
+    tag_id: i64,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.tag_photos(&photo_ids, tag_id).map_err(AppError::from)
 }
 
-/// Restore photos from trash
 #[tauri::command]
-pub async fn restore_photos(
+pub async fn untag_photos(
     state: State<'_, AppState>,
     photo_ids: Vec<i64>,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.restore_from_trash(&photo_ids).map_err(|e| e.to_string())
+    tag_id: i64,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.untag_photos(&photo_ids, tag_id).map_err(AppError::from)
 }
 
-/// Get detailed photo info by ID
 #[tauri::command]
-pub async fn get_photo_detail(
+pub async fn get_photo_tags(
     state: State<'_, AppState>,
     photo_id: i64,
-) -> Result<Option<crate::db::PhotoRecord>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.get_photo_by_id(photo_id).map_err(|e| e.to_string())
+) -> Result<Vec<crate::db::TagRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.get_tags_for_photo(photo_id).map_err(AppError::from)
 }
 
-// ── File operations ──
+// ── Albums ──
 
-/// Permanently delete photos from DB and optionally from disk
 #[tauri::command]
-pub async fn hard_delete_photos(
+pub async fn create_album(
+    state: State<'_, AppState>,
+    name: String,
+    parent_album_id: Option<i64>,
+) -> Result<crate::db::AlbumRecord, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.create_album(&name, parent_album_id).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_album(
+    state: State<'_, AppState>,
+    album_id: i64,
+    cascade: Option<bool>,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.delete_album(album_id, cascade.unwrap_or(false)).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn rename_album(
+    state: State<'_, AppState>,
+    album_id: i64,
+    new_name: String,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.rename_album(album_id, &new_name).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_albums(
+    state: State<'_, AppState>,
+    nested: Option<bool>,
+) -> Result<AlbumListing, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    if nested.unwrap_or(false) {
+        db.get_album_tree().map(AlbumListing::Nested).map_err(AppError::from)
+    } else {
+        db.get_albums().map(AlbumListing::Flat).map_err(AppError::from)
+    }
+}
+
+/// `get_albums` result: flat list (default) or the nested album tree
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum AlbumListing {
+    Flat(Vec<crate::db::AlbumRecord>),
+    Nested(Vec<crate::db::AlbumNode>),
+}
+
+#[tauri::command]
+pub async fn add_to_album(
     state: State<'_, AppState>,
+    album_id: i64,
     photo_ids: Vec<i64>,
-    delete_from_disk: bool,
-) -> Result<u64, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.add_photos_to_album(album_id, &photo_ids).map_err(AppError::from)
+}
 
-    // Security: validate paths belong to indexed libraries before disk deletion
-    let library_roots = db.get_library_root_paths().unwrap_or_default();
-    let paths = db.hard_delete_photos(&photo_ids).map_err(|e| e.to_string())?;
-    if delete_from_disk {
-        for p in &paths {
-            let canonical = std::fs::canonicalize(p).unwrap_or_default();
-            let canonical_str = canonical.to_string_lossy();
-            let is_safe = library_roots.iter().any(|root| canonical_str.starts_with(root));
-            if is_safe {
-                let _ = std::fs::remove_file(p);
+#[tauri::command]
+pub async fn remove_from_album(
+    state: State<'_, AppState>,
+    album_id: i64,
+    photo_ids: Vec<i64>,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.remove_photos_from_album(album_id, &photo_ids).map_err(AppError::from)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumExportResult {
+    pub copied: usize,
+    /// Album photos whose source file no longer exists
+    pub skipped_missing: Vec<String>,
+}
+
+/// Copy an album's photos, in album order, into `dest_dir` as plain files. With `numbered`
+/// each name gets a zero-padded "001 - " prefix so other apps sort them the same way.
+/// Existing files are never overwritten; clashing names get a " (n)" suffix.
+#[tauri::command]
+pub async fn export_album(
+    state: State<'_, AppState>,
+    album_id: i64,
+    dest_dir: String,
+    numbered: bool,
+) -> Result<AlbumExportResult, AppError> {
+    let photos = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.get_album_photos(album_id)?
+    };
+    let dest = std::path::PathBuf::from(&dest_dir);
+    std::fs::create_dir_all(&dest)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let width = photos.len().to_string().len().max(3);
+        let mut result = AlbumExportResult { copied: 0, skipped_missing: Vec::new() };
+        for (i, photo) in photos.iter().enumerate() {
+            let src = std::path::Path::new(&photo.path);
+            if !src.exists() {
+                result.skipped_missing.push(photo.path.clone());
+                continue;
+            }
+            let mut name = if numbered {
+                format!("{:0width$} - {}", i + 1, photo.filename, width = width)
             } else {
-                eprintln!("⚠ Blocked deletion of file outside library roots: {}", p);
+                photo.filename.clone()
+            };
+            if dest.join(&name).exists() {
+                name = unique_filename(&dest, &name);
             }
+            std::fs::copy(src, dest.join(&name))
+                .map_err(|e| format!("Failed to copy {}: {}", photo.path, e))?;
+            result.copied += 1;
         }
-    }
-    Ok(paths.len() as u64)
+        eprintln!(
+            "✓ Exported album {}: {} copied, {} missing",
+            album_id,
+            result.copied,
+            result.skipped_missing.len()
+        );
+        Ok(result)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
-/// Rename a photo file on disk and in DB
+/// Add a selection to several albums at once; returns per-album added/already-present counts
 #[tauri::command]
-pub async fn rename_photo(
+pub async fn add_photos_to_albums(
+    state: State<'_, AppState>,
+    photo_ids: Vec<i64>,
+    album_ids: Vec<i64>,
+) -> Result<Vec<crate::db::AlbumAddResult>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.add_photos_to_albums(&album_ids, &photo_ids).map_err(AppError::from)
+}
+
+/// Move one photo within an album's manual order (0 = start; past the end = last)
+#[tauri::command]
+pub async fn reorder_album(
     state: State<'_, AppState>,
+    album_id: i64,
     photo_id: i64,
-    new_filename: String,
-) -> Result<String, String> {
-    // Security: validate filename has no path separators or traversal
-    if new_filename.contains('/') || new_filename.contains('\\') || new_filename.contains("..") {
-        return Err("Invalid filename: must not contain path separators or '..'".to_string());
-    }
-    if new_filename.is_empty() || new_filename.len() > 255 {
-        return Err("Invalid filename: must be 1-255 characters".to_string());
-    }
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    // Get old path first
-    let old_photo = db.get_photo_by_id(photo_id).map_err(|e| e.to_string())?;
-    let old_photo = old_photo.ok_or("Photo not found")?;
-    let old_path = std::path::PathBuf::from(&old_photo.path);
-    let new_path_str = db.rename_photo(photo_id, &new_filename).map_err(|e| e.to_string())?;
-    let new_path = std::path::PathBuf::from(&new_path_str);
-    // Rename on disk
-    if old_path.exists() {
-        std::fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
-    }
-    Ok(new_path_str)
+    new_index: usize,
+) -> Result<(), AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.reorder_album(album_id, photo_id, new_index).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => AppError::NotFound("Photo is not in this album".to_string()),
+        e => e.into(),
+    })
 }
 
-// ── Tags ──
+#[tauri::command]
+pub async fn get_album_photos(
+    state: State<'_, AppState>,
+    album_id: i64,
+) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.get_album_photos(album_id).map_err(AppError::from)
+}
 
+/// Photo count, total size, date span and media-type breakdown for an album header
 #[tauri::command]
-pub async fn create_tag(
+pub async fn get_album_stats(
     state: State<'_, AppState>,
-    name: String,
-    color: String,
-) -> Result<crate::db::TagRecord, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.create_tag(&name, &color).map_err(|e| e.to_string())
+    album_id: i64,
+) -> Result<crate::db::AlbumStats, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.get_album_stats(album_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Unknown album id {}", album_id)))
 }
 
+// ── Date inference ──
+
+/// Estimate dates for photos without EXIF dates from dated neighbours in the same folder
+/// (defaults to the current library). Estimated photos are flagged `dateIsEstimated`.
 #[tauri::command]
-pub async fn delete_tag(
+pub async fn infer_dates(state: State<'_, AppState>, library_id: Option<i64>) -> Result<usize, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    let count = db.infer_dates(library_id)?;
+    eprintln!("✓ Estimated dates for {} photos in library {}", count, library_id);
+    Ok(count)
+}
+
+/// Groups of photos sharing a filename across folders (defaults to the current library),
+/// with paths and sizes so the user can pick which copy to keep
+#[tauri::command]
+pub async fn find_filename_collisions(
     state: State<'_, AppState>,
-    tag_id: i64,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.delete_tag(tag_id).map_err(|e| e.to_string())
+    library_id: Option<i64>,
+) -> Result<Vec<crate::db::FilenameCollision>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    db.find_filename_collisions(library_id).map_err(AppError::from)
+}
+
+/// Tiny images (web-saved thumbnails, icons) worth bulk-trashing: longer edge at most
+/// `max_dimension` px or file at most `max_bytes`. Reads stored metadata only, no decoding.
+#[tauri::command]
+pub async fn find_small_images(
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+    max_dimension: Option<u32>,
+    max_bytes: Option<u64>,
+) -> Result<crate::db::SmallImages, AppError> {
+    if max_dimension.is_none() && max_bytes.is_none() {
+        return Err(AppError::InvalidInput(
+            "Give a max dimension, a max file size, or both".to_string(),
+        ));
+    }
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, &state, library_id)?;
+    let max_bytes = max_bytes.map(|b| b.min(i64::MAX as u64) as i64);
+    db.find_small_images(library_id, max_dimension.map(i64::from), max_bytes)
+        .map_err(AppError::from)
+}
+
+// ── EXIF re-parse ──
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReparseResult {
+    pub updated: usize,
+    pub total: usize,
+    /// Stopped early by `cancel_reparse_exif`; rows done so far keep their new values
+    pub cancelled: bool,
+}
+
+/// Re-read EXIF for every photo of a library (defaults to the current one) and refresh the
+/// metadata columns, so parser fixes reach existing libraries without a clear-and-rescan.
+/// No directory walk and no thumbnail work; tags, albums and edits are untouched.
+/// Progress is reported as phase "reparse"; `cancel_reparse_exif` stops it between chunks.
+#[tauri::command]
+pub async fn reparse_exif(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+) -> Result<ReparseResult, AppError> {
+    state.reparse_cancel.store(false, Ordering::SeqCst);
+    let (library_id, pending) = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        let library_id = resolve_library_id(db, &state, library_id)?;
+        (library_id, db.get_photos_for_reparse(library_id)?)
+    };
+    let total = pending.len() as u64;
+    let mut done = 0u64;
+    let mut result = ReparseResult { total: pending.len(), ..Default::default() };
+
+    const CHUNK: usize = 100;
+    for chunk in pending.chunks(CHUNK) {
+        if state.reparse_cancel.load(Ordering::SeqCst) {
+            result.cancelled = true;
+            break;
+        }
+        let chunk = chunk.to_vec();
+        let chunk_len = chunk.len() as u64;
+        let parsed: Vec<(i64, scan::ExifData)> = tauri::async_runtime::spawn_blocking(move || {
+            use rayon::prelude::*;
+            scan::scan_pool().install(|| {
+                chunk
+                    .par_iter()
+                    .filter(|(_, path)| std::path::Path::new(path).exists())
+                    .map(|(id, path)| (*id, scan::parse_exif_data(std::path::Path::new(path))))
+                    .collect()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        {
+            let db_guard = ensure_db(&state)?;
+            let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+            db.update_exif_metadata(&parsed)?;
+        }
+        result.updated += parsed.len();
+        done += chunk_len;
+        emit_progress(&app, IndexProgress {
+            phase: "reparse".to_string(),
+            current: done,
+            total: Some(total),
+            ..Default::default()
+        });
+    }
+
+    emit_progress(&app, IndexProgress {
+        phase: "done".to_string(),
+        current: done,
+        total: Some(total),
+        ..Default::default()
+    });
+    eprintln!(
+        "✓ Re-parsed EXIF for {} of {} photos in library {}{}",
+        result.updated,
+        result.total,
+        library_id,
+        if result.cancelled { " (cancelled)" } else { "" }
+    );
+    Ok(result)
+}
+
+/// Ask a running `reparse_exif` to stop after its current chunk
+#[tauri::command]
+pub async fn cancel_reparse_exif(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.reparse_cancel.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+// ── Blurhash ──
+
+/// Backfill BlurHash placeholders for photos indexed without the scan flag.
+/// Decodes on the scan pool in chunks, reporting progress as phase "blurhash".
+/// Returns how many hashes were written.
+#[tauri::command]
+pub async fn generate_blurhashes(app: AppHandle, state: State<'_, AppState>) -> Result<usize, AppError> {
+    let pending = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.get_photos_missing_blurhash()?
+    };
+    let total = pending.len() as u64;
+    let mut done = 0u64;
+    let mut written = 0usize;
+
+    const CHUNK: usize = 100;
+    for chunk in pending.chunks(CHUNK) {
+        let chunk_len = chunk.len() as u64;
+        let chunk = chunk.to_vec();
+        let hashes: Vec<(i64, String)> = tauri::async_runtime::spawn_blocking(move || {
+            use rayon::prelude::*;
+            scan::scan_pool().install(|| {
+                chunk
+                    .par_iter()
+                    .filter_map(|(id, path)| {
+                        scan::compute_blurhash(std::path::Path::new(path)).map(|h| (*id, h))
+                    })
+                    .collect()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        {
+            let db_guard = ensure_db(&state)?;
+            let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+            db.set_blurhashes(&hashes)?;
+        }
+        written += hashes.len();
+        done += chunk_len;
+        emit_progress(&app, IndexProgress {
+            phase: "blurhash".to_string(),
+            current: done,
+            total: Some(total),
+            ..Default::default()
+        });
+    }
+
+    eprintln!("✓ Generated {} blurhashes ({} photos checked)", written, total);
+    Ok(written)
 }
 
-#[tauri::command]
-pub async fn get_tags(
-    state: State<'_, AppState>,
-) -> Result<Vec<crate::db::TagRecord>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.get_tags().map_err(|e| e.to_string())
+// ── Bursts ──
+
+/// Bursts (near-identical frames shot seconds apart) with the sharpest frame suggested as
+/// the keeper. Scoring decodes thumbnails, so it only happens when `analyze` is set; without
+/// it, frames that were never scored are left out. Progress is reported as phase "bursts".
+#[tauri::command]
+pub async fn get_burst_groups(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    library_id: Option<i64>,
+    analyze: Option<bool>,
+) -> Result<Vec<crate::burst::BurstGroup>, AppError> {
+    let load = || -> Result<Vec<crate::burst::BurstCandidate>, AppError> {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        let library_id = resolve_library_id(db, &state, library_id)?;
+        Ok(db.get_burst_candidates(library_id)?)
+    };
+    let mut candidates = load()?;
+
+    if analyze.unwrap_or(false) {
+        let pending: Vec<(i64, String)> = crate::burst::time_runs(&candidates)
+            .into_iter()
+            .flatten()
+            .filter(|c| c.burst_hash.is_none() || c.sharpness.is_none())
+            .map(|c| (c.id, c.path.clone()))
+            .collect();
+        let total = pending.len() as u64;
+        let mut done = 0u64;
+
+        const CHUNK: usize = 50;
+        for chunk in pending.chunks(CHUNK) {
+            let mut thumbs: Vec<(i64, std::path::PathBuf)> = Vec::with_capacity(chunk.len());
+            for (id, path) in chunk {
+                if let Ok(thumb_path) = thumb::get_or_create_thumbnail(path).await {
+                    thumbs.push((*id, thumb_path));
+                }
+            }
+            let signatures: Vec<(i64, i64, f64)> = tauri::async_runtime::spawn_blocking(move || {
+                use rayon::prelude::*;
+                scan::scan_pool().install(|| {
+                    thumbs
+                        .par_iter()
+                        .filter_map(|(id, thumb_path)| {
+                            crate::burst::compute_signature(thumb_path).map(|(h, s)| (*id, h, s))
+                        })
+                        .collect()
+                })
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+            {
+                let db_guard = ensure_db(&state)?;
+                let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+                db.set_burst_signatures(&signatures)?;
+            }
+            done += chunk.len() as u64;
+            emit_progress(&app, IndexProgress {
+                phase: "bursts".to_string(),
+                current: done,
+                total: Some(total),
+                ..Default::default()
+            });
+        }
+        if total > 0 {
+            eprintln!("✓ Scored {} photos for burst detection", total);
+            candidates = load()?;
+        }
+    }
+
+    Ok(crate::burst::time_runs(&candidates)
+        .into_iter()
+        .flat_map(crate::burst::group_run)
+        .collect())
 }
 
-#[tauri::command]
-pub async fn tag_photos(
-    state: State<'_, AppState>,
-    photo_ids: Vec<i64>,
-    tag_id: i64,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.tag_photos(&photo_ids, tag_id).map_err(|e| e.to_string())
+// ── Faces ──
+
+#[cfg(feature = "faces")]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaceGroupingResult {
+    pub faces: usize,
+    pub clusters: usize,
+    /// Stopped by `cancel_group_faces`; the previous clusters are kept
+    pub cancelled: bool,
 }
 
+/// Cluster the stored face embeddings of the open libraries into people. Runs on the face
+/// pool, separate from indexing, and can be stopped with `cancel_group_faces`.
+#[cfg(feature = "faces")]
 #[tauri::command]
-pub async fn untag_photos(
-    state: State<'_, AppState>,
-    photo_ids: Vec<i64>,
-    tag_id: i64,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.untag_photos(&photo_ids, tag_id).map_err(|e| e.to_string())
+pub async fn group_faces(state: State<'_, AppState>) -> Result<FaceGroupingResult, AppError> {
+    let embeddings = {
+        let db_guard = ensure_db(&state)?;
+        let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+        db.get_face_embeddings(&lock(&state.libraries).ids())?
+    };
+    let faces = embeddings.len();
+    let assignments = crate::faces::run(move |cancel| crate::faces::cluster_embeddings(&embeddings, cancel)).await?;
+    let Some(assignments) = assignments else {
+        return Ok(FaceGroupingResult { faces, cancelled: true, ..Default::default() });
+    };
+    let clusters = assignments.iter().map(|(_, c)| *c).max().unwrap_or(0) as usize;
+
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    db.set_face_clusters(&assignments)?;
+    eprintln!("✓ Grouped {} faces into {} people", faces, clusters);
+    Ok(FaceGroupingResult { faces, clusters, cancelled: false })
 }
 
+#[cfg(feature = "faces")]
 #[tauri::command]
-pub async fn get_photo_tags(
-    state: State<'_, AppState>,
-    photo_id: i64,
-) -> Result<Vec<crate::db::TagRecord>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.get_tags_for_photo(photo_id).map_err(|e| e.to_string())
+pub async fn cancel_group_faces() -> Result<(), AppError> {
+    crate::faces::cancel();
+    Ok(())
 }
 
-// ── Albums ──
-
+/// People found by the last `group_faces` pass, largest first
+#[cfg(feature = "faces")]
 #[tauri::command]
-pub async fn create_album(
-    state: State<'_, AppState>,
-    name: String,
-) -> Result<crate::db::AlbumRecord, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.create_album(&name).map_err(|e| e.to_string())
+pub async fn get_face_clusters(state: State<'_, AppState>) -> Result<Vec<crate::db::FaceCluster>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    Ok(db.get_face_clusters(&lock(&state.libraries).ids())?)
 }
 
+#[cfg(feature = "faces")]
 #[tauri::command]
-pub async fn delete_album(
+pub async fn get_photos_for_face_cluster(
     state: State<'_, AppState>,
-    album_id: i64,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.delete_album(album_id).map_err(|e| e.to_string())
+    cluster_id: i64,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<crate::db::PhotoRecord>, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let mut photos = db.get_photos_for_face_cluster(cluster_id, limit.unwrap_or(100).min(500), offset.unwrap_or(0))?;
+    mark_cached_thumbnails(db, &mut photos)?;
+    Ok(photos)
 }
 
+// ── Catalog ──
+
+/// Dump every photo with its tags and albums to `out_path` as "json" or "csv".
+/// Returns the number of photos written.
 #[tauri::command]
-pub async fn rename_album(
+pub async fn export_catalog(
     state: State<'_, AppState>,
-    album_id: i64,
-    new_name: String,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.rename_album(album_id, &new_name).map_err(|e| e.to_string())
+    format: String,
+    out_path: String,
+) -> Result<i64, AppError> {
+    let format = crate::catalog::CatalogFormat::parse(&format)?;
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let count = crate::catalog::export_catalog(db, format, std::path::Path::new(&out_path))?;
+    eprintln!("✓ Exported {} photos to {}", count, out_path);
+    Ok(count)
 }
 
+/// Write a library's geotagged photos as a GPX track (plus one waypoint per photo)
 #[tauri::command]
-pub async fn get_albums(
+pub async fn export_gpx(
     state: State<'_, AppState>,
-) -> Result<Vec<crate::db::AlbumRecord>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.get_albums().map_err(|e| e.to_string())
+    library_id: Option<i64>,
+    out_path: String,
+) -> Result<crate::geo_export::GeoExportResult, AppError> {
+    export_geo(&state, library_id, &out_path, crate::geo_export::GeoFormat::Gpx)
 }
 
+/// Write a library's geotagged photos as KML placemarks joined by a path line
 #[tauri::command]
-pub async fn add_to_album(
+pub async fn export_kml(
     state: State<'_, AppState>,
-    album_id: i64,
-    photo_ids: Vec<i64>,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.add_photos_to_album(album_id, &photo_ids).map_err(|e| e.to_string())
+    library_id: Option<i64>,
+    out_path: String,
+) -> Result<crate::geo_export::GeoExportResult, AppError> {
+    export_geo(&state, library_id, &out_path, crate::geo_export::GeoFormat::Kml)
 }
 
-#[tauri::command]
-pub async fn remove_from_album(
-    state: State<'_, AppState>,
-    album_id: i64,
-    photo_ids: Vec<i64>,
-) -> Result<(), String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.remove_photos_from_album(album_id, &photo_ids).map_err(|e| e.to_string())
+fn export_geo(
+    state: &AppState,
+    library_id: Option<i64>,
+    out_path: &str,
+    format: crate::geo_export::GeoFormat,
+) -> Result<crate::geo_export::GeoExportResult, AppError> {
+    let db_guard = ensure_db(state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = resolve_library_id(db, state, library_id)?;
+    let (photos, skipped) = db.get_geotagged_photos(library_id)?;
+    let name = db
+        .get_all_libraries()?
+        .into_iter()
+        .find(|l| l.id == library_id)
+        .map(|l| l.name)
+        .unwrap_or_else(|| "Library".to_string());
+    crate::geo_export::write_geo(&photos, &name, format, std::path::Path::new(out_path))?;
+    eprintln!("✓ Exported {} geotagged photos to {} ({} without GPS)", photos.len(), out_path, skipped);
+    Ok(crate::geo_export::GeoExportResult { included: photos.len() as i64, skipped })
 }
 
+/// Merge a JSON catalog from another install: recreates tags/albums and re-links matching
+/// photos. Metadata that differs is reported in `conflicts`, never overwritten.
 #[tauri::command]
-pub async fn get_album_photos(
+pub async fn import_catalog(
     state: State<'_, AppState>,
-    album_id: i64,
-) -> Result<Vec<crate::db::PhotoRecord>, String> {
-    let db_guard = state.db.lock().unwrap();
-    let db = db_guard.as_ref().ok_or("No library loaded")?;
-    db.get_album_photos(album_id).map_err(|e| e.to_string())
+    src_path: String,
+) -> Result<crate::catalog::ImportSummary, AppError> {
+    let db_guard = ensure_db(&state)?;
+    let db = db_guard.as_ref().ok_or(AppError::NoLibrary)?;
+    let summary = crate::catalog::import_catalog(db, std::path::Path::new(&src_path))?;
+    eprintln!(
+        "✓ Imported catalog: {} matched, {} unmatched, {} conflicts",
+        summary.matched,
+        summary.unmatched,
+        summary.conflicts.len()
+    );
+    Ok(summary)
 }
 
 // ── Photo Editor ──
@@ -703,14 +3442,14 @@ pub async fn save_edited_photo(
     state: State<'_, AppState>,
     image_data: String,
     target_path: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     // Security: validate target path is within a known library root
     let target = std::path::PathBuf::from(&target_path);
     if target_path.contains("..") {
-        return Err("Invalid path: path traversal not allowed".to_string());
+        return Err(AppError::InvalidInput("Invalid path: path traversal not allowed".to_string()));
     }
     {
-        let db_guard = state.db.lock().unwrap();
+        let db_guard = ensure_db(&state)?;
         if let Some(db) = db_guard.as_ref() {
             let roots = db.get_library_root_paths().unwrap_or_default();
             let target_canonical = std::fs::canonicalize(target.parent().unwrap_or(&target))
@@ -718,14 +3457,14 @@ pub async fn save_edited_photo(
             let target_str = target_canonical.to_string_lossy();
             let is_safe = roots.iter().any(|root| target_str.starts_with(root));
             if !is_safe {
-                return Err("Cannot save file outside of library directories".to_string());
+                return Err(AppError::InvalidInput("Cannot save file outside of library directories".to_string()));
             }
         }
     }
 
     // Security: limit payload size to 50MB
     if image_data.len() > 50 * 1024 * 1024 {
-        return Err("Image data too large (max 50MB)".to_string());
+        return Err(AppError::InvalidInput("Image data too large (max 50MB)".to_string()));
     }
 
     use base64::Engine;
@@ -745,40 +3484,40 @@ pub async fn scan_directory(
     app: AppHandle,
     state: State<'_, AppState>,
     dir_path: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let path = std::path::PathBuf::from(&dir_path);
     if !path.exists() || !path.is_dir() {
-        return Err("Invalid or missing directory".to_string());
+        return Err(AppError::InvalidInput("Invalid or missing directory".to_string()));
     }
     let dir_str = path.to_string_lossy().to_string();
 
     // Ensure DB is open
     let db_path = db_path(&app);
     if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(parent)?;
     }
 
     // Get or create DB + library
     let db = {
-        let db_guard = state.db.lock().unwrap();
+        let db_guard = lock(&state.db);
         if db_guard.is_some() {
             drop(db_guard);
             // DB already open
             None
         } else {
             drop(db_guard);
-            Some(Database::new(&db_path).map_err(|e| e.to_string())?)
+            Some(open_database(&app)?)
         }
     };
 
     if let Some(new_db) = db {
-        *state.db.lock().unwrap() = Some(new_db);
+        *lock(&state.db) = Some(new_db);
     }
 
     let app_clone = app.clone();
-    let state_db = state.db.lock().unwrap();
-    let db_ref = state_db.as_ref().ok_or("No database")?;
-    let _library_id = db_ref.get_or_create_library(&dir_str).map_err(|e| e.to_string())?;
+    let state_db = ensure_db(&state)?;
+    let db_ref = state_db.as_ref().ok_or(AppError::NoLibrary)?;
+    let library_id = library_id_for_root(db_ref, &state, &dir_str)?;
 
     // Phase 1: Check hot cache (directory scanned < 60s ago)
     if let Ok(Some(last_scanned)) = db_ref.get_directory_scan_time(&dir_str) {
@@ -819,8 +3558,12 @@ pub async fn scan_directory(
     let db_path_clone = db_path.clone();
 
     tauri::async_runtime::spawn(async move {
+        let _scan = ScanGuard::begin(&app_clone).await;
         // Collect file paths first (fast)
-        let paths = scan::collect_media_paths(&std::path::PathBuf::from(&dir_str_clone));
+        let paths = scan::collect_media_paths(
+            &std::path::PathBuf::from(&dir_str_clone),
+            &scan::ScanOptions::default(),
+        );
         let total = paths.len();
 
         let _ = app_clone.emit("scan-started", serde_json::json!({
@@ -846,14 +3589,7 @@ pub async fn scan_directory(
             }
         };
 
-        let library_id = match bg_db.get_or_create_library(&dir_str_clone) {
-            Ok(id) => id,
-            Err(e) => {
-                eprintln!("  ⚠ Failed to get library: {}", e);
-                return;
-            }
-        };
-
+        let flatten = bg_db.library_flattens_folders(library_id).unwrap_or(false);
         let mut errors = 0usize;
 
         for path in &paths {
@@ -889,7 +3625,25 @@ pub async fn scan_directory(
             }
 
             // Need to generate/regenerate thumbnail
-            let scanned = scan::build_scanned_file_light(path, &root);
+            let scanned = scan::build_scanned_file_light(path, &root, flatten);
+
+            // Placeholders are indexed without a thumbnail; decoding one would download it.
+            // With no thumbnail cached they are re-checked on the next scan.
+            if let Some(sf) = scanned.as_ref().filter(|sf| sf.is_placeholder) {
+                let _ = bg_db.upsert_photo_with_thumb(library_id, sf, "", 0, 0, current_mtime);
+                let info = thumb::ThumbnailInfo {
+                    original_path: path_str,
+                    thumb_path: String::new(),
+                    width: 0,
+                    height: 0,
+                    filename: sf.filename.clone(),
+                    file_size: sf.size_bytes as u64,
+                    date_modified: current_mtime,
+                    error: true,
+                };
+                let _ = app_clone.emit("thumb-ready", &info);
+                continue;
+            }
 
             let info = thumb::get_or_create_thumbnail_info(
                 &path_str,
@@ -941,7 +3695,7 @@ pub struct SystemInfo {
 }
 
 #[tauri::command]
-pub async fn get_system_info() -> Result<SystemInfo, String> {
+pub async fn get_system_info() -> Result<SystemInfo, AppError> {
     use sysinfo::System;
     let mut sys = System::new_all();
     sys.refresh_all();
@@ -963,11 +3717,11 @@ pub async fn save_edit_params(
     state: tauri::State<'_, AppState>,
     photo_path: String,
     params_json: String,
-) -> Result<(), String> {
-    let db = state.db.lock().unwrap();
+) -> Result<(), AppError> {
+    let db = ensure_db(&state)?;
     if let Some(ref db) = *db {
         db.save_edit_params(&photo_path, &params_json)
-            .map_err(|e| format!("Failed to save edit params: {}", e))?;
+            .map_err(|e| AppError::Database(format!("Failed to save edit params: {}", e)))?;
     }
     Ok(())
 }
@@ -976,12 +3730,608 @@ pub async fn save_edit_params(
 pub async fn load_edit_params(
     state: tauri::State<'_, AppState>,
     photo_path: String,
-) -> Result<Option<String>, String> {
-    let db = state.db.lock().unwrap();
+) -> Result<Option<String>, AppError> {
+    let db = ensure_db(&state)?;
     if let Some(ref db) = *db {
         db.load_edit_params(&photo_path)
-            .map_err(|e| format!("Failed to load edit params: {}", e))
+            .map_err(|e| AppError::Database(format!("Failed to load edit params: {}", e)))
     } else {
         Ok(None)
     }
 }
+
+// ── Scan Settings ──
+
+/// Override the recognized photo/video extensions. An empty list restores the built-in defaults.
+/// Takes effect on the next scan; re-index a library to pick up newly recognized files.
+#[tauri::command]
+pub async fn set_media_extensions(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    photo_exts: Vec<String>,
+    video_exts: Vec<String>,
+) -> Result<scan::MediaExtensions, AppError> {
+    let photo = scan::normalize_extensions(&photo_exts);
+    let video = scan::normalize_extensions(&video_exts);
+
+    let persist = |db: &Database| -> Result<(), AppError> {
+        for (key, exts) in [(SETTING_PHOTO_EXTENSIONS, &photo), (SETTING_VIDEO_EXTENSIONS, &video)] {
+            if exts.is_empty() {
+                db.delete_setting(key)?;
+            } else {
+                let json = serde_json::to_string(exts)?;
+                db.set_setting(key, &json)?;
+            }
+        }
+        Ok(())
+    };
+
+    {
+        let db_guard = lock(&state.db);
+        match db_guard.as_ref() {
+            Some(db) => persist(db)?,
+            None => persist(&open_database(&app)?)?,
+        }
+    }
+
+    scan::set_media_extensions(Some(photo), Some(video));
+    Ok(scan::current_media_extensions())
+}
+
+#[tauri::command]
+pub async fn get_media_extensions() -> Result<scan::MediaExtensions, AppError> {
+    Ok(scan::current_media_extensions())
+}
+
+/// Set glob patterns (`*`, `?`) for files and folders the scanner skips. Patterns without a `/`
+/// match entry names; patterns with one match paths relative to the library root.
+/// An empty list restores the defaults.
+#[tauri::command]
+pub async fn set_ignore_patterns(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    patterns: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    let current = scan::normalize_ignore_patterns(&patterns);
+
+    let persist = |db: &Database| -> Result<(), AppError> {
+        if current == scan::default_ignore_patterns() {
+            db.delete_setting(SETTING_IGNORE_PATTERNS).map_err(AppError::from)
+        } else {
+            let json = serde_json::to_string(&current)?;
+            db.set_setting(SETTING_IGNORE_PATTERNS, &json).map_err(AppError::from)
+        }
+    };
+
+    {
+        let db_guard = lock(&state.db);
+        match db_guard.as_ref() {
+            Some(db) => persist(db)?,
+            None => persist(&open_database(&app)?)?,
+        }
+    }
+
+    scan::set_ignore_patterns(Some(current.clone()));
+    Ok(current)
+}
+
+#[tauri::command]
+pub async fn get_ignore_patterns() -> Result<Vec<String>, AppError> {
+    Ok(scan::current_ignore_patterns())
+}
+
+/// Choose the folders `scan_default_directories` indexes. Paths must be absolute; an empty
+/// list restores the OS's Pictures, Downloads and Documents folders. Returns the effective list.
+#[tauri::command]
+pub async fn set_default_scan_dirs(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    dirs: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for dir in dirs.iter().map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        if !std::path::Path::new(dir).is_absolute() {
+            return Err(AppError::InvalidInput(format!("Not an absolute path: {}", dir)));
+        }
+        if !cleaned.iter().any(|c| c == dir) {
+            cleaned.push(dir.to_string());
+        }
+    }
+
+    let persist = |db: &Database| -> Result<Vec<String>, AppError> {
+        if cleaned.is_empty() {
+            db.delete_setting(SETTING_DEFAULT_SCAN_DIRS)?;
+        } else {
+            db.set_setting_json(SETTING_DEFAULT_SCAN_DIRS, &cleaned)?;
+        }
+        Ok(default_scan_dirs(&app, db)
+            .into_iter()
+            .map(|(_, p)| p.to_string_lossy().to_string())
+            .collect())
+    };
+
+    let db_guard = lock(&state.db);
+    match db_guard.as_ref() {
+        Some(db) => persist(db),
+        None => persist(&open_database(&app)?),
+    }
+}
+
+/// The folders `scan_default_directories` will index
+#[tauri::command]
+pub async fn get_default_scan_dirs(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let dirs = {
+        let db_guard = lock(&state.db);
+        match db_guard.as_ref() {
+            Some(db) => default_scan_dirs(&app, db),
+            None => default_scan_dirs(&app, &open_database(&app)?),
+        }
+    };
+    Ok(dirs.into_iter().map(|(_, p)| p.to_string_lossy().to_string()).collect())
+}
+
+/// Effective parallelism: metadata-extraction threads and concurrent thumbnail decodes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceOptions {
+    pub scan_threads: usize,
+    pub thumb_concurrency: usize,
+    pub default_scan_threads: usize,
+    pub default_thumb_concurrency: usize,
+}
+
+fn current_performance_options() -> PerformanceOptions {
+    PerformanceOptions {
+        scan_threads: scan::scan_threads(),
+        thumb_concurrency: thumb::thumb_concurrency(),
+        default_scan_threads: scan::default_scan_threads(),
+        default_thumb_concurrency: thumb::default_thumb_concurrency(),
+    }
+}
+
+/// Tune indexing speed against responsiveness. `None` restores the CPU-count default.
+/// Applies immediately; scans and thumbnails already running finish with the old limits.
+#[tauri::command]
+pub async fn set_performance_options(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    scan_threads: Option<usize>,
+    thumb_concurrency: Option<usize>,
+) -> Result<PerformanceOptions, AppError> {
+    for (name, value) in [("scanThreads", scan_threads), ("thumbConcurrency", thumb_concurrency)] {
+        if let Some(v) = value {
+            if v == 0 || v > MAX_PARALLELISM {
+                return Err(AppError::InvalidInput(format!(
+                    "{} must be between 1 and {}",
+                    name, MAX_PARALLELISM
+                )));
+            }
+        }
+    }
+
+    let persist = |db: &Database| -> Result<(), AppError> {
+        for (key, value) in [(SETTING_SCAN_THREADS, scan_threads), (SETTING_THUMB_CONCURRENCY, thumb_concurrency)] {
+            match value {
+                Some(v) => db.set_setting_json(key, &v)?,
+                None => db.delete_setting(key)?,
+            }
+        }
+        Ok(())
+    };
+
+    {
+        let db_guard = lock(&state.db);
+        match db_guard.as_ref() {
+            Some(db) => persist(db)?,
+            None => persist(&open_database(&app)?)?,
+        }
+    }
+
+    scan::set_scan_threads(scan_threads);
+    thumb::set_thumb_concurrency(thumb_concurrency);
+    Ok(current_performance_options())
+}
+
+#[tauri::command]
+pub async fn get_performance_options() -> Result<PerformanceOptions, AppError> {
+    Ok(current_performance_options())
+}
+
+/// Largest accepted unsharp-mask radius; beyond this thumbnails just look haloed
+const MAX_THUMB_SHARPEN: f32 = 5.0;
+
+/// Trade thumbnail speed for crispness: JPEG quality (default 75), a Lanczos3 final resize
+/// and an unsharp-mask radius. `None` restores that field's default. Thumbnails made with
+/// other settings are regenerated as they're next requested.
+#[tauri::command]
+pub async fn set_thumbnail_quality(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    jpeg_quality: Option<u8>,
+    lanczos: Option<bool>,
+    sharpen: Option<f32>,
+) -> Result<thumb::ThumbQuality, AppError> {
+    if jpeg_quality.is_some_and(|q| q == 0 || q > 100) {
+        return Err(AppError::InvalidInput("jpegQuality must be between 1 and 100".to_string()));
+    }
+    if sharpen.is_some_and(|s| !(0.0..=MAX_THUMB_SHARPEN).contains(&s)) {
+        return Err(AppError::InvalidInput(format!(
+            "sharpen must be between 0 and {}",
+            MAX_THUMB_SHARPEN
+        )));
+    }
+    let default = thumb::ThumbQuality::DEFAULT;
+    let quality = thumb::ThumbQuality {
+        jpeg_quality: jpeg_quality.unwrap_or(default.jpeg_quality),
+        lanczos: lanczos.unwrap_or(default.lanczos),
+        sharpen: sharpen.unwrap_or(default.sharpen),
+    };
+
+    let persist = |db: &Database| -> Result<(), AppError> {
+        if quality == default {
+            db.delete_setting(SETTING_THUMB_QUALITY)?;
+        } else {
+            db.set_setting_json(SETTING_THUMB_QUALITY, &quality)?;
+        }
+        Ok(())
+    };
+    match lock(&state.db).as_ref() {
+        Some(db) => persist(db)?,
+        None => persist(&open_database(&app)?)?,
+    }
+    thumb::set_thumb_quality(Some(quality));
+    Ok(quality)
+}
+
+#[tauri::command]
+pub async fn get_thumbnail_quality() -> Result<thumb::ThumbQuality, AppError> {
+    Ok(thumb::thumb_quality())
+}
+
+/// Opt in to animated grid thumbnails for GIF/WebP animations (off by default: they are
+/// much larger than stills)
+#[tauri::command]
+pub async fn set_animated_thumbnails(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<bool, AppError> {
+    let persist = |db: &Database| -> Result<(), AppError> {
+        if enabled {
+            db.set_setting_json(SETTING_ANIMATED_THUMBS, &true)?;
+        } else {
+            db.delete_setting(SETTING_ANIMATED_THUMBS)?;
+        }
+        Ok(())
+    };
+    match lock(&state.db).as_ref() {
+        Some(db) => persist(db)?,
+        None => persist(&open_database(&app)?)?,
+    }
+    thumb::set_animated_thumbs(Some(enabled));
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub async fn get_animated_thumbnails() -> Result<bool, AppError> {
+    Ok(thumb::animated_thumbs())
+}
+
+/// Choose which half of a RAW+JPEG pair is shown and thumbnailed: "jpeg" or "raw"
+#[tauri::command]
+pub async fn set_raw_preference(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    preference: String,
+) -> Result<String, AppError> {
+    let preference = preference.to_lowercase();
+    if preference != "jpeg" && preference != "raw" {
+        return Err(AppError::InvalidInput("Preference must be \"jpeg\" or \"raw\"".to_string()));
+    }
+    let json = serde_json::to_string(&preference)?;
+    let db_guard = lock(&state.db);
+    match db_guard.as_ref() {
+        Some(db) => db.set_setting(SETTING_RAW_PREFERENCE, &json),
+        None => open_database(&app)?.set_setting(SETTING_RAW_PREFERENCE, &json),
+    }?;
+    Ok(preference)
+}
+
+/// Whether files without an EXIF date are dated by their mtime (true, the default) or left
+/// undated. Applies to files indexed from now on.
+#[tauri::command]
+pub async fn set_mtime_date_fallback(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<bool, AppError> {
+    let persist = |db: &Database| -> Result<(), AppError> {
+        if enabled {
+            db.delete_setting(SETTING_MTIME_DATE_FALLBACK)?;
+        } else {
+            db.set_setting_json(SETTING_MTIME_DATE_FALLBACK, &false)?;
+        }
+        Ok(())
+    };
+    match lock(&state.db).as_ref() {
+        Some(db) => persist(db)?,
+        None => persist(&open_database(&app)?)?,
+    }
+    scan::set_mtime_date_fallback(Some(enabled));
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub async fn get_mtime_date_fallback() -> Result<bool, AppError> {
+    Ok(scan::mtime_date_fallback())
+}
+
+#[tauri::command]
+pub async fn get_raw_preference(state: State<'_, AppState>) -> Result<String, AppError> {
+    let db_guard = ensure_db(&state)?;
+    Ok(db_guard.as_ref().map(raw_preference).unwrap_or_else(|| "jpeg".to_string()))
+}
+
+// ── Generic settings ──
+
+/// Read any persisted setting as JSON; null when unset
+#[tauri::command]
+pub async fn get_setting(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    key: String,
+) -> Result<serde_json::Value, AppError> {
+    let db_guard = lock(&state.db);
+    let value = match db_guard.as_ref() {
+        Some(db) => db.get_setting_json(&key),
+        None => open_database(&app)?.get_setting_json(&key),
+    };
+    Ok(value.unwrap_or(serde_json::Value::Null))
+}
+
+/// Persist a free-form UI setting (any JSON value; null removes it). Scanner and
+/// performance settings have their own commands and are refused here.
+#[tauri::command]
+pub async fn set_setting(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    key: String,
+    value: serde_json::Value,
+) -> Result<(), AppError> {
+    let key = key.trim();
+    if key.is_empty() || key.len() > MAX_SETTING_KEY_LEN {
+        return Err(AppError::InvalidInput(format!(
+            "Setting keys must be 1-{} characters",
+            MAX_SETTING_KEY_LEN
+        )));
+    }
+    if MANAGED_SETTINGS.contains(&key) {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' is managed by its own command",
+            key
+        )));
+    }
+    let persist = |db: &Database| -> Result<(), AppError> {
+        if value.is_null() {
+            db.delete_setting(key)?;
+        } else {
+            db.set_setting_json(key, &value)?;
+        }
+        Ok(())
+    };
+    let db_guard = lock(&state.db);
+    match db_guard.as_ref() {
+        Some(db) => persist(db),
+        None => persist(&open_database(&app)?),
+    }
+}
+
+// ── Database Location ──
+
+/// Where the catalog file lives
+#[tauri::command]
+pub async fn get_database_location(state: State<'_, AppState>) -> Result<String, AppError> {
+    Ok(lock(&state.db_path).to_string_lossy().to_string())
+}
+
+/// Move the catalog into directory `path` and use it from now on, including after restart.
+/// The connection is closed, the file copied and opened from its new place before the old
+/// copy is deleted; on any failure the old catalog stays in use. Refused during a scan.
+#[tauri::command]
+pub async fn set_database_location(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<String, AppError> {
+    if state.active_scans.load(Ordering::SeqCst) > 0 {
+        return Err(AppError::Conflict("Can't move the catalog while a scan is running".to_string()));
+    }
+    let dir = std::path::PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(AppError::InvalidInput(format!("Not a directory: {}", path)));
+    }
+    let probe = dir.join(".photo_sorter_write_test");
+    std::fs::write(&probe, b"")
+        .map_err(|e| AppError::InvalidInput(format!("{} is not writable: {}", path, e)))?;
+    let _ = std::fs::remove_file(&probe);
+    let new_path = dir.canonicalize().unwrap_or(dir).join(DB_FILE_NAME);
+
+    // Hold both locks for the whole move: commands and scans starting meanwhile wait, then
+    // see the new location
+    let mut db_guard = lock(&state.db);
+    let mut path_guard = lock(&state.db_path);
+    let old_path = path_guard.clone();
+    if old_path.canonicalize().unwrap_or_else(|_| old_path.clone()) == new_path {
+        return Ok(new_path.to_string_lossy().to_string());
+    }
+    if new_path.exists() {
+        return Err(AppError::Conflict(format!("A catalog already exists at {}", new_path.display())));
+    }
+
+    let had_catalog = old_path.exists();
+    let new_db = if had_catalog {
+        // Dropping the connection closes it; `ensure_db` reopens the old file if we bail out
+        *db_guard = None;
+        std::fs::copy(&old_path, &new_path).map_err(|e| {
+            let _ = std::fs::remove_file(&new_path);
+            AppError::Io(format!("Failed to copy catalog: {}", e))
+        })?;
+        match Database::new(&new_path) {
+            Ok(db) => Some(db),
+            Err(e) => {
+                let _ = std::fs::remove_file(&new_path);
+                return Err(AppError::Database(format!("Copied catalog failed to open: {}", e)));
+            }
+        }
+    } else {
+        None
+    };
+
+    let data_dir = app.path().app_data_dir()?;
+    let persisted = std::fs::create_dir_all(&data_dir)
+        .and_then(|_| std::fs::write(data_dir.join(DB_LOCATION_FILE), new_path.to_string_lossy().as_bytes()));
+    if let Err(e) = persisted {
+        if had_catalog {
+            drop(new_db);
+            let _ = std::fs::remove_file(&new_path);
+        }
+        return Err(AppError::Io(format!("Failed to save catalog location: {}", e)));
+    }
+
+    *path_guard = new_path.clone();
+    if let Some(db) = new_db {
+        apply_scan_settings(&db);
+        *db_guard = Some(db);
+        if let Err(e) = std::fs::remove_file(&old_path) {
+            eprintln!("  ⚠ Moved catalog but could not delete {}: {}", old_path.display(), e);
+        }
+    }
+    eprintln!("✓ Catalog location set to {}", new_path.display());
+    Ok(new_path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{scanned, test_db};
+    use crate::db::PhotoFilter;
+
+    #[test]
+    fn all_libraries_view_lists_every_open_library() {
+        let db = test_db("all-libraries");
+        let first = db.get_or_create_library("/library").unwrap();
+        let second = db.get_or_create_library("/other").unwrap();
+        db.insert_photos_batch(first, &[scanned("a.jpg", Some("2021-01-01T10:00:00Z"), None)])
+            .unwrap();
+        let mut other = scanned("b.jpg", Some("2021-01-02T10:00:00Z"), None);
+        other.path = "/other/b.jpg".to_string();
+        db.insert_photos_batch(second, &[other]).unwrap();
+
+        let mut libraries = OpenLibraries::default();
+        libraries.open(first, "/library".to_string());
+        libraries.open(second, "/other".to_string());
+        assert_eq!(libraries.ids(), vec![first, second]);
+
+        let photos = db.get_photos(&libraries.ids(), 100, 0, &PhotoFilter::default()).unwrap();
+        let mut paths: Vec<&str> = photos.iter().map(|p| p.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/library/a.jpg", "/other/b.jpg"]);
+    }
+
+    #[test]
+    fn sequential_adds_keep_both_libraries_and_the_first_active() {
+        let mut libraries = OpenLibraries::default();
+        libraries.add(1, "/first".to_string());
+        libraries.add(2, "/second".to_string());
+        assert_eq!(libraries.ids(), vec![1, 2]);
+        assert_eq!(libraries.active, Some(1));
+        assert_eq!(libraries.active_root(), Some("/first"));
+
+        // Adding again is a no-op
+        libraries.add(2, "/second".to_string());
+        assert_eq!(libraries.ids(), vec![1, 2]);
+    }
+
+    #[test]
+    fn open_activates_without_duplicating() {
+        let mut libraries = OpenLibraries::default();
+        libraries.add(1, "/first".to_string());
+        libraries.open(2, "/second".to_string());
+        assert_eq!(libraries.active, Some(2));
+        libraries.open(1, "/first".to_string());
+        assert_eq!(libraries.ids(), vec![1, 2]);
+        assert_eq!(libraries.active, Some(1));
+    }
+
+    #[test]
+    fn merge_keeps_open_libraries_and_the_active_one() {
+        let mut libraries = OpenLibraries::default();
+        libraries.open(2, "/second".to_string());
+        libraries.merge(vec![(1, "/first".to_string()), (2, "/second".to_string()), (3, "/third".to_string())]);
+        assert_eq!(libraries.ids(), vec![2, 1, 3]);
+        assert_eq!(libraries.active, Some(2));
+
+        let mut empty = OpenLibraries::default();
+        empty.merge(vec![(4, "/fourth".to_string())]);
+        assert_eq!(empty.active, Some(4));
+    }
+
+    #[test]
+    fn default_scan_skips_a_missing_folder() {
+        let missing = std::env::temp_dir().join(format!("ifoto-missing-{}", std::process::id()));
+        let present = std::env::temp_dir();
+        let dirs = vec![("Gone".to_string(), missing.clone()), ("Here".to_string(), present.clone())];
+
+        let mut indexed = Vec::new();
+        let (results, libraries, _) = tauri::async_runtime::block_on(index_each_dir(&dirs, |name, path| {
+            indexed.push(path);
+            async move { Ok((7, None, serde_json::json!({ "name": name }))) }
+        }));
+
+        assert_eq!(indexed, vec![present.clone()]);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["missing"], true);
+        assert_eq!(results[0]["path"], missing.to_string_lossy().as_ref());
+        assert_eq!(results[1]["name"], "Here");
+        assert_eq!(libraries, vec![(7, present.to_string_lossy().to_string())]);
+    }
+
+    #[test]
+    fn filenames_may_contain_dots_but_not_be_dot_entries() {
+        assert!(validate_filename("trip..final.jpg").is_ok());
+        assert!(validate_filename("..hidden.jpg").is_ok());
+        assert!(validate_filename(".").is_err());
+        assert!(validate_filename("..").is_err());
+        assert!(validate_filename("../escape.jpg").is_err());
+        assert!(validate_filename("a\\b.jpg").is_err());
+    }
+
+    #[test]
+    fn folder_rescan_only_counts_vanished_files_as_gone() {
+        let folder = std::env::temp_dir().join(format!("ifoto-diff-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        // Still on disk, but not a media file, so the walk skips it
+        let skipped = folder.join("notes.txt");
+        std::fs::write(&skipped, b"x").unwrap();
+        let vanished = folder.join("vanished.jpg");
+        let row = |id| crate::db::IndexedFile {
+            id,
+            modified_at: "2020-01-01T00:00:00Z".to_string(),
+            size_bytes: 1,
+            motion_path: None,
+            is_placeholder: false,
+        };
+        let existing = std::collections::HashMap::from([
+            (skipped.to_string_lossy().to_string(), row(1)),
+            (vanished.to_string_lossy().to_string(), row(2)),
+        ]);
+
+        let (changed, _, gone) = diff_folder(&folder, &scan::ScanOptions::default(), &existing);
+        let _ = std::fs::remove_dir_all(&folder);
+        assert!(changed.is_empty());
+        assert_eq!(gone, vec![2]);
+    }
+}
@@ -1,8 +1,9 @@
+use crate::search::{SearchFilter, SearchQuery};
 use chrono::Utc;
 use rusqlite::{Connection, Result as SqlResult};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
 
 pub struct Database {
     conn: Mutex<Connection>,
@@ -35,9 +36,217 @@ pub struct PhotoRecord {
     pub iso: Option<i32>,
     pub shutter_speed: Option<String>,
     pub aperture: Option<String>,
+    /// Exposure time in seconds and f-number, parsed for numeric filtering
+    pub shutter_seconds: Option<f64>,
+    pub f_number: Option<f64>,
     pub focal_length: Option<String>,
     pub gps_lat: Option<f64>,
     pub gps_lon: Option<f64>,
+    /// Raw EXIF orientation (1-8); the viewer applies the matching CSS transform
+    pub orientation: Option<i32>,
+    /// Exposure compensation in EV
+    pub exposure_bias: Option<f64>,
+    /// Local-time offset of `taken_at` (which is stored in UTC); None means unknown
+    pub utc_offset_minutes: Option<i32>,
+    /// Compact blurred placeholder shown until the thumbnail loads (optional at scan time)
+    pub blurhash: Option<String>,
+    /// "photo", "screenshot" or "document" (scan heuristic, overridable per photo)
+    pub category: Option<String>,
+    /// `taken_at` was inferred from neighbouring files rather than read from EXIF
+    #[serde(default)]
+    pub date_is_estimated: bool,
+    /// Paired Live Photo video (same folder and base name), played on hover
+    pub motion_path: Option<String>,
+    /// RAW+JPEG pair linkage: both members carry both paths; None when unpaired
+    pub raw_path: Option<String>,
+    pub jpeg_path: Option<String>,
+    /// The file wasn't on disk at the last `reconcile_library`; the row is kept until purged
+    #[serde(default)]
+    pub is_missing: bool,
+    /// Gamut from the embedded colour profile ("srgb", "display_p3", "adobe_rgb", "prophoto",
+    /// "rec2020" or "other"); None for videos and rows indexed before detection existed
+    pub color_space: Option<String>,
+    /// PQ/HLG transfer or more than 8 bits per channel
+    #[serde(default)]
+    pub is_hdr: bool,
+    /// IPTC/XMP title and caption set by other photo tools
+    pub title: Option<String>,
+    pub caption: Option<String>,
+    /// Full images in a HEIF container (bursts, sequences) or frames of a GIF/WebP;
+    /// None for other formats
+    pub frame_count: Option<i32>,
+    /// Animated GIF or WebP; `get_animated_thumbnail` renders a moving preview
+    #[serde(default)]
+    pub is_animated: bool,
+    /// Where `taken_at` came from: "exif", "sidecar", "mtime", "estimated" or "manual";
+    /// None when undated
+    pub date_source: Option<String>,
+    /// Cloud-sync placeholder not downloaded at the last scan (no metadata or thumbnail)
+    #[serde(default)]
+    pub is_placeholder: bool,
+    /// Grid thumbnail already cached at the current quality; only filled by the paged listings
+    /// (`get_photos`, `get_all_photos`), so the UI can show the blurhash until it exists
+    #[serde(default)]
+    pub thumbnail_exists: bool,
+    /// Displayed width / height (EXIF rotation applied); None when dimensions are unknown
+    pub aspect_ratio: Option<f64>,
+    pub megapixels: Option<f64>,
+}
+
+impl PhotoRecord {
+    /// Fill the fields derived from the stored width/height/orientation
+    fn with_derived(mut self) -> Self {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) if w > 0 && h > 0 => {
+                let (w, h) = if matches!(self.orientation, Some(5..=8)) { (h, w) } else { (w, h) };
+                self.aspect_ratio = Some(w as f64 / h as f64);
+                self.megapixels = Some((w as f64 * h as f64 / 10_000.0).round() / 100.0);
+            }
+            _ => {
+                self.aspect_ratio = None;
+                self.megapixels = None;
+            }
+        }
+        self
+    }
+}
+
+/// Narrows a search to one album or one folder
+#[derive(Debug, Clone)]
+pub enum SearchScope {
+    Album(i64),
+    /// A `folder_rel`; its subfolders are included
+    Folder(String),
+}
+
+/// Optional filters shared by the grid listing and the slideshow
+#[derive(Debug, Clone, Default)]
+pub struct PhotoFilter<'a> {
+    pub year: Option<i32>,
+    pub month: Option<i32>,
+    pub folder_rel: Option<&'a str>,
+    pub media_type: Option<&'a str>,
+    pub category: Option<&'a str>,
+    /// Collapse RAW+JPEG pairs to the preferred member ("raw" or "jpeg"); None lists both
+    pub pair_preference: Option<&'a str>,
+    /// Displayed shape: "portrait", "landscape" or "square"
+    pub shape: Option<&'a str>,
+    pub min_megapixels: Option<f64>,
+    pub iso: Option<NumericRange>,
+    pub shutter_seconds: Option<NumericRange>,
+    pub f_number: Option<NumericRange>,
+    /// A colour space name, or "wide" for any gamut wider than sRGB
+    pub color_space: Option<&'a str>,
+    pub hdr: Option<bool>,
+    /// Only cloud placeholders (true) or only downloaded files (false)
+    pub placeholder: Option<bool>,
+}
+
+/// Columns `set_photo_field` may change in bulk
+pub const BULK_EDIT_FIELDS: &[&str] = &["camera_make", "camera_model", "lens", "title", "caption"];
+
+/// Inclusive numeric bounds; either end may be left open
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct NumericRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl NumericRange {
+    pub fn is_valid(&self) -> bool {
+        self.min.map_or(true, f64::is_finite) && self.max.map_or(true, f64::is_finite)
+    }
+}
+
+/// A search hit with its relevance score (photo fields are flattened alongside)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub photo: PhotoRecord,
+    pub score: f64,
+}
+
+/// Photos grouped into one map cell: centroid pin, bounding box and a cover photo
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationCluster {
+    pub lat: f64,
+    pub lon: f64,
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+    pub count: i64,
+    /// Most recent photo in the cluster
+    pub cover_id: i64,
+    pub cover_path: String,
+}
+
+/// Outcome of adding a selection to one album
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumAddResult {
+    pub album_id: i64,
+    pub added: i64,
+    pub already_present: i64,
+}
+
+/// Photos sharing a filename across different folders
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilenameCollision {
+    pub filename: String,
+    pub photos: Vec<CollisionPhoto>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollisionPhoto {
+    pub id: i64,
+    pub path: String,
+    pub folder_rel: String,
+    pub size_bytes: i64,
+}
+
+/// Photos under a size threshold, with what trashing them all would free
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmallImages {
+    pub photos: Vec<PhotoRecord>,
+    pub count: usize,
+    pub total_bytes: i64,
+}
+
+/// What the index knows about a file, compared against disk when re-indexing a folder
+#[derive(Debug, Clone)]
+pub struct IndexedFile {
+    pub id: i64,
+    pub modified_at: String,
+    pub size_bytes: i64,
+    /// Live Photo clip folded into this row (it has no row of its own)
+    pub motion_path: Option<String>,
+    pub is_placeholder: bool,
+}
+
+/// One photo in a catalog export: every record field plus tag and album membership
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    #[serde(flatten)]
+    pub photo: PhotoRecord,
+    pub tags: Vec<String>,
+    pub albums: Vec<String>,
+}
+
+/// A geotagged photo for GPX/KML export
+#[derive(Debug, Clone)]
+pub struct GeoPhoto {
+    pub path: String,
+    pub filename: String,
+    pub taken_at: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -47,6 +256,20 @@ pub struct LibraryInfo {
     pub root_path: String,
     pub name: String,
     pub photo_count: i64,
+    /// `folder_rel` holds only the immediate parent folder name (set at index time)
+    pub flatten_folders: bool,
+}
+
+/// Sidebar badge counts. Everything except `trash` excludes soft-deleted photos.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStats {
+    pub total: i64,
+    pub photos: i64,
+    pub videos: i64,
+    pub favorites: i64,
+    pub trash: i64,
+    pub with_gps: i64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -64,6 +287,33 @@ pub struct CategoryMonth {
     pub count: i64,
 }
 
+/// A person in the People view (see `get_face_clusters`)
+#[cfg(feature = "faces")]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaceCluster {
+    pub cluster_id: i64,
+    pub photo_count: i64,
+    pub cover_photo_id: Option<i64>,
+}
+
+/// Fresh counts for the timeline buckets touched by a date edit (see `get_category_deltas`)
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryDeltas {
+    pub years: Vec<CategoryYear>,
+    pub months: Vec<CategoryMonth>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryDay {
+    pub year: i32,
+    pub month: i32,
+    pub day: i32,
+    pub count: i64,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
@@ -74,6 +324,28 @@ pub struct FolderNode {
     pub children: Vec<FolderNode>,
 }
 
+/// Representative photo for a folder or timeline period
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverPhoto {
+    pub id: i64,
+    pub path: String,
+}
+
+/// How many ranked candidates per group are considered when picking a cover
+const COVER_CANDIDATES: i64 = 5;
+
+/// Cover ordering term: rows with a stored thumbnail first
+const HAS_THUMB: &str = "(thumb_path IS NOT NULL AND thumb_path != '') DESC";
+
+/// Year and month covers for the timeline, keyed "2023" and "2023-05"
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineCovers {
+    pub years: std::collections::BTreeMap<String, CoverPhoto>,
+    pub months: Option<std::collections::BTreeMap<String, CoverPhoto>>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TagRecord {
@@ -90,9 +362,101 @@ pub struct AlbumRecord {
     pub created_at: String,
     pub photo_count: i64,
     pub cover_path: Option<String>,
+    /// Containing album for nested albums; None at the top level
+    pub parent_album_id: Option<i64>,
+}
+
+/// Summary for an album header ("142 photos · 3.1 GB · Jun–Aug 2023")
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumStats {
+    pub photo_count: i64,
+    pub total_bytes: i64,
+    /// Earliest and latest `taken_at`; None when no photo in the album is dated
+    pub earliest: Option<String>,
+    pub latest: Option<String>,
+    /// Count per media type ("photo", "video")
+    pub media_types: std::collections::BTreeMap<String, i64>,
+}
+
+/// An album with its sub-albums, for the sidebar tree
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumNode {
+    #[serde(flatten)]
+    pub album: AlbumRecord,
+    pub children: Vec<AlbumNode>,
+}
+
+/// Capture time shifted into the photo's local time, so year/month/day grouping matches
+/// the wall clock where it was taken (23:30 in Tokyo stays on that day, not the next UTC one)
+const LOCAL_DATE: &str =
+    "datetime(COALESCE(taken_at, modified_at), printf('%+d minutes', COALESCE(utc_offset_minutes, 0)))";
+
+/// Filename ordering that compares digit runs numerically ("IMG_9" < "IMG_10")
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    fn chunks(s: &str) -> Vec<(bool, String)> {
+        let mut out: Vec<(bool, String)> = Vec::new();
+        for c in s.to_lowercase().chars() {
+            let digit = c.is_ascii_digit();
+            match out.last_mut() {
+                Some((d, run)) if *d == digit => run.push(c),
+                _ => out.push((digit, c.to_string())),
+            }
+        }
+        out
+    }
+    let (ca, cb) = (chunks(a), chunks(b));
+    for (x, y) in ca.iter().zip(cb.iter()) {
+        let ord = match (x, y) {
+            ((true, nx), (true, ny)) => {
+                let (tx, ty) = (nx.trim_start_matches('0'), ny.trim_start_matches('0'));
+                tx.len().cmp(&ty.len()).then_with(|| tx.cmp(ty))
+            }
+            _ => x.1.cmp(&y.1),
+        };
+        if ord != std::cmp::Ordering::Equal {
+            return ord;
+        }
+    }
+    ca.len().cmp(&cb.len())
+}
+
+/// Non-numeric lead of a filename ("IMG_" for "IMG_0042.jpg"); files share a sequence
+/// when their leads match
+fn sequence_prefix(filename: &str) -> String {
+    filename
+        .chars()
+        .take_while(|c| !c.is_ascii_digit())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Display name for a library: the last component of its root path
+fn library_name(root_path: &str) -> String {
+    Path::new(root_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Library")
+        .to_string()
 }
 
 impl Database {
+    /// Lock the connection, recovering if a panic poisoned the mutex — the connection itself
+    /// is still usable, and one crashed command shouldn't fail every later one. A transaction
+    /// the panicking thread left open is rolled back first.
+    fn lock_conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|poisoned| {
+            eprintln!("  ⚠ Database lock was poisoned by a panic; recovering");
+            self.conn.clear_poison();
+            let conn = poisoned.into_inner();
+            if !conn.is_autocommit() {
+                let _ = conn.execute_batch("ROLLBACK");
+            }
+            conn
+        })
+    }
+
     pub fn new(db_path: &Path) -> SqlResult<Self> {
         let conn = Connection::open(db_path)?;
         let db = Database {
@@ -103,7 +467,7 @@ impl Database {
     }
 
     fn init_schema(&self) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         conn.execute_batch(
             r#"
             CREATE TABLE IF NOT EXISTS library (
@@ -135,6 +499,15 @@ impl Database {
                 focal_length TEXT,
                 gps_lat REAL,
                 gps_lon REAL,
+                orientation INTEGER,
+                exposure_bias REAL,
+                utc_offset_minutes INTEGER,
+                blurhash TEXT,
+                category TEXT,
+                date_is_estimated INTEGER NOT NULL DEFAULT 0,
+                motion_path TEXT,
+                raw_path TEXT,
+                jpeg_path TEXT,
                 UNIQUE(library_id, path),
                 FOREIGN KEY (library_id) REFERENCES library(id)
             );
@@ -158,7 +531,8 @@ impl Database {
             CREATE TABLE IF NOT EXISTS albums (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 name TEXT NOT NULL,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                parent_album_id INTEGER REFERENCES albums(id)
             );
             CREATE TABLE IF NOT EXISTS album_photos (
                 album_id INTEGER NOT NULL,
@@ -176,6 +550,43 @@ impl Database {
                 photo_count   INTEGER NOT NULL
             );
 
+            -- Key/value app settings (JSON-encoded values)
+            CREATE TABLE IF NOT EXISTS settings (
+                key   TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+
+            -- "Recents" shelf: last opened time per photo, capped in mark_viewed
+            CREATE TABLE IF NOT EXISTS recently_viewed (
+                photo_id  INTEGER PRIMARY KEY,
+                viewed_at TEXT NOT NULL,
+                FOREIGN KEY (photo_id) REFERENCES photos(id) ON DELETE CASCADE
+            );
+
+            -- Files whose parse overran the per-file scan timeout, kept for a later retry
+            CREATE TABLE IF NOT EXISTS scan_timeouts (
+                library_id   INTEGER NOT NULL,
+                path         TEXT    NOT NULL,
+                timed_out_at TEXT    NOT NULL,
+                PRIMARY KEY (library_id, path)
+            );
+
+            -- Faces found by the `faces` feature: box as fractions of the image, embedding as
+            -- little-endian f32s, and the person cluster last assigned by `group_faces`
+            CREATE TABLE IF NOT EXISTS faces (
+                id         INTEGER PRIMARY KEY,
+                photo_id   INTEGER NOT NULL,
+                x          REAL    NOT NULL,
+                y          REAL    NOT NULL,
+                w          REAL    NOT NULL,
+                h          REAL    NOT NULL,
+                embedding  BLOB    NOT NULL,
+                cluster_id INTEGER,
+                FOREIGN KEY (photo_id) REFERENCES photos(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_faces_photo   ON faces(photo_id);
+            CREATE INDEX IF NOT EXISTS idx_faces_cluster ON faces(cluster_id);
+
             CREATE INDEX IF NOT EXISTS idx_photos_file_path     ON photos(path);
             CREATE INDEX IF NOT EXISTS idx_photos_date_modified ON photos(modified_at);
             "#,
@@ -209,6 +620,29 @@ impl Database {
             ("thumb_path", "ALTER TABLE photos ADD COLUMN thumb_path TEXT"),
             ("date_modified_unix", "ALTER TABLE photos ADD COLUMN date_modified_unix INTEGER NOT NULL DEFAULT 0"),
             ("edit_params", "ALTER TABLE photos ADD COLUMN edit_params TEXT"),
+            ("orientation", "ALTER TABLE photos ADD COLUMN orientation INTEGER"),
+            ("exposure_bias", "ALTER TABLE photos ADD COLUMN exposure_bias REAL"),
+            ("utc_offset_minutes", "ALTER TABLE photos ADD COLUMN utc_offset_minutes INTEGER"),
+            ("blurhash", "ALTER TABLE photos ADD COLUMN blurhash TEXT"),
+            ("category", "ALTER TABLE photos ADD COLUMN category TEXT"),
+            ("date_is_estimated", "ALTER TABLE photos ADD COLUMN date_is_estimated INTEGER NOT NULL DEFAULT 0"),
+            ("motion_path", "ALTER TABLE photos ADD COLUMN motion_path TEXT"),
+            ("raw_path", "ALTER TABLE photos ADD COLUMN raw_path TEXT"),
+            ("jpeg_path", "ALTER TABLE photos ADD COLUMN jpeg_path TEXT"),
+            ("burst_hash", "ALTER TABLE photos ADD COLUMN burst_hash INTEGER"),
+            ("sharpness", "ALTER TABLE photos ADD COLUMN sharpness REAL"),
+            ("is_missing", "ALTER TABLE photos ADD COLUMN is_missing INTEGER NOT NULL DEFAULT 0"),
+            ("shutter_seconds", "ALTER TABLE photos ADD COLUMN shutter_seconds REAL"),
+            ("f_number", "ALTER TABLE photos ADD COLUMN f_number REAL"),
+            ("color_space", "ALTER TABLE photos ADD COLUMN color_space TEXT"),
+            ("is_hdr", "ALTER TABLE photos ADD COLUMN is_hdr INTEGER NOT NULL DEFAULT 0"),
+            ("title", "ALTER TABLE photos ADD COLUMN title TEXT"),
+            ("caption", "ALTER TABLE photos ADD COLUMN caption TEXT"),
+            ("frame_count", "ALTER TABLE photos ADD COLUMN frame_count INTEGER"),
+            ("date_source", "ALTER TABLE photos ADD COLUMN date_source TEXT"),
+            ("is_placeholder", "ALTER TABLE photos ADD COLUMN is_placeholder INTEGER NOT NULL DEFAULT 0"),
+            ("is_animated", "ALTER TABLE photos ADD COLUMN is_animated INTEGER NOT NULL DEFAULT 0"),
+            ("edited_fields", "ALTER TABLE photos ADD COLUMN edited_fields TEXT NOT NULL DEFAULT ''"),
         ];
 
         for (col, sql) in migrations {
@@ -217,133 +651,345 @@ impl Database {
                 eprintln!("  ➕ Migrated: added column {}", col);
             }
         }
+        if !columns.contains(&"date_source".to_string()) {
+            // Best guess for existing rows: an mtime fallback stored the mtime verbatim
+            conn.execute(
+                "UPDATE photos SET date_source = CASE
+                     WHEN taken_at IS NULL THEN NULL
+                     WHEN date_is_estimated = 1 THEN 'estimated'
+                     WHEN taken_at = modified_at THEN 'mtime'
+                     ELSE 'exif' END",
+                [],
+            )?;
+        }
+
+        let album_columns: Vec<String> = conn
+            .prepare("PRAGMA table_info(albums)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|r| r.ok())
+            .collect();
+        if !album_columns.contains(&"parent_album_id".to_string()) {
+            conn.execute("ALTER TABLE albums ADD COLUMN parent_album_id INTEGER REFERENCES albums(id)", [])?;
+            eprintln!("  ➕ Migrated: added column albums.parent_album_id");
+        }
+
+        let library_columns: Vec<String> = conn
+            .prepare("PRAGMA table_info(library)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|r| r.ok())
+            .collect();
+        if !library_columns.contains(&"flatten_folders".to_string()) {
+            conn.execute("ALTER TABLE library ADD COLUMN flatten_folders INTEGER NOT NULL DEFAULT 0", [])?;
+            eprintln!("  ➕ Migrated: added column library.flatten_folders");
+        }
+
+        // Indexes on migrated columns can only be created once the columns exist
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_photos_gps ON photos(gps_lat, gps_lon);",
+        )?;
+        Ok(())
+    }
+
+    /// Whether a library groups photos by immediate parent folder only
+    pub fn library_flattens_folders(&self, library_id: i64) -> SqlResult<bool> {
+        let conn = self.lock_conn();
+        let flatten: Option<i32> = conn
+            .query_row("SELECT flatten_folders FROM library WHERE id = ?1", [library_id], |row| row.get(0))
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+        Ok(flatten.unwrap_or(0) != 0)
+    }
+
+    pub fn set_library_flatten_folders(&self, library_id: i64, flatten: bool) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute(
+            "UPDATE library SET flatten_folders = ?1 WHERE id = ?2",
+            rusqlite::params![flatten as i32, library_id],
+        )?;
         Ok(())
     }
 
+    /// Id of the library rooted at `root_path`, without creating it
+    pub fn get_library_id(&self, root_path: &str) -> SqlResult<Option<i64>> {
+        let conn = self.lock_conn();
+        match conn.query_row("SELECT id FROM library WHERE root_path = ?1", [root_path], |row| row.get(0)) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Id of the library rooted at `root_path`, creating it if needed. For indexing paths;
+    /// lookups should use `get_library_id`. An existing library keeps its `created_at`.
     pub fn get_or_create_library(&self, root_path: &str) -> SqlResult<i64> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        // The no-op update makes RETURNING yield the existing row's id
         let id: i64 = conn.query_row(
-            "INSERT INTO library (root_path, created_at) VALUES (?1, ?2) ON CONFLICT(root_path) DO UPDATE SET created_at = ?2 RETURNING id",
+            "INSERT INTO library (root_path, created_at) VALUES (?1, ?2) ON CONFLICT(root_path) DO UPDATE SET root_path = excluded.root_path RETURNING id",
             [root_path, &now],
             |row| row.get(0),
         )?;
         Ok(id)
     }
 
+    pub fn library_exists(&self, library_id: i64) -> SqlResult<bool> {
+        let conn = self.lock_conn();
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM library WHERE id = ?1)",
+            [library_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// `ON CONFLICT` clause for (re-)inserting a scanned file. A path already in the library
+    /// has the given columns refreshed in place, keeping its row id and so everything keyed
+    /// on it: favorite, trash state, tags, albums. User edits survive too: a manual date
+    /// (`date_source = 'manual'`) and any column listed in `edited_fields`. A thumbnail not in
+    /// `columns` is kept only while the file's size and mtime are unchanged.
+    fn upsert_clause(columns: &str) -> String {
+        let manual_date = "photos.date_source = 'manual'";
+        let mut sets: Vec<String> = columns
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !matches!(*c, "library_id" | "path"))
+            .map(|c| match c {
+                "taken_at" | "date_source" => {
+                    format!("{0} = CASE WHEN {1} THEN photos.{0} ELSE excluded.{0} END", c, manual_date)
+                }
+                _ if c == "category" || BULK_EDIT_FIELDS.contains(&c) => format!(
+                    "{0} = CASE WHEN {1} THEN photos.{0} ELSE excluded.{0} END",
+                    c,
+                    Self::is_edited_sql(c)
+                ),
+                _ => format!("{0} = excluded.{0}", c),
+            })
+            .collect();
+        sets.push("is_missing = 0".to_string());
+        sets.push(format!("date_is_estimated = CASE WHEN {} THEN photos.date_is_estimated ELSE 0 END", manual_date));
+        if !sets.iter().any(|s| s.starts_with("thumb_path ")) {
+            sets.push(
+                "thumb_path = CASE WHEN photos.size_bytes = excluded.size_bytes \
+                 AND photos.modified_at = excluded.modified_at THEN photos.thumb_path END"
+                    .to_string(),
+            );
+        }
+        format!("ON CONFLICT(library_id, path) DO UPDATE SET {}", sets.join(", "))
+    }
+
     pub fn clear_photos_for_library(&self, library_id: i64) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
+        // Foreign keys aren't enforced, so links to the photos go explicitly
+        for table in ["photo_tags", "album_photos", "recently_viewed", "faces"] {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE photo_id IN (SELECT id FROM photos WHERE library_id = ?1)", table),
+                [library_id],
+            )?;
+        }
         conn.execute("DELETE FROM photos WHERE library_id = ?1", [library_id])?;
         Ok(())
     }
 
     pub fn remove_library(&self, library_id: i64) -> SqlResult<()> {
         self.clear_photos_for_library(library_id)?;
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         conn.execute("DELETE FROM library WHERE id = ?1", [library_id])?;
         Ok(())
     }
 
-    #[allow(clippy::too_many_arguments, dead_code)]
-    pub fn insert_photo(
-        &self,
-        library_id: i64,
-        path: &str,
-        filename: &str,
-        folder_rel: &str,
-        taken_at: Option<&str>,
-        modified_at: &str,
-        media_type: &str,
-        size_bytes: i64,
-        width: Option<i32>,
-        height: Option<i32>,
-        camera_make: Option<&str>,
-        camera_model: Option<&str>,
-        lens: Option<&str>,
-        iso: Option<i32>,
-        shutter_speed: Option<&str>,
-        aperture: Option<&str>,
-        focal_length: Option<&str>,
-        gps_lat: Option<f64>,
-        gps_lon: Option<f64>,
-    ) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            r#"
-            INSERT OR REPLACE INTO photos (library_id, path, filename, folder_rel, taken_at, modified_at, media_type, size_bytes, width, height,
-                                           camera_make, camera_model, lens, iso, shutter_speed, aperture, focal_length, gps_lat, gps_lon)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
-            "#,
-            rusqlite::params![
-                library_id,
-                path,
-                filename,
-                folder_rel,
-                taken_at,
-                modified_at,
-                media_type,
-                size_bytes,
-                width,
-                height,
-                camera_make,
-                camera_model,
-                lens,
-                iso,
-                shutter_speed,
-                aperture,
-                focal_length,
-                gps_lat,
-                gps_lon,
-            ],
-        )?;
-        Ok(())
+    /// Move a library from `old_root` to `new_root` in one transaction: the root itself, and
+    /// the prefix of every photo path and paired motion/RAW/JPEG path. Ids, tags, albums and
+    /// edits are untouched. Returns the number of photos moved; 0 rows and no change when the
+    /// library isn't rooted at `old_root`.
+    pub fn relocate_library(&self, library_id: i64, old_root: &str, new_root: &str) -> SqlResult<usize> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<usize> {
+            let updated = conn.execute(
+                "UPDATE library SET root_path = ?1 WHERE id = ?2 AND root_path = ?3",
+                rusqlite::params![new_root, library_id, old_root],
+            )?;
+            if updated == 0 {
+                return Ok(0);
+            }
+            let rebase = |column: &str| {
+                format!(
+                    "{0} = CASE WHEN substr({0}, 1, length(?2)) = ?2 THEN ?1 || substr({0}, length(?2) + 1) ELSE {0} END",
+                    column
+                )
+            };
+            conn.execute(
+                &format!(
+                    "UPDATE photos SET {}, {}, {}, {} WHERE library_id = ?3 AND substr(path, 1, length(?2)) = ?2",
+                    rebase("path"),
+                    rebase("motion_path"),
+                    rebase("raw_path"),
+                    rebase("jpeg_path")
+                ),
+                rusqlite::params![new_root, old_root, library_id],
+            )
+        })();
+        match result {
+            Ok(moved) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(moved)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
     }
 
-    /// Batch insert photos inside a single transaction — ~50x faster than individual inserts
-    pub fn batch_insert_photos(
+    /// Batch insert photos inside a single transaction — ~50x faster than individual inserts.
+    /// Returns the inserted records with their row ids, in input order.
+    pub fn insert_photos_batch(
         &self,
         library_id: i64,
         photos: &[crate::scan::ScannedFile],
-    ) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+    ) -> SqlResult<Vec<PhotoRecord>> {
+        let conn = self.lock_conn();
         conn.execute_batch("BEGIN")?;
-        {
-            let mut stmt = conn.prepare(
-                r#"INSERT OR REPLACE INTO photos (library_id, path, filename, folder_rel, taken_at, modified_at, media_type, size_bytes, width, height,
-                                                   camera_make, camera_model, lens, iso, shutter_speed, aperture, focal_length, gps_lat, gps_lon)
-                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)"#,
-            )?;
+        let result = (|| -> SqlResult<Vec<PhotoRecord>> {
+            let columns = "library_id, path, filename, folder_rel, taken_at, modified_at, media_type, size_bytes, width, height,
+                           camera_make, camera_model, lens, iso, shutter_speed, aperture, focal_length, gps_lat, gps_lon, orientation, exposure_bias, utc_offset_minutes, blurhash, category, motion_path, raw_path, jpeg_path,
+                           shutter_seconds, f_number, color_space, is_hdr, title, caption, frame_count, date_source, is_placeholder, is_animated";
+            let mut stmt = conn.prepare(&format!(
+                "INSERT INTO photos ({})
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36, ?37)
+                   {}
+                   RETURNING id",
+                columns,
+                Self::upsert_clause(columns)
+            ))?;
+            let mut out = Vec::with_capacity(photos.len());
             for s in photos {
-                stmt.execute(rusqlite::params![
-                    library_id,
-                    s.path,
-                    s.filename,
-                    s.folder_rel,
-                    s.taken_at,
-                    s.modified_at,
-                    s.media_type,
-                    s.size_bytes,
-                    s.width,
-                    s.height,
-                    s.camera_make,
-                    s.camera_model,
-                    s.lens,
-                    s.iso,
-                    s.shutter_speed,
-                    s.aperture,
-                    s.focal_length,
-                    s.gps_lat,
-                    s.gps_lon,
-                ])?;
+                let id: i64 = stmt.query_row(
+                    rusqlite::params![
+                        library_id,
+                        s.path,
+                        s.filename,
+                        s.folder_rel,
+                        s.taken_at,
+                        s.modified_at,
+                        s.media_type,
+                        s.size_bytes,
+                        s.width,
+                        s.height,
+                        s.camera_make,
+                        s.camera_model,
+                        s.lens,
+                        s.iso,
+                        s.shutter_speed,
+                        s.aperture,
+                        s.focal_length,
+                        s.gps_lat,
+                        s.gps_lon,
+                        s.orientation,
+                        s.exposure_bias,
+                        s.utc_offset_minutes,
+                        s.blurhash,
+                        s.category,
+                        s.motion_path,
+                        s.raw_path,
+                        s.jpeg_path,
+                        s.shutter_seconds,
+                        s.f_number,
+                        s.color_space,
+                        s.is_hdr,
+                        s.title,
+                        s.caption,
+                        s.frame_count,
+                        s.date_source,
+                        s.is_placeholder,
+                        s.is_animated,
+                    ],
+                    |row| row.get(0),
+                )?;
+                Self::apply_keyword_tags(&conn, id, &s.keywords)?;
+                out.push(Self::photo_from_scanned(id, s));
+            }
+            Ok(out)
+        })();
+        match result {
+            Ok(records) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(records)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
             }
         }
-        conn.execute_batch("COMMIT")?;
-        Ok(())
+    }
+
+    /// Helper: build a PhotoRecord for a freshly inserted row from its scan data
+    fn photo_from_scanned(id: i64, s: &crate::scan::ScannedFile) -> PhotoRecord {
+        PhotoRecord {
+            id,
+            path: s.path.clone(),
+            filename: s.filename.clone(),
+            folder_rel: s.folder_rel.clone(),
+            taken_at: s.taken_at.clone(),
+            modified_at: s.modified_at.clone(),
+            media_type: s.media_type.clone(),
+            size_bytes: s.size_bytes,
+            width: s.width,
+            height: s.height,
+            source: String::new(),
+            is_favorite: false,
+            is_deleted: false,
+            deleted_at: None,
+            camera_make: s.camera_make.clone(),
+            camera_model: s.camera_model.clone(),
+            lens: s.lens.clone(),
+            iso: s.iso,
+            shutter_speed: s.shutter_speed.clone(),
+            aperture: s.aperture.clone(),
+            shutter_seconds: s.shutter_seconds,
+            f_number: s.f_number,
+            focal_length: s.focal_length.clone(),
+            gps_lat: s.gps_lat,
+            gps_lon: s.gps_lon,
+            orientation: s.orientation,
+            exposure_bias: s.exposure_bias,
+            utc_offset_minutes: s.utc_offset_minutes,
+            blurhash: s.blurhash.clone(),
+            category: s.category.clone(),
+            date_is_estimated: false,
+            motion_path: s.motion_path.clone(),
+            raw_path: s.raw_path.clone(),
+            jpeg_path: s.jpeg_path.clone(),
+            is_missing: false,
+            color_space: s.color_space.clone(),
+            is_hdr: s.is_hdr,
+            title: s.title.clone(),
+            caption: s.caption.clone(),
+            frame_count: s.frame_count,
+            date_source: s.date_source.clone(),
+            is_placeholder: s.is_placeholder,
+            thumbnail_exists: false,
+            is_animated: s.is_animated,
+            aspect_ratio: None,
+            megapixels: None,
+        }
+        .with_derived()
     }
 
     /// Helper: standard columns for photo queries
     fn photo_select_cols() -> &'static str {
-        "id, path, filename, folder_rel, taken_at, modified_at, media_type, size_bytes, width, height, is_favorite, is_deleted, deleted_at, camera_make, camera_model, lens, iso, shutter_speed, aperture, focal_length, gps_lat, gps_lon"
+        "id, path, filename, folder_rel, taken_at, modified_at, media_type, size_bytes, width, height, is_favorite, is_deleted, deleted_at, camera_make, camera_model, lens, iso, shutter_speed, aperture, focal_length, gps_lat, gps_lon, orientation, exposure_bias, utc_offset_minutes, blurhash, category, date_is_estimated, motion_path, raw_path, jpeg_path, is_missing, shutter_seconds, f_number, color_space, is_hdr, title, caption, frame_count, date_source, is_placeholder, is_animated"
+    }
+
+    /// Helper: standard columns qualified with a table alias, for joined queries
+    fn photo_select_cols_with(alias: &str) -> String {
+        Self::photo_select_cols()
+            .split(", ")
+            .map(|c| format!("{}.{}", alias, c))
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 
     /// Helper: construct PhotoRecord from a row with standard columns
@@ -369,51 +1015,67 @@ impl Database {
             iso: row.get(16)?,
             shutter_speed: row.get(17)?,
             aperture: row.get(18)?,
+            shutter_seconds: row.get(32)?,
+            f_number: row.get(33)?,
             focal_length: row.get(19)?,
             gps_lat: row.get(20)?,
             gps_lon: row.get(21)?,
-        })
+            orientation: row.get(22)?,
+            exposure_bias: row.get(23)?,
+            utc_offset_minutes: row.get(24)?,
+            blurhash: row.get(25)?,
+            category: row.get(26)?,
+            date_is_estimated: row.get::<_, i32>(27).unwrap_or(0) != 0,
+            motion_path: row.get(28)?,
+            raw_path: row.get(29)?,
+            jpeg_path: row.get(30)?,
+            is_missing: row.get::<_, i32>(31).unwrap_or(0) != 0,
+            color_space: row.get(34)?,
+            is_hdr: row.get::<_, i32>(35).unwrap_or(0) != 0,
+            title: row.get(36)?,
+            caption: row.get(37)?,
+            frame_count: row.get(38)?,
+            date_source: row.get(39)?,
+            is_placeholder: row.get::<_, i32>(40).unwrap_or(0) != 0,
+            thumbnail_exists: false,
+            is_animated: row.get::<_, i32>(41).unwrap_or(0) != 0,
+            aspect_ratio: None,
+            megapixels: None,
+        }
+        .with_derived())
     }
 
+    /// One page of photos from the given libraries. With more than one library each record's
+    /// `source` names the library it came from.
     pub fn get_photos(
         &self,
-        library_id: i64,
+        library_ids: &[i64],
         limit: i64,
         offset: i64,
-        year: Option<i32>,
-        month: Option<i32>,
-        folder_rel: Option<&str>,
-        media_type: Option<&str>,
+        filter: &PhotoFilter,
     ) -> SqlResult<Vec<PhotoRecord>> {
-        let conn = self.conn.lock().unwrap();
+        if library_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let conn = self.lock_conn();
+        let placeholders = vec!["?"; library_ids.len()].join(", ");
         let mut sql = format!(
-            "SELECT {} FROM photos WHERE library_id = ?1 AND is_deleted = 0",
-            Self::photo_select_cols()
+            "SELECT {}, (SELECT root_path FROM library WHERE id = photos.library_id) FROM photos
+             WHERE library_id IN ({}) AND is_deleted = 0",
+            Self::photo_select_cols(),
+            placeholders
         );
-        let mut extra: Vec<String> = Vec::new();
-
-        if let Some(y) = year {
-            sql.push_str(" AND strftime('%Y', COALESCE(taken_at, modified_at)) = ?");
-            extra.push(format!("{:04}", y));
-        }
-        if let Some(m) = month {
-            sql.push_str(" AND strftime('%m', COALESCE(taken_at, modified_at)) = ?");
-            extra.push(format!("{:02}", m));
-        }
-        if let Some(f) = folder_rel {
-            sql.push_str(" AND (folder_rel = ? OR folder_rel LIKE ?)");
-            extra.push(f.to_string());
-            extra.push(format!("{}%", f));
-        }
-        if let Some(t) = media_type {
-            sql.push_str(" AND media_type = ?");
-            extra.push(t.to_string());
-        }
-
-        sql.push_str(" ORDER BY COALESCE(taken_at, modified_at) DESC, path LIMIT ? OFFSET ?");
+        let (filters, extra) = Self::photo_filter_sql(filter);
+        sql.push_str(&filters);
+        // id breaks ties between libraries sharing a path, so pages never overlap or skip
+        sql.push_str(" ORDER BY COALESCE(taken_at, modified_at) DESC, path, id LIMIT ? OFFSET ?");
 
         let mut stmt = conn.prepare(&sql)?;
-        let mut param_refs: Vec<&dyn rusqlite::ToSql> = vec![&library_id];
+        let root_col = stmt.column_count() - 1;
+        let mut param_refs: Vec<&dyn rusqlite::ToSql> = Vec::new();
+        for id in library_ids {
+            param_refs.push(id);
+        }
         for e in &extra {
             param_refs.push(e);
         }
@@ -423,45 +1085,425 @@ impl Database {
         let mut rows = stmt.query(rusqlite::params_from_iter(param_refs))?;
         let mut out = Vec::new();
         while let Some(row) = rows.next()? {
-            out.push(Self::photo_from_row(row, String::new())?);
+            let source = if library_ids.len() > 1 {
+                library_name(&row.get::<_, String>(root_col)?)
+            } else {
+                String::new()
+            };
+            out.push(Self::photo_from_row(row, source)?);
         }
         Ok(out)
     }
 
-    pub fn search_photos(
+    /// Ids of the photos either side of `current_id` in `get_photos` order under the same
+    /// filters, as (previous, next). Seeks from the current photo's sort key rather than
+    /// materialising the list, so it stays cheap on large libraries. With `wrap`, stepping
+    /// past either end continues from the other. None for both when the photo doesn't exist.
+    pub fn get_adjacent_photos(
         &self,
-        library_id: i64,
-        query: &str,
-        limit: i64,
-    ) -> SqlResult<Vec<PhotoRecord>> {
-        let conn = self.conn.lock().unwrap();
-        let pattern = format!("%{}%", query.replace('%', "\\%").replace('_', "\\_"));
-        let sql = format!(
-            "SELECT {} FROM photos \
-             WHERE library_id = ?1 AND is_deleted = 0 AND \
-             (path LIKE ?2 ESCAPE '\\' OR filename LIKE ?2 ESCAPE '\\' OR folder_rel LIKE ?2 ESCAPE '\\' \
-              OR taken_at LIKE ?2 ESCAPE '\\' OR camera_make LIKE ?2 ESCAPE '\\' OR camera_model LIKE ?2 ESCAPE '\\' \
-              OR id IN (SELECT pt.photo_id FROM photo_tags pt JOIN tags t ON t.id=pt.tag_id WHERE t.name LIKE ?2 ESCAPE '\\')) \
-             ORDER BY taken_at DESC LIMIT ?3",
-            Self::photo_select_cols()
-        );
-        let mut stmt = conn.prepare(&sql)?;
-        let mut rows = stmt.query(rusqlite::params![library_id, pattern, limit])?;
-        let mut out = Vec::new();
-        while let Some(row) = rows.next()? {
-            out.push(Self::photo_from_row(row, String::new())?);
+        library_ids: &[i64],
+        current_id: i64,
+        filter: &PhotoFilter,
+        wrap: bool,
+    ) -> SqlResult<(Option<i64>, Option<i64>)> {
+        if library_ids.is_empty() {
+            return Ok((None, None));
         }
-        Ok(out)
-    }
+        let conn = self.lock_conn();
+        let current: Option<(String, String)> = match conn.query_row(
+            "SELECT COALESCE(taken_at, modified_at), path FROM photos WHERE id = ?1",
+            [current_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(key) => Some(key),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        };
+        let Some((date, path)) = current else {
+            return Ok((None, None));
+        };
 
-    pub fn get_years(&self, library_id: i64) -> SqlResult<Vec<CategoryYear>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT CAST(strftime('%Y', taken_at) AS INTEGER) AS y, COUNT(*) FROM photos 
-             WHERE library_id = ?1 AND taken_at IS NOT NULL GROUP BY y ORDER BY y DESC",
+        let placeholders = vec!["?"; library_ids.len()].join(", ");
+        let (filters, extra) = Self::photo_filter_sql(filter);
+        // `seek` is None for the wrap-around lookup of the first/last row
+        let step = |seek: Option<&str>, order: &str| -> SqlResult<Option<i64>> {
+            let sql = format!(
+                "SELECT id FROM photos WHERE library_id IN ({}) AND is_deleted = 0{}{} AND id != ? \
+                 ORDER BY {} LIMIT 1",
+                placeholders,
+                filters,
+                seek.unwrap_or(""),
+                order
+            );
+            let mut param_refs: Vec<&dyn rusqlite::ToSql> = Vec::new();
+            for id in library_ids {
+                param_refs.push(id);
+            }
+            for e in &extra {
+                param_refs.push(e);
+            }
+            if seek.is_some() {
+                param_refs.extend([&date as &dyn rusqlite::ToSql, &date, &path, &path, &current_id]);
+            }
+            param_refs.push(&current_id);
+            match conn.query_row(&sql, rusqlite::params_from_iter(param_refs), |row| row.get(0)) {
+                Ok(id) => Ok(Some(id)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+        // The grid sorts newest first, ties by path, then id
+        const FORWARD: &str = "COALESCE(taken_at, modified_at) DESC, path, id";
+        const BACKWARD: &str = "COALESCE(taken_at, modified_at), path DESC, id DESC";
+        let mut previous = step(
+            Some(
+                " AND (COALESCE(taken_at, modified_at) > ? OR (COALESCE(taken_at, modified_at) = ? \
+                 AND (path < ? OR (path = ? AND id < ?))))",
+            ),
+            BACKWARD,
         )?;
-        let mut rows = stmt.query([library_id])?;
-        let mut out = Vec::new();
+        let mut next = step(
+            Some(
+                " AND (COALESCE(taken_at, modified_at) < ? OR (COALESCE(taken_at, modified_at) = ? \
+                 AND (path > ? OR (path = ? AND id > ?))))",
+            ),
+            FORWARD,
+        )?;
+        if wrap {
+            if previous.is_none() {
+                previous = step(None, BACKWARD)?;
+            }
+            if next.is_none() {
+                next = step(None, FORWARD)?;
+            }
+        }
+        Ok((previous, next))
+    }
+
+    /// Helper: the `get_photos` filters as ` AND ...` clauses with anonymous placeholders
+    fn photo_filter_sql(filter: &PhotoFilter) -> (String, Vec<String>) {
+        let mut sql = String::new();
+        let mut params: Vec<String> = Vec::new();
+        if let Some(y) = filter.year {
+            sql.push_str(&format!(" AND strftime('%Y', {}) = ?", LOCAL_DATE));
+            params.push(format!("{:04}", y));
+        }
+        if let Some(m) = filter.month {
+            sql.push_str(&format!(" AND strftime('%m', {}) = ?", LOCAL_DATE));
+            params.push(format!("{:02}", m));
+        }
+        if let Some(f) = filter.folder_rel {
+            // The folder and its subfolders; a prefix compare, so `_`/`%` in names are literal
+            // and "2024" doesn't pull in a sibling "2024-trip"
+            let prefix = format!("{}{}", f, std::path::MAIN_SEPARATOR);
+            sql.push_str(" AND (folder_rel = ? OR substr(folder_rel, 1, length(?)) = ?)");
+            params.push(f.to_string());
+            params.push(prefix.clone());
+            params.push(prefix);
+        }
+        if let Some(t) = filter.media_type {
+            sql.push_str(" AND media_type = ?");
+            params.push(t.to_string());
+        }
+        if let Some(c) = filter.category {
+            // Rows indexed before categories existed count as plain photos
+            sql.push_str(" AND COALESCE(category, 'photo') = ?");
+            params.push(c.to_string());
+        }
+        if let Some(pref) = filter.pair_preference {
+            sql.push_str(" AND (raw_path IS NULL OR path = CASE ? WHEN 'raw' THEN raw_path ELSE jpeg_path END)");
+            params.push(pref.to_string());
+        }
+        if let Some(shape) = filter.shape {
+            // Rotated (EXIF 5-8) images display with width and height swapped
+            let (w, h) = (
+                "(CASE WHEN orientation IN (5,6,7,8) THEN height ELSE width END)",
+                "(CASE WHEN orientation IN (5,6,7,8) THEN width ELSE height END)",
+            );
+            sql.push_str(" AND width > 0 AND height > 0");
+            // Within 1% counts as square so cropped "square" shots still match
+            match shape {
+                "portrait" => sql.push_str(&format!(" AND {w} * 1.0 < {h} * 0.99")),
+                "landscape" => sql.push_str(&format!(" AND {w} * 0.99 > {h} * 1.0")),
+                _ => sql.push_str(&format!(" AND ABS({w} - {h}) <= MAX({w}, {h}) * 0.01")),
+            }
+        }
+        if let Some(mp) = filter.min_megapixels {
+            // Numeric literal: a text parameter would compare as a string
+            sql.push_str(&format!(" AND width * height >= {}", (mp * 1_000_000.0).ceil() as i64));
+        }
+        for (col, range) in [
+            ("iso", filter.iso),
+            ("shutter_seconds", filter.shutter_seconds),
+            ("f_number", filter.f_number),
+        ] {
+            let Some(range) = range else { continue };
+            if let Some(min) = range.min {
+                sql.push_str(&format!(" AND {} >= {}", col, min));
+            }
+            if let Some(max) = range.max {
+                sql.push_str(&format!(" AND {} <= {}", col, max));
+            }
+        }
+        if let Some(space) = filter.color_space {
+            // Photos without a detected profile are sRGB
+            if space == "wide" {
+                let list = crate::scan::WIDE_GAMUT_SPACES
+                    .iter()
+                    .map(|s| format!("'{}'", s))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(&format!(" AND color_space IN ({})", list));
+            } else {
+                sql.push_str(" AND COALESCE(color_space, 'srgb') = ?");
+                params.push(space.to_string());
+            }
+        }
+        if let Some(hdr) = filter.hdr {
+            sql.push_str(if hdr { " AND is_hdr = 1" } else { " AND is_hdr = 0" });
+        }
+        if let Some(placeholder) = filter.placeholder {
+            sql.push_str(if placeholder { " AND is_placeholder = 1" } else { " AND is_placeholder = 0" });
+        }
+        (sql, params)
+    }
+
+    /// Ids and paths for a slideshow, oldest first, using the same filters as `get_photos`.
+    /// Videos are skipped unless `include_videos` (an explicit `media_type` always wins).
+    pub fn get_slideshow_items(
+        &self,
+        library_id: i64,
+        filter: &PhotoFilter,
+        include_videos: bool,
+    ) -> SqlResult<Vec<(i64, String)>> {
+        let conn = self.lock_conn();
+        let filter = PhotoFilter {
+            media_type: filter.media_type.or((!include_videos).then_some("photo")),
+            ..filter.clone()
+        };
+        let (filters, extra) = Self::photo_filter_sql(&filter);
+        let sql = format!(
+            "SELECT id, path FROM photos WHERE library_id = ? AND is_deleted = 0{} \
+             ORDER BY COALESCE(taken_at, modified_at), path",
+            filters
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut param_refs: Vec<&dyn rusqlite::ToSql> = vec![&library_id];
+        for e in &extra {
+            param_refs.push(e);
+        }
+        let rows = stmt.query_map(rusqlite::params_from_iter(param_refs), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Helper: escape `%`/`_` (and the escape character itself) for a LIKE pattern
+    /// (use with `ESCAPE '\\'`)
+    fn like_escape(term: &str) -> String {
+        term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    }
+
+    /// Helper: `%term%` LIKE pattern
+    fn like_contains(term: &str) -> String {
+        format!("%{}%", Self::like_escape(term))
+    }
+
+    /// Helper: relevance score expression for a search. Per free-text term: exact filename
+    /// (with or without extension) 100, filename prefix 50, filename substring 30, exact tag 60,
+    /// partial tag 40, title/caption 20, folder 15, camera 10. Up to 5 extra points for recency (linear over
+    /// five years) so it only breaks ties between similar matches.
+    fn search_score(query: &SearchQuery) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+        let mut parts: Vec<String> = vec![
+            "MAX(0.0, 5.0 - (julianday('now') - julianday(COALESCE(taken_at, modified_at))) / 365.0)".to_string(),
+        ];
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        for term in query.text_terms() {
+            let escaped = Self::like_escape(term);
+            parts.push(
+                "(CASE WHEN filename = ? COLLATE NOCASE OR filename LIKE ? ESCAPE '\\' THEN 100 \
+                  WHEN filename LIKE ? ESCAPE '\\' THEN 50 \
+                  WHEN filename LIKE ? ESCAPE '\\' THEN 30 ELSE 0 END)".to_string(),
+            );
+            params.push(Box::new(term.to_string()));
+            params.push(Box::new(format!("{}.%", escaped)));
+            params.push(Box::new(format!("{}%", escaped)));
+            params.push(Box::new(Self::like_contains(term)));
+            parts.push(
+                "(CASE WHEN EXISTS (SELECT 1 FROM photo_tags pt JOIN tags t ON t.id = pt.tag_id \
+                                    WHERE pt.photo_id = photos.id AND t.name = ? COLLATE NOCASE) THEN 60 \
+                  WHEN EXISTS (SELECT 1 FROM photo_tags pt JOIN tags t ON t.id = pt.tag_id \
+                               WHERE pt.photo_id = photos.id AND t.name LIKE ? ESCAPE '\\') THEN 40 ELSE 0 END)".to_string(),
+            );
+            params.push(Box::new(term.to_string()));
+            params.push(Box::new(Self::like_contains(term)));
+            parts.push(
+                "(CASE WHEN title LIKE ? ESCAPE '\\' OR caption LIKE ? ESCAPE '\\' THEN 20 ELSE 0 END)".to_string(),
+            );
+            params.push(Box::new(Self::like_contains(term)));
+            params.push(Box::new(Self::like_contains(term)));
+            parts.push("(CASE WHEN folder_rel LIKE ? ESCAPE '\\' THEN 15 ELSE 0 END)".to_string());
+            params.push(Box::new(Self::like_contains(term)));
+            parts.push(
+                "(CASE WHEN camera_make LIKE ? ESCAPE '\\' OR camera_model LIKE ? ESCAPE '\\' THEN 10 ELSE 0 END)".to_string(),
+            );
+            params.push(Box::new(Self::like_contains(term)));
+            params.push(Box::new(Self::like_contains(term)));
+        }
+        (parts.join(" + "), params)
+    }
+
+    /// Helper: translate parsed search filters into AND-ed SQL conditions plus their params
+    fn search_conditions(query: &SearchQuery) -> (Vec<String>, Vec<Box<dyn rusqlite::ToSql>>) {
+        let date = LOCAL_DATE;
+        let tag_match = "id IN (SELECT pt.photo_id FROM photo_tags pt JOIN tags t ON t.id = pt.tag_id WHERE t.name LIKE ? ESCAPE '\\')";
+        let mut clauses: Vec<String> = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        for filter in &query.filters {
+            match filter {
+                SearchFilter::Camera(v) => {
+                    clauses.push("(camera_make LIKE ? ESCAPE '\\' OR camera_model LIKE ? ESCAPE '\\')".to_string());
+                    params.push(Box::new(Self::like_contains(v)));
+                    params.push(Box::new(Self::like_contains(v)));
+                }
+                SearchFilter::Lens(v) => {
+                    clauses.push("lens LIKE ? ESCAPE '\\'".to_string());
+                    params.push(Box::new(Self::like_contains(v)));
+                }
+                SearchFilter::Iso(cmp, n) => {
+                    clauses.push(format!("iso {} ?", cmp.sql()));
+                    params.push(Box::new(*n));
+                }
+                SearchFilter::Shutter(cmp, secs) => {
+                    clauses.push(format!("shutter_seconds {} ?", cmp.sql()));
+                    params.push(Box::new(*secs));
+                }
+                SearchFilter::Aperture(cmp, f) => {
+                    clauses.push(format!("f_number {} ?", cmp.sql()));
+                    params.push(Box::new(*f));
+                }
+                SearchFilter::Year(y) => {
+                    clauses.push(format!("strftime('%Y', {}) = ?", date));
+                    params.push(Box::new(format!("{:04}", y)));
+                }
+                SearchFilter::Month(m) => {
+                    clauses.push(format!("strftime('%m', {}) = ?", date));
+                    params.push(Box::new(format!("{:02}", m)));
+                }
+                SearchFilter::Tag(v) => {
+                    clauses.push(tag_match.to_string());
+                    params.push(Box::new(Self::like_contains(v)));
+                }
+                SearchFilter::Favorite(f) => {
+                    clauses.push("is_favorite = ?".to_string());
+                    params.push(Box::new(*f as i32));
+                }
+                SearchFilter::MediaType(t) => {
+                    clauses.push("media_type = ?".to_string());
+                    params.push(Box::new(t.clone()));
+                }
+                SearchFilter::Folder(v) => {
+                    clauses.push("folder_rel LIKE ? ESCAPE '\\'".to_string());
+                    params.push(Box::new(Self::like_contains(v)));
+                }
+                SearchFilter::Extension(ext) => {
+                    clauses.push("LOWER(filename) LIKE ? ESCAPE '\\'".to_string());
+                    params.push(Box::new(format!("%.{}", Self::like_escape(ext))));
+                }
+                SearchFilter::Text(v) => {
+                    let cols = ["filename", "folder_rel", "path", "taken_at", "camera_make", "camera_model", "title", "caption"];
+                    let mut parts: Vec<String> = cols.iter().map(|c| format!("{} LIKE ? ESCAPE '\\'", c)).collect();
+                    parts.push(tag_match.to_string());
+                    clauses.push(format!("({})", parts.join(" OR ")));
+                    for _ in 0..parts.len() {
+                        params.push(Box::new(Self::like_contains(v)));
+                    }
+                }
+            }
+        }
+        (clauses, params)
+    }
+
+    /// Search the given libraries, best matches first (see `search_score`); `limit` applies
+    /// after ranking. Each result's `source` is its library's folder name.
+    pub fn search_photos(
+        &self,
+        library_ids: &[i64],
+        query: &SearchQuery,
+        scope: Option<&SearchScope>,
+        limit: i64,
+    ) -> SqlResult<Vec<SearchResult>> {
+        // An empty query would match (and rank) the whole library
+        if library_ids.is_empty() || !query.is_searchable() {
+            return Ok(Vec::new());
+        }
+        let conn = self.lock_conn();
+        let (score_sql, score_params) = Self::search_score(query);
+        let (mut clauses, mut filter_params) = Self::search_conditions(query);
+        match scope {
+            Some(SearchScope::Album(album_id)) => {
+                clauses.push("id IN (SELECT photo_id FROM album_photos WHERE album_id = ?)".to_string());
+                filter_params.push(Box::new(*album_id));
+            }
+            Some(SearchScope::Folder(folder_rel)) => {
+                let prefix = format!("{}{}", folder_rel, std::path::MAIN_SEPARATOR);
+                clauses.push("(folder_rel = ? OR substr(folder_rel, 1, length(?)) = ?)".to_string());
+                filter_params.push(Box::new(folder_rel.clone()));
+                filter_params.push(Box::new(prefix.clone()));
+                filter_params.push(Box::new(prefix));
+            }
+            None => {}
+        }
+        let placeholders = vec!["?"; library_ids.len()].join(", ");
+        let mut sql = format!(
+            "SELECT {}, (SELECT root_path FROM library WHERE id = photos.library_id), ({}) AS score \
+             FROM photos WHERE library_id IN ({}) AND is_deleted = 0",
+            Self::photo_select_cols(),
+            score_sql,
+            placeholders
+        );
+        for c in &clauses {
+            sql.push_str(" AND ");
+            sql.push_str(c);
+        }
+        sql.push_str(" ORDER BY score DESC, COALESCE(taken_at, modified_at) DESC LIMIT ?");
+
+        // Params in placeholder order: score expression, library ids, filters, limit
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = score_params;
+        for id in library_ids {
+            params.push(Box::new(*id));
+        }
+        params.extend(filter_params);
+        params.push(Box::new(limit));
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+        let mut stmt = conn.prepare(&sql)?;
+        let score_col = stmt.column_count() - 1;
+        let root_col = score_col - 1;
+        let mut rows = stmt.query(param_refs.as_slice())?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let root_path: String = row.get(root_col)?;
+            out.push(SearchResult {
+                photo: Self::photo_from_row(row, library_name(&root_path))?,
+                score: row.get(score_col)?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Photo count per year. Like `get_photos` date filters, a photo without `taken_at` is
+    /// bucketed by its `modified_at`, so a year (or month, or day) opens onto exactly the
+    /// photos it counts.
+    pub fn get_years(&self, library_id: i64) -> SqlResult<Vec<CategoryYear>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT CAST(strftime('%Y', {d}) AS INTEGER) AS y, COUNT(*) FROM photos 
+             WHERE library_id = ?1 AND is_deleted = 0 GROUP BY y ORDER BY y DESC",
+            d = LOCAL_DATE
+        ))?;
+        let mut rows = stmt.query([library_id])?;
+        let mut out = Vec::new();
         while let Some(row) = rows.next()? {
             out.push(CategoryYear {
                 year: row.get(0)?,
@@ -471,13 +1513,44 @@ impl Database {
         Ok(out)
     }
 
+    /// Photos with no real capture date (undated, mtime fallback or estimated), by path,
+    /// for triage
+    pub fn get_undated_photos(&self, library_id: i64, limit: i64, offset: i64) -> SqlResult<Vec<PhotoRecord>> {
+        let conn = self.lock_conn();
+        let sql = format!(
+            "SELECT {} FROM photos
+             WHERE library_id = ?1 AND is_deleted = 0
+               AND COALESCE(date_source, '') NOT IN ('exif', 'sidecar', 'manual')
+             ORDER BY path LIMIT ?2 OFFSET ?3",
+            Self::photo_select_cols()
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(rusqlite::params![library_id, limit, offset])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::photo_from_row(row, String::new())?);
+        }
+        Ok(out)
+    }
+
+    /// Photos with no capture date (indexed while the mtime fallback was off)
+    pub fn count_undated(&self, library_id: i64) -> SqlResult<i64> {
+        let conn = self.lock_conn();
+        conn.query_row(
+            "SELECT COUNT(*) FROM photos WHERE library_id = ?1 AND is_deleted = 0 AND taken_at IS NULL",
+            [library_id],
+            |row| row.get(0),
+        )
+    }
+
     pub fn get_months(&self, library_id: i64, year: i32) -> SqlResult<Vec<CategoryMonth>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let year_str = format!("{:04}", year);
-        let mut stmt = conn.prepare(
-            "SELECT CAST(strftime('%Y', taken_at) AS INTEGER), CAST(strftime('%m', taken_at) AS INTEGER), COUNT(*) 
-             FROM photos WHERE library_id = ?1 AND strftime('%Y', taken_at) = ?2 GROUP BY strftime('%Y-%m', taken_at) ORDER BY 2 DESC",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT CAST(strftime('%Y', {d}) AS INTEGER), CAST(strftime('%m', {d}) AS INTEGER), COUNT(*) 
+             FROM photos WHERE library_id = ?1 AND is_deleted = 0 AND strftime('%Y', {d}) = ?2 GROUP BY strftime('%Y-%m', {d}) ORDER BY 2 DESC",
+            d = LOCAL_DATE
+        ))?;
         let mut rows = stmt.query(rusqlite::params![library_id, year_str])?;
         let mut out = Vec::new();
         while let Some(row) = rows.next()? {
@@ -490,10 +1563,202 @@ impl Database {
         Ok(out)
     }
 
+    /// Current `get_years`/`get_months` counts for the buckets `photo_ids` fall in now, plus
+    /// the `previous` (year, month) buckets. Emptied buckets come back with a count of 0, so
+    /// the caller can patch its sidebar instead of refetching every category.
+    pub fn get_category_deltas(
+        &self,
+        library_id: i64,
+        photo_ids: &[i64],
+        previous: &[(i32, i32)],
+    ) -> SqlResult<CategoryDeltas> {
+        let conn = self.lock_conn();
+        let mut buckets: std::collections::BTreeSet<(i32, i32)> = previous.iter().copied().collect();
+        // Stay under SQLite's bound-parameter limit
+        for chunk in photo_ids.chunks(900) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                "SELECT DISTINCT CAST(strftime('%Y', {d}) AS INTEGER), CAST(strftime('%m', {d}) AS INTEGER)
+                 FROM photos WHERE library_id = ? AND is_deleted = 0 AND id IN ({p})",
+                d = LOCAL_DATE,
+                p = placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let params = std::iter::once(library_id).chain(chunk.iter().copied());
+            let mut rows = stmt.query(rusqlite::params_from_iter(params))?;
+            while let Some(row) = rows.next()? {
+                buckets.insert((row.get(0)?, row.get(1)?));
+            }
+        }
+
+        let mut month_stmt = conn.prepare(&format!(
+            "SELECT COUNT(*) FROM photos WHERE library_id = ?1 AND is_deleted = 0
+               AND strftime('%Y-%m', {d}) = ?2",
+            d = LOCAL_DATE
+        ))?;
+        let mut year_stmt = conn.prepare(&format!(
+            "SELECT COUNT(*) FROM photos WHERE library_id = ?1 AND is_deleted = 0
+               AND strftime('%Y', {d}) = ?2",
+            d = LOCAL_DATE
+        ))?;
+        let mut deltas = CategoryDeltas::default();
+        for &(year, month) in &buckets {
+            let ym = format!("{:04}-{:02}", year, month);
+            let count = month_stmt.query_row(rusqlite::params![library_id, ym], |row| row.get(0))?;
+            deltas.months.push(CategoryMonth { year, month, count });
+        }
+        let years: std::collections::BTreeSet<i32> = buckets.iter().map(|&(y, _)| y).collect();
+        for year in years {
+            let count = year_stmt.query_row(rusqlite::params![library_id, format!("{:04}", year)], |row| row.get(0))?;
+            deltas.years.push(CategoryYear { year, count });
+        }
+        Ok(deltas)
+    }
+
+    /// Per-day counts within one month (calendar heatmap), newest day first
+    pub fn get_days(&self, library_id: i64, year: i32, month: i32) -> SqlResult<Vec<CategoryDay>> {
+        let conn = self.lock_conn();
+        let ym = format!("{:04}-{:02}", year, month);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT CAST(strftime('%d', {d}) AS INTEGER) AS dd, COUNT(*) 
+             FROM photos WHERE library_id = ?1 AND is_deleted = 0 AND strftime('%Y-%m', {d}) = ?2
+             GROUP BY dd ORDER BY dd DESC",
+            d = LOCAL_DATE
+        ))?;
+        let mut rows = stmt.query(rusqlite::params![library_id, ym])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(CategoryDay {
+                year,
+                month,
+                day: row.get(0)?,
+                count: row.get(1)?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Geotagged photos in capture order, plus how many photos were left out for lacking
+    /// (valid) coordinates
+    pub fn get_geotagged_photos(&self, library_id: i64) -> SqlResult<(Vec<GeoPhoto>, i64)> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT path, filename, taken_at, gps_lat, gps_lon FROM photos
+             WHERE library_id = ?1 AND is_deleted = 0
+             ORDER BY COALESCE(taken_at, modified_at), path",
+        )?;
+        let mut rows = stmt.query([library_id])?;
+        let mut photos = Vec::new();
+        let mut skipped = 0;
+        while let Some(row) = rows.next()? {
+            let (lat, lon): (Option<f64>, Option<f64>) = (row.get(3)?, row.get(4)?);
+            match (lat, lon) {
+                (Some(lat), Some(lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {
+                    photos.push(GeoPhoto {
+                        path: row.get(0)?,
+                        filename: row.get(1)?,
+                        taken_at: row.get(2)?,
+                        lat,
+                        lon,
+                    });
+                }
+                _ => skipped += 1,
+            }
+        }
+        Ok((photos, skipped))
+    }
+
+    /// Grid-cluster geotagged photos into `cell_deg`-sized cells, largest clusters first.
+    /// Photos without GPS are skipped.
+    pub fn get_location_clusters(&self, library_id: i64, cell_deg: f64) -> SqlResult<Vec<LocationCluster>> {
+        let conn = self.lock_conn();
+        // Newest first, so the first photo seen in a cell becomes its cover
+        let mut stmt = conn.prepare(
+            "SELECT id, path, gps_lat, gps_lon FROM photos
+             WHERE library_id = ?1 AND is_deleted = 0 AND gps_lat IS NOT NULL AND gps_lon IS NOT NULL
+             ORDER BY COALESCE(taken_at, modified_at) DESC",
+        )?;
+        let mut rows = stmt.query([library_id])?;
+
+        let mut index: std::collections::HashMap<(i64, i64), usize> = std::collections::HashMap::new();
+        let mut clusters: Vec<LocationCluster> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let (id, path, lat, lon): (i64, String, f64, f64) =
+                (row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?);
+            let cell = ((lat / cell_deg).floor() as i64, (lon / cell_deg).floor() as i64);
+            match index.get(&cell) {
+                Some(&i) => {
+                    let c = &mut clusters[i];
+                    // lat/lon hold running sums until the end
+                    c.lat += lat;
+                    c.lon += lon;
+                    c.min_lat = c.min_lat.min(lat);
+                    c.min_lon = c.min_lon.min(lon);
+                    c.max_lat = c.max_lat.max(lat);
+                    c.max_lon = c.max_lon.max(lon);
+                    c.count += 1;
+                }
+                None => {
+                    index.insert(cell, clusters.len());
+                    clusters.push(LocationCluster {
+                        lat,
+                        lon,
+                        min_lat: lat,
+                        min_lon: lon,
+                        max_lat: lat,
+                        max_lon: lon,
+                        count: 1,
+                        cover_id: id,
+                        cover_path: path,
+                    });
+                }
+            }
+        }
+        for c in &mut clusters {
+            c.lat /= c.count as f64;
+            c.lon /= c.count as f64;
+        }
+        clusters.sort_by(|a, b| b.count.cmp(&a.count));
+        Ok(clusters)
+    }
+
+    /// Geotagged photos inside a map viewport. When `min_lon > max_lon` the box crosses the
+    /// antimeridian, so longitudes wrap (e.g. 170 → -170).
+    pub fn get_photos_in_bounds(
+        &self,
+        library_id: i64,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+        limit: i64,
+    ) -> SqlResult<Vec<PhotoRecord>> {
+        let conn = self.lock_conn();
+        let lon_clause = if min_lon <= max_lon {
+            "gps_lon BETWEEN ?4 AND ?5"
+        } else {
+            "(gps_lon >= ?4 OR gps_lon <= ?5)"
+        };
+        let sql = format!(
+            "SELECT {} FROM photos
+             WHERE library_id = ?1 AND is_deleted = 0 AND gps_lat BETWEEN ?2 AND ?3 AND {}
+             ORDER BY COALESCE(taken_at, modified_at) DESC LIMIT ?6",
+            Self::photo_select_cols(),
+            lon_clause
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(rusqlite::params![library_id, min_lat, max_lat, min_lon, max_lon, limit])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::photo_from_row(row, String::new())?);
+        }
+        Ok(out)
+    }
+
     pub fn get_folders_flat(&self, library_id: i64) -> SqlResult<Vec<(String, i64)>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare(
-            "SELECT folder_rel, COUNT(*) FROM photos WHERE library_id = ?1 GROUP BY folder_rel ORDER BY folder_rel",
+            "SELECT folder_rel, COUNT(*) FROM photos WHERE library_id = ?1 AND is_deleted = 0 GROUP BY folder_rel ORDER BY folder_rel",
         )?;
         let mut rows = stmt.query([library_id])?;
         let mut out = Vec::new();
@@ -503,10 +1768,101 @@ impl Database {
         Ok(out)
     }
 
+    /// Cover per folder: the newest live photo, preferring ones whose thumbnail is already on
+    /// disk. `folder` limits the result to that one folder.
+    pub fn get_folder_covers(
+        &self,
+        library_id: i64,
+        folder: Option<&str>,
+    ) -> SqlResult<std::collections::BTreeMap<String, CoverPhoto>> {
+        let (filter, params): (&str, Vec<String>) = match folder {
+            Some(f) => (" AND folder_rel = ?2", vec![f.to_string()]),
+            None => ("", Vec::new()),
+        };
+        self.cover_photos(
+            library_id,
+            "folder_rel",
+            filter,
+            &params,
+            &format!("{}, COALESCE(taken_at, modified_at) DESC, id DESC", HAS_THUMB),
+        )
+    }
+
+    /// Cover per year (and per month with `include_months`) in local time: favorites first,
+    /// then photos with a thumbnail, then the most recent. Estimated dates are included, as
+    /// they are in the timeline itself.
+    pub fn get_timeline_covers(&self, library_id: i64, include_months: bool) -> SqlResult<TimelineCovers> {
+        let order = format!("is_favorite DESC, {}, COALESCE(taken_at, modified_at) DESC, id DESC", HAS_THUMB);
+        // Same buckets as the timeline counts: undated files fall back to their mtime
+        let filter = "";
+        let years = self.cover_photos(library_id, &format!("strftime('%Y', {})", LOCAL_DATE), filter, &[], &order)?;
+        let months = if include_months {
+            Some(self.cover_photos(library_id, &format!("strftime('%Y-%m', {})", LOCAL_DATE), filter, &[], &order)?)
+        } else {
+            None
+        };
+        Ok(TimelineCovers { years, months })
+    }
+
+    /// Helper: one cover per `key_expr` group. Up to `COVER_CANDIDATES` rows per group are
+    /// ranked by `order`; the first whose recorded thumbnail hasn't gone missing from disk
+    /// wins, falling back to the top-ranked row.
+    fn cover_photos(
+        &self,
+        library_id: i64,
+        key_expr: &str,
+        filter: &str,
+        params: &[String],
+        order: &str,
+    ) -> SqlResult<std::collections::BTreeMap<String, CoverPhoto>> {
+        let conn = self.lock_conn();
+        let sql = format!(
+            "SELECT key, id, path, thumb_path FROM (
+                SELECT {key} AS key, id, path, thumb_path,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY {key}
+                           ORDER BY {order}
+                       ) AS rank
+                FROM photos
+                WHERE library_id = ?1 AND is_deleted = 0 AND is_missing = 0{filter}
+             )
+             WHERE key IS NOT NULL AND rank <= {limit}
+             ORDER BY key, rank",
+            key = key_expr,
+            order = order,
+            filter = filter,
+            limit = COVER_CANDIDATES
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut bound: Vec<&dyn rusqlite::ToSql> = vec![&library_id];
+        bound.extend(params.iter().map(|p| p as &dyn rusqlite::ToSql));
+        let mut rows = stmt.query(bound.as_slice())?;
+
+        // (cover, settled): a cover is settled once its thumbnail is known not to be stale
+        let mut covers: std::collections::BTreeMap<String, (CoverPhoto, bool)> = std::collections::BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let key: String = row.get(0)?;
+            if covers.get(&key).is_some_and(|(_, settled)| *settled) {
+                continue;
+            }
+            let thumb: Option<String> = row.get(3)?;
+            let stale = thumb.is_some_and(|t| !t.is_empty() && !Path::new(&t).exists());
+            let candidate = CoverPhoto { id: row.get(1)?, path: row.get(2)? };
+            match covers.get_mut(&key) {
+                Some(entry) if !stale => *entry = (candidate, true),
+                Some(_) => {}
+                None => {
+                    covers.insert(key, (candidate, !stale));
+                }
+            }
+        }
+        Ok(covers.into_iter().map(|(k, (cover, _))| (k, cover)).collect())
+    }
+
     pub fn get_media_type_counts(&self, library_id: i64) -> SqlResult<Vec<(String, i64)>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare(
-            "SELECT media_type, COUNT(*) FROM photos WHERE library_id = ?1 GROUP BY media_type",
+            "SELECT media_type, COUNT(*) FROM photos WHERE library_id = ?1 AND is_deleted = 0 GROUP BY media_type",
         )?;
         let mut rows = stmt.query([library_id])?;
         let mut out = Vec::new();
@@ -517,7 +1873,7 @@ impl Database {
     }
 
     pub fn get_photo_by_id(&self, id: i64) -> SqlResult<Option<PhotoRecord>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let sql = format!("SELECT {} FROM photos WHERE id = ?1", Self::photo_select_cols());
         let mut stmt = conn.prepare(&sql)?;
         let mut rows = stmt.query([id])?;
@@ -527,25 +1883,146 @@ impl Database {
         Ok(None)
     }
 
+    /// Photos for a list of ids, in the order given. Ids that no longer exist are skipped;
+    /// repeated ids appear once, at their first position.
+    pub fn get_photos_by_ids(&self, ids: &[i64]) -> SqlResult<Vec<PhotoRecord>> {
+        let conn = self.lock_conn();
+        let mut found: std::collections::HashMap<i64, PhotoRecord> = std::collections::HashMap::new();
+        // Stay under SQLite's bound-parameter limit
+        for chunk in ids.chunks(900) {
+            let placeholders: Vec<String> = chunk.iter().enumerate().map(|(i, _)| format!("?{}", i + 1)).collect();
+            let sql = format!(
+                "SELECT {}, l.root_path \
+                 FROM photos p JOIN library l ON l.id = p.library_id \
+                 WHERE p.id IN ({})",
+                Self::photo_select_cols_with("p"),
+                placeholders.join(", "),
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let root_col = stmt.column_count() - 1;
+            let mut rows = stmt.query(rusqlite::params_from_iter(chunk))?;
+            while let Some(row) = rows.next()? {
+                let root_path: String = row.get(root_col)?;
+                let photo = Self::photo_from_row(row, library_name(&root_path))?;
+                found.insert(photo.id, photo);
+            }
+        }
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    /// Look up a photo by absolute path (any library)
+    pub fn get_photo_by_path(&self, path: &str) -> SqlResult<Option<PhotoRecord>> {
+        let conn = self.lock_conn();
+        let sql = format!("SELECT {} FROM photos WHERE path = ?1 LIMIT 1", Self::photo_select_cols());
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query([path])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(Self::photo_from_row(row, String::new())?));
+        }
+        Ok(None)
+    }
+
+    /// Photos with this filename and byte size — a cheap identity check when paths differ
+    /// (e.g. the same library mounted elsewhere on another machine)
+    pub fn find_photos_by_name_and_size(&self, filename: &str, size_bytes: i64) -> SqlResult<Vec<PhotoRecord>> {
+        let conn = self.lock_conn();
+        let sql = format!(
+            "SELECT {} FROM photos WHERE filename = ?1 AND size_bytes = ?2",
+            Self::photo_select_cols()
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(rusqlite::params![filename, size_bytes])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::photo_from_row(row, String::new())?);
+        }
+        Ok(out)
+    }
+
+    /// Filenames (case-insensitive) that occur in more than one folder of a library —
+    /// a cheap hint at duplicate imports without hashing file contents
+    pub fn find_filename_collisions(&self, library_id: i64) -> SqlResult<Vec<FilenameCollision>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, path, filename, folder_rel, size_bytes FROM photos
+             WHERE library_id = ?1 AND is_deleted = 0 AND lower(filename) IN (
+                 SELECT lower(filename) FROM photos WHERE library_id = ?1 AND is_deleted = 0
+                 GROUP BY lower(filename) HAVING COUNT(DISTINCT folder_rel) > 1
+             )
+             ORDER BY lower(filename), folder_rel, filename",
+        )?;
+        let mut rows = stmt.query([library_id])?;
+        let mut out: Vec<FilenameCollision> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let filename: String = row.get(2)?;
+            let photo = CollisionPhoto {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                folder_rel: row.get(3)?,
+                size_bytes: row.get(4)?,
+            };
+            match out.last_mut() {
+                Some(group) if group.filename.eq_ignore_ascii_case(&filename) => group.photos.push(photo),
+                _ => out.push(FilenameCollision { filename, photos: vec![photo] }),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Photos whose longer edge is at most `max_dimension` or whose file is at most `max_bytes`
+    /// (either threshold may be omitted). Uses stored dimensions, so unknown sizes never match
+    /// on dimension. Smallest first.
+    pub fn find_small_images(
+        &self,
+        library_id: i64,
+        max_dimension: Option<i64>,
+        max_bytes: Option<i64>,
+    ) -> SqlResult<SmallImages> {
+        let conn = self.lock_conn();
+        let mut conds: Vec<String> = Vec::new();
+        if let Some(d) = max_dimension {
+            conds.push(format!(
+                "(width > 0 AND height > 0 AND MAX(width, height) <= {})",
+                d
+            ));
+        }
+        if let Some(b) = max_bytes {
+            conds.push(format!("size_bytes <= {}", b));
+        }
+        if conds.is_empty() {
+            return Ok(SmallImages { photos: Vec::new(), count: 0, total_bytes: 0 });
+        }
+        let sql = format!(
+            "SELECT {} FROM photos
+             WHERE library_id = ? AND is_deleted = 0 AND media_type = 'photo' AND ({})
+             ORDER BY COALESCE(width * height, 0), size_bytes, path",
+            Self::photo_select_cols(),
+            conds.join(" OR ")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let photos = stmt
+            .query_map([library_id], |row| Self::photo_from_row(row, String::new()))?
+            .collect::<SqlResult<Vec<_>>>()?;
+        let total_bytes = photos.iter().map(|p| p.size_bytes).sum();
+        Ok(SmallImages { count: photos.len(), total_bytes, photos })
+    }
+
     pub fn get_all_libraries(&self) -> SqlResult<Vec<LibraryInfo>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare(
-            "SELECT l.id, l.root_path, COUNT(p.id) FROM library l LEFT JOIN photos p ON p.library_id = l.id GROUP BY l.id ORDER BY l.root_path",
+            "SELECT l.id, l.root_path, COUNT(p.id), l.flatten_folders FROM library l LEFT JOIN photos p ON p.library_id = l.id AND p.is_deleted = 0 GROUP BY l.id ORDER BY l.root_path",
         )?;
         let mut rows = stmt.query([])?;
         let mut out = Vec::new();
         while let Some(row) = rows.next()? {
             let root_path: String = row.get(1)?;
-            let name = std::path::Path::new(&root_path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("Library")
-                .to_string();
+            let name = library_name(&root_path);
             out.push(LibraryInfo {
                 id: row.get(0)?,
                 root_path,
                 name,
                 photo_count: row.get(2)?,
+                flatten_folders: row.get::<_, i32>(3).unwrap_or(0) != 0,
             });
         }
         Ok(out)
@@ -560,20 +2037,20 @@ impl Database {
         if library_ids.is_empty() {
             return Ok(Vec::new());
         }
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let placeholders: Vec<String> = library_ids.iter().enumerate().map(|(i, _)| format!("?{}", i + 1)).collect();
         let sql = format!(
-            "SELECT p.id, p.path, p.filename, p.folder_rel, p.taken_at, p.modified_at, p.media_type, p.size_bytes, p.width, p.height, \
-             p.is_favorite, p.is_deleted, p.deleted_at, p.camera_make, p.camera_model, p.lens, p.iso, p.shutter_speed, p.aperture, p.focal_length, p.gps_lat, p.gps_lon, \
-             l.root_path \
+            "SELECT {}, l.root_path \
              FROM photos p JOIN library l ON l.id = p.library_id \
              WHERE p.library_id IN ({}) AND p.is_deleted = 0 \
-             ORDER BY COALESCE(p.taken_at, p.modified_at) DESC, p.path LIMIT ?{} OFFSET ?{}",
+             ORDER BY COALESCE(p.taken_at, p.modified_at) DESC, p.path, p.id LIMIT ?{} OFFSET ?{}",
+            Self::photo_select_cols_with("p"),
             placeholders.join(", "),
             library_ids.len() + 1,
             library_ids.len() + 2,
         );
         let mut stmt = conn.prepare(&sql)?;
+        let root_col = stmt.column_count() - 1;
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = library_ids.iter().map(|id| Box::new(*id) as Box<dyn rusqlite::ToSql>).collect();
         params.push(Box::new(limit));
         params.push(Box::new(offset));
@@ -582,44 +2059,15 @@ impl Database {
         let mut rows = stmt.query(param_refs.as_slice())?;
         let mut out = Vec::new();
         while let Some(row) = rows.next()? {
-            let root_path: String = row.get(22)?;
-            let source = std::path::Path::new(&root_path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("Library")
-                .to_string();
-            // Use manual construction because source comes from col 22 (root_path)
-            out.push(PhotoRecord {
-                id: row.get(0)?,
-                path: row.get(1)?,
-                filename: row.get(2)?,
-                folder_rel: row.get(3)?,
-                taken_at: row.get(4)?,
-                modified_at: row.get(5)?,
-                media_type: row.get(6)?,
-                size_bytes: row.get(7)?,
-                width: row.get(8)?,
-                height: row.get(9)?,
-                source,
-                is_favorite: row.get::<_, i32>(10).unwrap_or(0) != 0,
-                is_deleted: row.get::<_, i32>(11).unwrap_or(0) != 0,
-                deleted_at: row.get(12)?,
-                camera_make: row.get(13)?,
-                camera_model: row.get(14)?,
-                lens: row.get(15)?,
-                iso: row.get(16)?,
-                shutter_speed: row.get(17)?,
-                aperture: row.get(18)?,
-                focal_length: row.get(19)?,
-                gps_lat: row.get(20)?,
-                gps_lon: row.get(21)?,
-            });
+            // root_path is appended after the standard columns
+            let root_path: String = row.get(root_col)?;
+            out.push(Self::photo_from_row(row, library_name(&root_path))?);
         }
         Ok(out)
     }
 
     pub fn count_photos_for_library(&self, library_id: i64) -> SqlResult<i64> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         conn.query_row(
             "SELECT COUNT(*) FROM photos WHERE library_id = ?1",
             [library_id],
@@ -632,7 +2080,7 @@ impl Database {
         if library_ids.is_empty() {
             return Ok(0);
         }
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let placeholders: Vec<String> = library_ids.iter().enumerate().map(|(i, _)| format!("?{}", i + 1)).collect();
         let sql = format!(
             "SELECT COUNT(*) FROM photos WHERE library_id IN ({}) AND is_deleted = 0",
@@ -644,9 +2092,42 @@ impl Database {
         stmt.query_row(param_refs.as_slice(), |row| row.get(0))
     }
 
+    /// All sidebar badge counts for the given libraries in a single pass
+    pub fn get_library_stats(&self, library_ids: &[i64]) -> SqlResult<LibraryStats> {
+        if library_ids.is_empty() {
+            return Ok(LibraryStats::default());
+        }
+        let conn = self.lock_conn();
+        let placeholders: Vec<String> = library_ids.iter().enumerate().map(|(i, _)| format!("?{}", i + 1)).collect();
+        let sql = format!(
+            "SELECT \
+                COALESCE(SUM(is_deleted = 0), 0), \
+                COALESCE(SUM(is_deleted = 0 AND media_type = 'photo'), 0), \
+                COALESCE(SUM(is_deleted = 0 AND media_type = 'video'), 0), \
+                COALESCE(SUM(is_deleted = 0 AND is_favorite = 1), 0), \
+                COALESCE(SUM(is_deleted = 1), 0), \
+                COALESCE(SUM(is_deleted = 0 AND gps_lat IS NOT NULL AND gps_lon IS NOT NULL), 0) \
+             FROM photos WHERE library_id IN ({})",
+            placeholders.join(", ")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let params: Vec<Box<dyn rusqlite::ToSql>> = library_ids.iter().map(|id| Box::new(*id) as Box<dyn rusqlite::ToSql>).collect();
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        stmt.query_row(param_refs.as_slice(), |row| {
+            Ok(LibraryStats {
+                total: row.get(0)?,
+                photos: row.get(1)?,
+                videos: row.get(2)?,
+                favorites: row.get(3)?,
+                trash: row.get(4)?,
+                with_gps: row.get(5)?,
+            })
+        })
+    }
+
     /// Get all library root paths (for path validation / security)
     pub fn get_library_root_paths(&self) -> SqlResult<Vec<String>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare("SELECT root_path FROM library")?;
         let mut rows = stmt.query([])?;
         let mut out = Vec::new();
@@ -659,7 +2140,7 @@ impl Database {
     // ── Favorites ──
 
     pub fn toggle_favorite(&self, photo_id: i64) -> SqlResult<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let current: i32 = conn.query_row(
             "SELECT is_favorite FROM photos WHERE id = ?1",
             [photo_id],
@@ -673,20 +2154,10 @@ impl Database {
         Ok(new_val != 0)
     }
 
-    #[allow(dead_code)]
-    pub fn get_favorites_count(&self) -> SqlResult<i64> {
-        let conn = self.conn.lock().unwrap();
-        conn.query_row(
-            "SELECT COUNT(*) FROM photos WHERE is_favorite = 1 AND is_deleted = 0",
-            [],
-            |row| row.get(0),
-        )
-    }
-
     // ── Trash ──
 
     pub fn soft_delete(&self, photo_ids: &[i64]) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
         for id in photo_ids {
             conn.execute(
@@ -697,67 +2168,216 @@ impl Database {
         Ok(())
     }
 
-    pub fn restore_from_trash(&self, photo_ids: &[i64]) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
-        for id in photo_ids {
-            conn.execute(
-                "UPDATE photos SET is_deleted = 0, deleted_at = NULL WHERE id = ?1",
-                [id],
-            )?;
+    /// Helper: ids of the non-trashed photos in `library_ids` matching `filter`
+    fn filtered_photo_ids(conn: &Connection, library_ids: &[i64], filter: &PhotoFilter) -> SqlResult<Vec<i64>> {
+        if library_ids.is_empty() {
+            return Ok(Vec::new());
         }
-        Ok(())
-    }
-
-    #[allow(dead_code)]
-    pub fn get_trash_count(&self) -> SqlResult<i64> {
-        let conn = self.conn.lock().unwrap();
-        conn.query_row(
-            "SELECT COUNT(*) FROM photos WHERE is_deleted = 1",
-            [],
-            |row| row.get(0),
-        )
+        let placeholders = vec!["?"; library_ids.len()].join(", ");
+        let (filters, extra) = Self::photo_filter_sql(filter);
+        let sql = format!(
+            "SELECT id FROM photos WHERE library_id IN ({}) AND is_deleted = 0{}",
+            placeholders, filters
+        );
+        let mut param_refs: Vec<&dyn rusqlite::ToSql> = Vec::new();
+        for id in library_ids {
+            param_refs.push(id);
+        }
+        for e in &extra {
+            param_refs.push(e);
+        }
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(param_refs), |row| row.get(0))?;
+        rows.collect()
     }
 
-    // ── File operations ──
+    /// Trash every photo matching `filter` in one transaction. Returns the ids trashed.
+    pub fn soft_delete_by_filter(&self, library_ids: &[i64], filter: &PhotoFilter) -> SqlResult<Vec<i64>> {
+        let conn = self.lock_conn();
+        let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<Vec<i64>> {
+            let ids = Self::filtered_photo_ids(&conn, library_ids, filter)?;
+            let mut stmt = conn.prepare("UPDATE photos SET is_deleted = 1, deleted_at = ?1 WHERE id = ?2")?;
+            for id in &ids {
+                stmt.execute(rusqlite::params![now, id])?;
+            }
+            Ok(ids)
+        })();
+        match result {
+            Ok(ids) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(ids)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
 
-    pub fn hard_delete_photos(&self, photo_ids: &[i64]) -> SqlResult<Vec<String>> {
-        let conn = self.conn.lock().unwrap();
-        let mut paths = Vec::new();
+    /// Remove `tag_id` from every photo matching `filter` in one transaction.
+    /// Returns how many photos lost the tag.
+    pub fn untag_by_filter(&self, library_ids: &[i64], filter: &PhotoFilter, tag_id: i64) -> SqlResult<usize> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<usize> {
+            let ids = Self::filtered_photo_ids(&conn, library_ids, filter)?;
+            let mut stmt = conn.prepare("DELETE FROM photo_tags WHERE photo_id = ?1 AND tag_id = ?2")?;
+            let mut removed = 0;
+            for id in &ids {
+                removed += stmt.execute(rusqlite::params![id, tag_id])?;
+            }
+            Ok(removed)
+        })();
+        match result {
+            Ok(removed) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(removed)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    pub fn restore_from_trash(&self, photo_ids: &[i64]) -> SqlResult<()> {
+        let conn = self.lock_conn();
         for id in photo_ids {
-            if let Ok(path) = conn.query_row(
-                "SELECT path FROM photos WHERE id = ?1",
+            conn.execute(
+                "UPDATE photos SET is_deleted = 0, deleted_at = NULL WHERE id = ?1",
                 [id],
-                |row| row.get::<_, String>(0),
-            ) {
-                paths.push(path);
+            )?;
+        }
+        Ok(())
+    }
+
+    // ── File operations ──
+
+    /// Delete photo rows and everything linked to them in one transaction. Returns their paths.
+    pub fn hard_delete_photos(&self, photo_ids: &[i64]) -> SqlResult<Vec<String>> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<Vec<String>> {
+            let mut paths = Vec::new();
+            for id in photo_ids {
+                if let Ok(path) = conn.query_row(
+                    "SELECT path FROM photos WHERE id = ?1",
+                    [id],
+                    |row| row.get::<_, String>(0),
+                ) {
+                    paths.push(path);
+                }
+                conn.execute("DELETE FROM photo_tags WHERE photo_id = ?1", [id])?;
+                conn.execute("DELETE FROM album_photos WHERE photo_id = ?1", [id])?;
+                conn.execute("DELETE FROM recently_viewed WHERE photo_id = ?1", [id])?;
+                conn.execute("DELETE FROM faces WHERE photo_id = ?1", [id])?;
+                conn.execute("DELETE FROM photos WHERE id = ?1", [id])?;
+            }
+            Ok(paths)
+        })();
+        match result {
+            Ok(paths) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(paths)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
             }
-            conn.execute("DELETE FROM photo_tags WHERE photo_id = ?1", [id])?;
-            conn.execute("DELETE FROM album_photos WHERE photo_id = ?1", [id])?;
-            conn.execute("DELETE FROM photos WHERE id = ?1", [id])?;
         }
-        Ok(paths)
     }
 
-    pub fn rename_photo(&self, photo_id: i64, new_filename: &str) -> SqlResult<String> {
-        let conn = self.conn.lock().unwrap();
-        let old_path: String = conn.query_row(
-            "SELECT path FROM photos WHERE id = ?1",
-            [photo_id],
-            |row| row.get(0),
-        )?;
-        let old = std::path::Path::new(&old_path);
-        let new_path = old.with_file_name(new_filename).to_string_lossy().to_string();
+    /// Every photo of a library as `(id, path, is_missing)`, for checking against disk
+    pub fn get_photo_paths(&self, library_id: i64) -> SqlResult<Vec<(i64, String, bool)>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare("SELECT id, path, is_missing FROM photos WHERE library_id = ?1")?;
+        let rows = stmt.query_map([library_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get::<_, i32>(2)? != 0))
+        })?;
+        rows.collect()
+    }
+
+    pub fn set_missing(&self, photo_ids: &[i64], missing: bool) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let mut stmt = conn.prepare("UPDATE photos SET is_missing = ?1 WHERE id = ?2")?;
+            for id in photo_ids {
+                stmt.execute(rusqlite::params![missing as i32, id])?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT"),
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Photos flagged missing by the last reconcile, as `(id, path)`
+    pub fn get_missing_photos(&self, library_id: i64) -> SqlResult<Vec<(i64, String)>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare("SELECT id, path FROM photos WHERE library_id = ?1 AND is_missing = 1")?;
+        let rows = stmt.query_map([library_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Point a photo row at a new path (used when a file is moved or renamed on disk)
+    pub fn move_photo(&self, photo_id: i64, new_path: &str, new_filename: &str) -> SqlResult<()> {
+        let conn = self.lock_conn();
         conn.execute(
             "UPDATE photos SET filename = ?1, path = ?2 WHERE id = ?3",
             rusqlite::params![new_filename, new_path, photo_id],
         )?;
-        Ok(new_path)
+        Ok(())
+    }
+
+    /// Apply several (photo_id, new_path, new_filename) moves in one transaction
+    pub fn move_photos(&self, moves: &[(i64, String, String)]) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        for (photo_id, new_path, new_filename) in moves {
+            if let Err(e) = conn.execute(
+                "UPDATE photos SET filename = ?1, path = ?2 WHERE id = ?3",
+                rusqlite::params![new_filename, new_path, photo_id],
+            ) {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(e);
+            }
+        }
+        conn.execute_batch("COMMIT")?;
+        Ok(())
     }
 
     // ── Tags ──
 
+    /// Tag a photo with its embedded IPTC/XMP keywords, creating tags that don't exist yet.
+    /// Matching is case-insensitive so "Beach" joins an existing "beach" tag.
+    fn apply_keyword_tags(conn: &Connection, photo_id: i64, keywords: &[String]) -> SqlResult<()> {
+        for keyword in keywords {
+            let existing = conn
+                .prepare_cached("SELECT id FROM tags WHERE name = ?1 COLLATE NOCASE LIMIT 1")?
+                .query_row([keyword], |row| row.get::<_, i64>(0));
+            let tag_id = match existing {
+                Ok(id) => id,
+                Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    conn.prepare_cached("INSERT INTO tags (name) VALUES (?1)")?.execute([keyword])?;
+                    conn.last_insert_rowid()
+                }
+                Err(e) => return Err(e),
+            };
+            conn.prepare_cached("INSERT OR IGNORE INTO photo_tags (photo_id, tag_id) VALUES (?1, ?2)")?
+                .execute(rusqlite::params![photo_id, tag_id])?;
+        }
+        Ok(())
+    }
+
     pub fn create_tag(&self, name: &str, color: &str) -> SqlResult<TagRecord> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         conn.execute(
             "INSERT INTO tags (name, color) VALUES (?1, ?2)",
             rusqlite::params![name, color],
@@ -767,14 +2387,55 @@ impl Database {
     }
 
     pub fn delete_tag(&self, tag_id: i64) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         conn.execute("DELETE FROM photo_tags WHERE tag_id = ?1", [tag_id])?;
         conn.execute("DELETE FROM tags WHERE id = ?1", [tag_id])?;
         Ok(())
     }
 
+    /// Fold `source_tag_id` into `target_tag_id` in one transaction: its photos get the target
+    /// tag (photos already carrying it are skipped, so the (photo_id, tag_id) key holds), then
+    /// the source tag is deleted. Returns how many photo associations moved, or None when
+    /// either tag doesn't exist.
+    pub fn merge_tags(&self, source_tag_id: i64, target_tag_id: i64) -> SqlResult<Option<usize>> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<Option<usize>> {
+            let found: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM tags WHERE id IN (?1, ?2)",
+                [source_tag_id, target_tag_id],
+                |row| row.get(0),
+            )?;
+            if found != 2 {
+                return Ok(None);
+            }
+            let moved = conn.execute(
+                "INSERT OR IGNORE INTO photo_tags (photo_id, tag_id)
+                 SELECT photo_id, ?2 FROM photo_tags WHERE tag_id = ?1",
+                [source_tag_id, target_tag_id],
+            )?;
+            conn.execute("DELETE FROM photo_tags WHERE tag_id = ?1", [source_tag_id])?;
+            conn.execute("DELETE FROM tags WHERE id = ?1", [source_tag_id])?;
+            Ok(Some(moved))
+        })();
+        match result {
+            Ok(Some(moved)) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(Some(moved))
+            }
+            Ok(None) => {
+                conn.execute_batch("ROLLBACK")?;
+                Ok(None)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
     pub fn get_tags(&self) -> SqlResult<Vec<TagRecord>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare("SELECT id, name, color FROM tags ORDER BY name")?;
         let rows = stmt.query_map([], |row| {
             Ok(TagRecord {
@@ -787,7 +2448,7 @@ impl Database {
     }
 
     pub fn tag_photos(&self, photo_ids: &[i64], tag_id: i64) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         for pid in photo_ids {
             conn.execute(
                 "INSERT OR IGNORE INTO photo_tags (photo_id, tag_id) VALUES (?1, ?2)",
@@ -798,7 +2459,7 @@ impl Database {
     }
 
     pub fn untag_photos(&self, photo_ids: &[i64], tag_id: i64) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         for pid in photo_ids {
             conn.execute(
                 "DELETE FROM photo_tags WHERE photo_id = ?1 AND tag_id = ?2",
@@ -809,7 +2470,7 @@ impl Database {
     }
 
     pub fn get_tags_for_photo(&self, photo_id: i64) -> SqlResult<Vec<TagRecord>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare(
             "SELECT t.id, t.name, t.color FROM tags t JOIN photo_tags pt ON pt.tag_id = t.id WHERE pt.photo_id = ?1 ORDER BY t.name"
         )?;
@@ -825,26 +2486,63 @@ impl Database {
 
     // ── Albums ──
 
-    pub fn create_album(&self, name: &str) -> SqlResult<AlbumRecord> {
-        let conn = self.conn.lock().unwrap();
+    pub fn create_album(&self, name: &str, parent_album_id: Option<i64>) -> SqlResult<AlbumRecord> {
+        let conn = self.lock_conn();
         let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
         conn.execute(
-            "INSERT INTO albums (name, created_at) VALUES (?1, ?2)",
-            rusqlite::params![name, now],
+            "INSERT INTO albums (name, created_at, parent_album_id) VALUES (?1, ?2, ?3)",
+            rusqlite::params![name, now, parent_album_id],
         )?;
         let id = conn.last_insert_rowid();
-        Ok(AlbumRecord { id, name: name.to_string(), created_at: now, photo_count: 0, cover_path: None })
+        Ok(AlbumRecord {
+            id,
+            name: name.to_string(),
+            created_at: now,
+            photo_count: 0,
+            cover_path: None,
+            parent_album_id,
+        })
     }
 
-    pub fn delete_album(&self, album_id: i64) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM album_photos WHERE album_id = ?1", [album_id])?;
-        conn.execute("DELETE FROM albums WHERE id = ?1", [album_id])?;
-        Ok(())
+    /// Delete an album. With `cascade` its whole subtree goes too; otherwise its
+    /// sub-albums move up to its parent so nothing is orphaned.
+    pub fn delete_album(&self, album_id: i64, cascade: bool) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let ids: Vec<i64> = if cascade {
+                conn.prepare(
+                    "WITH RECURSIVE sub(id) AS (
+                         SELECT ?1 UNION SELECT a.id FROM albums a JOIN sub ON a.parent_album_id = sub.id
+                     ) SELECT id FROM sub",
+                )?
+                .query_map([album_id], |row| row.get(0))?
+                .collect::<SqlResult<_>>()?
+            } else {
+                conn.execute(
+                    "UPDATE albums SET parent_album_id = (SELECT parent_album_id FROM albums WHERE id = ?1)
+                     WHERE parent_album_id = ?1",
+                    [album_id],
+                )?;
+                vec![album_id]
+            };
+            for id in ids {
+                conn.execute("DELETE FROM album_photos WHERE album_id = ?1", [id])?;
+                conn.execute("DELETE FROM albums WHERE id = ?1", [id])?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT"),
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
     }
 
     pub fn rename_album(&self, album_id: i64, new_name: &str) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         conn.execute(
             "UPDATE albums SET name = ?1 WHERE id = ?2",
             rusqlite::params![new_name, album_id],
@@ -853,11 +2551,12 @@ impl Database {
     }
 
     pub fn get_albums(&self) -> SqlResult<Vec<AlbumRecord>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare(
             "SELECT a.id, a.name, a.created_at,
                     (SELECT COUNT(*) FROM album_photos ap WHERE ap.album_id = a.id) as cnt,
-                    (SELECT p.path FROM album_photos ap2 JOIN photos p ON p.id=ap2.photo_id WHERE ap2.album_id=a.id ORDER BY ap2.position LIMIT 1)
+                    (SELECT p.path FROM album_photos ap2 JOIN photos p ON p.id=ap2.photo_id WHERE ap2.album_id=a.id ORDER BY ap2.position LIMIT 1),
+                    a.parent_album_id
              FROM albums a ORDER BY a.created_at DESC"
         )?;
         let rows = stmt.query_map([], |row| {
@@ -867,13 +2566,67 @@ impl Database {
                 created_at: row.get(2)?,
                 photo_count: row.get(3)?,
                 cover_path: row.get(4)?,
+                parent_album_id: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Albums containing a photo, by name
+    pub fn get_albums_for_photo(&self, photo_id: i64) -> SqlResult<Vec<AlbumRecord>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT a.id, a.name, a.created_at,
+                    (SELECT COUNT(*) FROM album_photos ap WHERE ap.album_id = a.id) as cnt,
+                    (SELECT p.path FROM album_photos ap2 JOIN photos p ON p.id=ap2.photo_id WHERE ap2.album_id=a.id ORDER BY ap2.position LIMIT 1),
+                    a.parent_album_id
+             FROM albums a
+             WHERE a.id IN (SELECT album_id FROM album_photos WHERE photo_id = ?1)
+             ORDER BY a.name COLLATE NOCASE"
+        )?;
+        let rows = stmt.query_map([photo_id], |row| {
+            Ok(AlbumRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+                photo_count: row.get(3)?,
+                cover_path: row.get(4)?,
+                parent_album_id: row.get(5)?,
             })
         })?;
         rows.collect()
     }
 
+    /// Albums nested under their parents (same per-level order as `get_albums`).
+    /// Albums whose parent no longer exists are shown at the top level.
+    pub fn get_album_tree(&self) -> SqlResult<Vec<AlbumNode>> {
+        let albums = self.get_albums()?;
+        let ids: std::collections::HashSet<i64> = albums.iter().map(|a| a.id).collect();
+        let mut children: std::collections::HashMap<Option<i64>, Vec<AlbumRecord>> =
+            std::collections::HashMap::new();
+        for album in albums {
+            let parent = album.parent_album_id.filter(|p| ids.contains(p));
+            children.entry(parent).or_default().push(album);
+        }
+        fn build(
+            parent: Option<i64>,
+            children: &mut std::collections::HashMap<Option<i64>, Vec<AlbumRecord>>,
+        ) -> Vec<AlbumNode> {
+            children
+                .remove(&parent)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|album| {
+                    let id = album.id;
+                    AlbumNode { album, children: build(Some(id), children) }
+                })
+                .collect()
+        }
+        Ok(build(None, &mut children))
+    }
+
     pub fn add_photos_to_album(&self, album_id: i64, photo_ids: &[i64]) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let max_pos: i64 = conn.query_row(
             "SELECT COALESCE(MAX(position),0) FROM album_photos WHERE album_id = ?1",
             [album_id],
@@ -888,8 +2641,52 @@ impl Database {
         Ok(())
     }
 
+    /// Add photos to several albums in one transaction, appending to each album's order
+    pub fn add_photos_to_albums(&self, album_ids: &[i64], photo_ids: &[i64]) -> SqlResult<Vec<AlbumAddResult>> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<Vec<AlbumAddResult>> {
+            let mut out = Vec::with_capacity(album_ids.len());
+            for &album_id in album_ids {
+                let mut next_pos: i64 = conn.query_row(
+                    "SELECT COALESCE(MAX(position),0) FROM album_photos WHERE album_id = ?1",
+                    [album_id],
+                    |row| row.get(0),
+                )?;
+                let mut added = 0;
+                for pid in photo_ids {
+                    // INSERT OR IGNORE reports 0 rows for photos already in the album
+                    let n = conn.execute(
+                        "INSERT OR IGNORE INTO album_photos (album_id, photo_id, position) VALUES (?1, ?2, ?3)",
+                        rusqlite::params![album_id, pid, next_pos + 1],
+                    )?;
+                    if n > 0 {
+                        next_pos += 1;
+                        added += 1;
+                    }
+                }
+                out.push(AlbumAddResult {
+                    album_id,
+                    added,
+                    already_present: photo_ids.len() as i64 - added,
+                });
+            }
+            Ok(out)
+        })();
+        match result {
+            Ok(out) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(out)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
     pub fn remove_photos_from_album(&self, album_id: i64, photo_ids: &[i64]) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         for pid in photo_ids {
             conn.execute(
                 "DELETE FROM album_photos WHERE album_id = ?1 AND photo_id = ?2",
@@ -899,11 +2696,74 @@ impl Database {
         Ok(())
     }
 
+    /// Move one photo to `new_index` (clamped, so a huge index means "end") and renumber
+    /// the album's positions 1..n in a single transaction
+    pub fn reorder_album(&self, album_id: i64, photo_id: i64, new_index: usize) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let mut ids: Vec<i64> = conn
+                .prepare("SELECT photo_id FROM album_photos WHERE album_id = ?1 ORDER BY position, rowid")?
+                .query_map([album_id], |row| row.get(0))?
+                .collect::<SqlResult<_>>()?;
+            let from = ids
+                .iter()
+                .position(|id| *id == photo_id)
+                .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+            let id = ids.remove(from);
+            ids.insert(new_index.min(ids.len()), id);
+
+            let mut stmt = conn.prepare("UPDATE album_photos SET position = ?1 WHERE album_id = ?2 AND photo_id = ?3")?;
+            for (i, pid) in ids.iter().enumerate() {
+                stmt.execute(rusqlite::params![i as i64 + 1, album_id, pid])?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT"),
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Stats over an album's photos (trashed ones excluded) in one grouped query.
+    /// None when the album doesn't exist; an empty album gives zeroed stats.
+    pub fn get_album_stats(&self, album_id: i64) -> SqlResult<Option<AlbumStats>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT p.media_type, COUNT(p.id), COALESCE(SUM(p.size_bytes), 0), MIN(p.taken_at), MAX(p.taken_at)
+             FROM albums a
+             LEFT JOIN album_photos ap ON ap.album_id = a.id
+             LEFT JOIN photos p ON p.id = ap.photo_id AND p.is_deleted = 0
+             WHERE a.id = ?1
+             GROUP BY p.media_type",
+        )?;
+        let mut rows = stmt.query([album_id])?;
+        let mut stats: Option<AlbumStats> = None;
+        while let Some(row) = rows.next()? {
+            let stats = stats.get_or_insert_with(AlbumStats::default);
+            let media_type: Option<String> = row.get(0)?;
+            let count: i64 = row.get(1)?;
+            stats.photo_count += count;
+            stats.total_bytes += row.get::<_, i64>(2)?;
+            let (earliest, latest): (Option<String>, Option<String>) = (row.get(3)?, row.get(4)?);
+            stats.earliest = [stats.earliest.take(), earliest].into_iter().flatten().min();
+            stats.latest = [stats.latest.take(), latest].into_iter().flatten().max();
+            // Photo-less albums yield a single all-NULL group
+            if let Some(media_type) = media_type {
+                stats.media_types.insert(media_type, count);
+            }
+        }
+        Ok(stats)
+    }
+
     pub fn get_album_photos(&self, album_id: i64) -> SqlResult<Vec<PhotoRecord>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let sql = format!(
-            "SELECT p.{} FROM photos p JOIN album_photos ap ON ap.photo_id = p.id WHERE ap.album_id = ?1 AND p.is_deleted = 0 ORDER BY ap.position",
-            Self::photo_select_cols()
+            "SELECT {} FROM photos p JOIN album_photos ap ON ap.photo_id = p.id WHERE ap.album_id = ?1 AND p.is_deleted = 0 ORDER BY ap.position",
+            Self::photo_select_cols_with("p")
         );
         let mut stmt = conn.prepare(&sql)?;
         let mut rows = stmt.query([album_id])?;
@@ -914,11 +2774,500 @@ impl Database {
         Ok(out)
     }
 
+    /// Every stored face embedding of the given libraries as `(face_id, embedding)`,
+    /// trashed photos excluded
+    #[cfg(feature = "faces")]
+    pub fn get_face_embeddings(&self, library_ids: &[i64]) -> SqlResult<Vec<(i64, Vec<f32>)>> {
+        let conn = self.lock_conn();
+        let placeholders = vec!["?"; library_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT f.id, f.embedding FROM faces f JOIN photos p ON p.id = f.photo_id
+             WHERE p.library_id IN ({}) AND p.is_deleted = 0 ORDER BY f.id",
+            placeholders
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(library_ids.iter()), |row| {
+            Ok((row.get(0)?, crate::faces::decode_embedding(&row.get::<_, Vec<u8>>(1)?)))
+        })?;
+        rows.collect()
+    }
+
+    /// Store the clusters from a `group_faces` pass, as `(face_id, cluster_id)`
+    #[cfg(feature = "faces")]
+    pub fn set_face_clusters(&self, assignments: &[(i64, i64)]) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let mut stmt = conn.prepare("UPDATE faces SET cluster_id = ?2 WHERE id = ?1")?;
+            for (face_id, cluster_id) in assignments {
+                stmt.execute([face_id, cluster_id])?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(())
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// People for the sidebar: each cluster with its photo count and the newest photo as cover,
+    /// largest first
+    #[cfg(feature = "faces")]
+    pub fn get_face_clusters(&self, library_ids: &[i64]) -> SqlResult<Vec<FaceCluster>> {
+        let conn = self.lock_conn();
+        let placeholders = vec!["?"; library_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT f.cluster_id, COUNT(DISTINCT p.id),
+                    (SELECT p2.id FROM faces f2 JOIN photos p2 ON p2.id = f2.photo_id
+                      WHERE f2.cluster_id = f.cluster_id AND p2.is_deleted = 0
+                      ORDER BY COALESCE(p2.taken_at, p2.modified_at) DESC, p2.id LIMIT 1)
+             FROM faces f JOIN photos p ON p.id = f.photo_id
+             WHERE f.cluster_id IS NOT NULL AND p.library_id IN ({}) AND p.is_deleted = 0
+             GROUP BY f.cluster_id
+             ORDER BY COUNT(DISTINCT p.id) DESC, f.cluster_id",
+            placeholders
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(library_ids.iter()), |row| {
+            Ok(FaceCluster {
+                cluster_id: row.get(0)?,
+                photo_count: row.get(1)?,
+                cover_photo_id: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Photos with at least one face in `cluster_id`, newest first
+    #[cfg(feature = "faces")]
+    pub fn get_photos_for_face_cluster(&self, cluster_id: i64, limit: i64, offset: i64) -> SqlResult<Vec<PhotoRecord>> {
+        let conn = self.lock_conn();
+        let sql = format!(
+            "SELECT {} FROM photos p
+             WHERE p.is_deleted = 0 AND p.id IN (SELECT photo_id FROM faces WHERE cluster_id = ?1)
+             ORDER BY COALESCE(p.taken_at, p.modified_at) DESC, p.id LIMIT ?2 OFFSET ?3",
+            Self::photo_select_cols_with("p")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query([cluster_id, limit, offset])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::photo_from_row(row, String::new())?);
+        }
+        Ok(out)
+    }
+
+    /// Every indexed file path, trash included (thumbnail cache maintenance)
+    pub fn get_all_photo_paths(&self) -> SqlResult<Vec<String>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare("SELECT path FROM photos")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Stored thumbnail paths for the given photos, keyed by id. Photos without one are omitted.
+    pub fn get_thumb_paths(&self, photo_ids: &[i64]) -> SqlResult<std::collections::HashMap<i64, String>> {
+        let conn = self.lock_conn();
+        let mut paths = std::collections::HashMap::new();
+        for chunk in photo_ids.chunks(900) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                "SELECT id, thumb_path FROM photos WHERE id IN ({}) AND thumb_path IS NOT NULL AND thumb_path != ''",
+                placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let mut rows = stmt.query(rusqlite::params_from_iter(chunk.iter()))?;
+            while let Some(row) = rows.next()? {
+                paths.insert(row.get(0)?, row.get(1)?);
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Point photos (by file path) at new thumbnail files
+    pub fn set_thumb_paths(&self, updates: &[(String, String)]) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare("UPDATE photos SET thumb_path = ?1 WHERE path = ?2")?;
+        for (path, thumb) in updates {
+            stmt.execute(rusqlite::params![thumb, path])?;
+        }
+        Ok(())
+    }
+
+    /// Whether the user has edited `column` of the row in `photos` (see `mark_edited_sql`)
+    fn is_edited_sql(column: &str) -> String {
+        format!("instr(photos.edited_fields || ',', ',{},') > 0", column)
+    }
+
+    /// `SET` fragment recording that the user edited `column`, so rescans leave it alone.
+    /// `edited_fields` holds the names as ",category,title".
+    fn mark_edited_sql(column: &str) -> String {
+        format!(
+            "edited_fields = CASE WHEN {} THEN edited_fields ELSE edited_fields || ',{}' END",
+            Self::is_edited_sql(column),
+            column
+        )
+    }
+
+    /// Manually set the category of photos (overrides the scan heuristic, including on
+    /// later rescans)
+    pub fn set_photo_category(&self, photo_ids: &[i64], category: &str) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        let sql = format!("UPDATE photos SET category = ?1, {} WHERE id = ?2", Self::mark_edited_sql("category"));
+        for id in photo_ids {
+            conn.execute(&sql, rusqlite::params![category, id])?;
+        }
+        Ok(())
+    }
+
+    // ── Bulk edits ──
+
+    /// Shift capture dates by `offset_seconds` in one transaction (e.g. a camera clock set
+    /// to the wrong zone). Returns `(id, path)` of each photo whose date moved.
+    pub fn shift_taken_at(&self, photo_ids: &[i64], offset_seconds: i64) -> SqlResult<Vec<(i64, String)>> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<Vec<(i64, String)>> {
+            let mut select = conn.prepare("SELECT path, taken_at FROM photos WHERE id = ?1")?;
+            let mut update = conn.prepare("UPDATE photos SET taken_at = ?1, date_source = 'manual' WHERE id = ?2")?;
+            let mut shifted = Vec::new();
+            for &id in photo_ids {
+                let row = select.query_row([id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+                });
+                let (path, taken_at) = match row {
+                    Ok((path, Some(taken_at))) => (path, taken_at),
+                    Ok((_, None)) | Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+                    Err(e) => return Err(e),
+                };
+                let Some(new_date) = chrono::DateTime::parse_from_rfc3339(&taken_at)
+                    .ok()
+                    .and_then(|t| t.with_timezone(&Utc).checked_add_signed(chrono::Duration::seconds(offset_seconds)))
+                else {
+                    continue;
+                };
+                update.execute(rusqlite::params![new_date.format("%Y-%m-%dT%H:%M:%SZ").to_string(), id])?;
+                shifted.push((id, path));
+            }
+            Ok(shifted)
+        })();
+        match result {
+            Ok(shifted) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(shifted)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Set one of `BULK_EDIT_FIELDS` on every given photo in one transaction (None clears it).
+    /// The field is marked edited, so rescans keep the value. Returns `(id, path)` of each
+    /// photo updated.
+    pub fn set_photo_field(&self, photo_ids: &[i64], field: &str, value: Option<&str>) -> SqlResult<Vec<(i64, String)>> {
+        // The column name is spliced into SQL, so it must come from the whitelist
+        let Some(column) = BULK_EDIT_FIELDS.iter().find(|&&f| f == field) else {
+            return Err(rusqlite::Error::InvalidColumnName(field.to_string()));
+        };
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<Vec<(i64, String)>> {
+            let mut stmt = conn.prepare(&format!(
+                "UPDATE photos SET {} = ?1, {} WHERE id = ?2 RETURNING path",
+                column,
+                Self::mark_edited_sql(column)
+            ))?;
+            let mut updated = Vec::new();
+            for &id in photo_ids {
+                match stmt.query_row(rusqlite::params![value, id], |row| row.get::<_, String>(0)) {
+                    Ok(path) => updated.push((id, path)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(updated)
+        })();
+        match result {
+            Ok(updated) => {
+                conn.execute_batch("COMMIT")?;
+                Ok(updated)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    // ── Date inference ──
+
+    /// Estimate capture dates for photos with no EXIF date (their `taken_at` is just the
+    /// file mtime, often a copy date) from sequentially named siblings in the same folder
+    /// that do have one: interpolated between the nearest dated neighbours by position,
+    /// or copied from the only one available. EXIF dates are never touched.
+    /// Returns how many photos got an estimate.
+    pub fn infer_dates(&self, library_id: i64) -> SqlResult<usize> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, folder_rel, filename, taken_at, utc_offset_minutes,
+                    (date_is_estimated = 1 OR taken_at IS NULL OR taken_at = modified_at)
+             FROM photos WHERE library_id = ?1 AND is_deleted = 0",
+        )?;
+        struct Row {
+            id: i64,
+            filename: String,
+            taken_at: Option<chrono::DateTime<Utc>>,
+            offset: Option<i32>,
+            undated: bool,
+        }
+        let mut folders: std::collections::HashMap<String, Vec<Row>> = std::collections::HashMap::new();
+        let mut rows = stmt.query([library_id])?;
+        while let Some(row) = rows.next()? {
+            let taken: Option<String> = row.get(3)?;
+            folders.entry(row.get(1)?).or_default().push(Row {
+                id: row.get(0)?,
+                filename: row.get(2)?,
+                taken_at: taken
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+                    .map(|t| t.with_timezone(&Utc)),
+                offset: row.get(4)?,
+                undated: row.get(5)?,
+            });
+        }
+        drop(rows);
+        drop(stmt);
+
+        let mut estimates: Vec<(i64, String, Option<i32>)> = Vec::new();
+        for files in folders.values_mut() {
+            files.sort_by(|a, b| natural_cmp(&a.filename, &b.filename));
+            let anchor = |i: usize| !files[i].undated && files[i].taken_at.is_some();
+            for i in 0..files.len() {
+                if !files[i].undated {
+                    continue;
+                }
+                let prefix = sequence_prefix(&files[i].filename);
+                let same_seq = |j: usize| sequence_prefix(&files[j].filename) == prefix;
+                let before = (0..i).rev().take_while(|&j| same_seq(j)).find(|&j| anchor(j));
+                let after = (i + 1..files.len()).take_while(|&j| same_seq(j)).find(|&j| anchor(j));
+                let (date, offset) = match (before, after) {
+                    (Some(b), Some(a)) => {
+                        let (tb, ta) = (files[b].taken_at.unwrap(), files[a].taken_at.unwrap());
+                        let frac = (i - b) as f64 / (a - b) as f64;
+                        let secs = ((ta - tb).num_seconds() as f64 * frac) as i64;
+                        (tb + chrono::Duration::seconds(secs), files[b].offset)
+                    }
+                    (Some(n), None) | (None, Some(n)) => (files[n].taken_at.unwrap(), files[n].offset),
+                    (None, None) => continue,
+                };
+                estimates.push((files[i].id, date.format("%Y-%m-%dT%H:%M:%SZ").to_string(), offset));
+            }
+        }
+
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let mut stmt = conn.prepare(
+                "UPDATE photos SET taken_at = ?1, utc_offset_minutes = ?2, date_is_estimated = 1, date_source = 'estimated' WHERE id = ?3",
+            )?;
+            for (id, date, offset) in &estimates {
+                stmt.execute(rusqlite::params![date, offset, id])?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT")?,
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(e);
+            }
+        }
+        Ok(estimates.len())
+    }
+
+    // ── Bursts ──
+
+    /// Dated photos of a library in capture order, with any cached burst signature.
+    /// Estimated dates are skipped — they can't place frames seconds apart.
+    pub fn get_burst_candidates(&self, library_id: i64) -> SqlResult<Vec<crate::burst::BurstCandidate>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, path, taken_at, CAST(strftime('%s', taken_at) AS INTEGER), burst_hash, sharpness
+             FROM photos
+             WHERE library_id = ?1 AND is_deleted = 0 AND media_type = 'photo'
+               AND taken_at IS NOT NULL AND date_is_estimated = 0
+             ORDER BY taken_at, path",
+        )?;
+        let rows = stmt.query_map([library_id], |row| {
+            Ok(crate::burst::BurstCandidate {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                taken_at: row.get(2)?,
+                taken_unix: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                burst_hash: row.get(4)?,
+                sharpness: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn set_burst_signatures(&self, signatures: &[(i64, i64, f64)]) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let mut stmt = conn.prepare("UPDATE photos SET burst_hash = ?1, sharpness = ?2 WHERE id = ?3")?;
+            for (id, hash, sharpness) in signatures {
+                stmt.execute(rusqlite::params![hash, sharpness, id])?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT"),
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    // ── EXIF re-parse ──
+
+    /// Photos of a library whose files are expected on disk, as `(id, path)`
+    pub fn get_photos_for_reparse(&self, library_id: i64) -> SqlResult<Vec<(i64, String)>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, path FROM photos WHERE library_id = ?1 AND media_type = 'photo' AND is_missing = 0 ORDER BY id",
+        )?;
+        let rows = stmt.query_map([library_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Overwrite the EXIF-derived columns from freshly parsed EXIF. The capture time is only
+    /// replaced when EXIF has one, so mtime fallbacks and inferred dates are left alone.
+    pub fn update_exif_metadata(&self, updates: &[(i64, crate::scan::ExifData)]) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let mut stmt = conn.prepare(
+                "UPDATE photos SET camera_make = ?1, camera_model = ?2, lens = ?3, iso = ?4,
+                     shutter_speed = ?5, aperture = ?6, shutter_seconds = ?7, f_number = ?8,
+                     focal_length = ?9, gps_lat = ?10, gps_lon = ?11, orientation = ?12, exposure_bias = ?13
+                 WHERE id = ?14",
+            )?;
+            let mut date_stmt = conn.prepare(
+                "UPDATE photos SET taken_at = ?1, utc_offset_minutes = ?2, date_is_estimated = 0, date_source = 'exif' WHERE id = ?3",
+            )?;
+            for (id, exif) in updates {
+                stmt.execute(rusqlite::params![
+                    exif.camera_make,
+                    exif.camera_model,
+                    exif.lens,
+                    exif.iso,
+                    exif.shutter_speed,
+                    exif.aperture,
+                    exif.shutter_seconds,
+                    exif.f_number,
+                    exif.focal_length,
+                    exif.gps_lat,
+                    exif.gps_lon,
+                    exif.orientation,
+                    exif.exposure_bias,
+                    id
+                ])?;
+                if let Some(taken_at) = &exif.taken_at {
+                    date_stmt.execute(rusqlite::params![taken_at, exif.utc_offset_minutes, id])?;
+                }
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT"),
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    // ── Blurhash ──
+
+    /// Photos that still need a BlurHash (backfill for libraries indexed without the flag)
+    pub fn get_photos_missing_blurhash(&self) -> SqlResult<Vec<(i64, String)>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, path FROM photos WHERE blurhash IS NULL AND media_type = 'photo' AND is_deleted = 0",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    pub fn set_blurhashes(&self, hashes: &[(i64, String)]) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute_batch("BEGIN")?;
+        let result = (|| -> SqlResult<()> {
+            let mut stmt = conn.prepare("UPDATE photos SET blurhash = ?1 WHERE id = ?2")?;
+            for (id, hash) in hashes {
+                stmt.execute(rusqlite::params![hash, id])?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT"),
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    // ── Catalog ──
+
+    /// Stream every photo (all libraries, trash included) to `f` one row at a time,
+    /// ordered by id. Returns the number of entries written.
+    pub fn for_each_catalog_entry<F>(&self, mut f: F) -> Result<i64, String>
+    where
+        F: FnMut(CatalogEntry) -> std::io::Result<()>,
+    {
+        let conn = self.lock_conn();
+        // Names are joined with the ASCII unit separator, which can't appear in user input
+        let sql = format!(
+            "SELECT {},
+                    (SELECT root_path FROM library WHERE id = photos.library_id),
+                    (SELECT group_concat(t.name, char(31)) FROM photo_tags pt JOIN tags t ON t.id = pt.tag_id WHERE pt.photo_id = photos.id),
+                    (SELECT group_concat(a.name, char(31)) FROM album_photos ap JOIN albums a ON a.id = ap.album_id WHERE ap.photo_id = photos.id)
+             FROM photos ORDER BY id",
+            Self::photo_select_cols()
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let cols = stmt.column_count();
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+        let split = |v: Option<String>| -> Vec<String> {
+            v.map(|s| s.split('\u{1f}').map(|n| n.to_string()).collect())
+                .unwrap_or_default()
+        };
+        let mut count = 0;
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let root_path: String = row.get(cols - 3).map_err(|e| e.to_string())?;
+            let photo = Self::photo_from_row(row, library_name(&root_path)).map_err(|e| e.to_string())?;
+            let tags = split(row.get(cols - 2).map_err(|e| e.to_string())?);
+            let albums = split(row.get(cols - 1).map_err(|e| e.to_string())?);
+            f(CatalogEntry { photo, tags, albums }).map_err(|e| e.to_string())?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
     // ── Directory Cache ──
 
     /// Get the last scan timestamp for a directory (for hot-cache check)
     pub fn get_directory_scan_time(&self, dir_path: &str) -> SqlResult<Option<i64>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare(
             "SELECT last_scanned FROM directories WHERE path = ?1"
         )?;
@@ -931,7 +3280,7 @@ impl Database {
 
     /// Insert or update directory scan metadata
     pub fn upsert_directory(&self, dir_path: &str, photo_count: i64) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
@@ -946,7 +3295,7 @@ impl Database {
 
     /// Get all cached photos for a directory prefix (for instant re-opens)
     pub fn get_cached_photos_for_dir(&self, dir_path: &str) -> SqlResult<Vec<crate::thumb::ThumbnailInfo>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let pattern = format!("{}%", dir_path);
         let mut stmt = conn.prepare(
             "SELECT path, thumb_path, width, height, filename, size_bytes, date_modified_unix \
@@ -972,7 +3321,7 @@ impl Database {
 
     /// Get photo mtime by file path (for incremental scan)
     pub fn get_photo_mtime(&self, file_path: &str) -> SqlResult<Option<i64>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let mut stmt = conn.prepare(
             "SELECT date_modified_unix FROM photos WHERE path = ?1"
         )?;
@@ -983,6 +3332,35 @@ impl Database {
         Ok(None)
     }
 
+    /// Indexed photos in `folder_rel` and its subfolders ("" is the whole library),
+    /// keyed by path, for change detection during an incremental re-index
+    pub fn get_folder_index_state(
+        &self,
+        library_id: i64,
+        folder_rel: &str,
+    ) -> SqlResult<std::collections::HashMap<String, IndexedFile>> {
+        let conn = self.lock_conn();
+        let prefix = format!("{}{}", folder_rel, std::path::MAIN_SEPARATOR);
+        let mut stmt = conn.prepare(
+            "SELECT path, id, modified_at, size_bytes, motion_path, is_placeholder FROM photos
+             WHERE library_id = ?1
+               AND (?2 = '' OR folder_rel = ?2 OR substr(folder_rel, 1, length(?3)) = ?3)",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![library_id, folder_rel, prefix], |row| {
+            Ok((
+                row.get(0)?,
+                IndexedFile {
+                    id: row.get(1)?,
+                    modified_at: row.get(2)?,
+                    size_bytes: row.get(3)?,
+                    motion_path: row.get(4)?,
+                    is_placeholder: row.get::<_, i32>(5)? != 0,
+                },
+            ))
+        })?;
+        rows.collect()
+    }
+
     /// Upsert a photo record with thumbnail path and mtime
     pub fn upsert_photo_with_thumb(
         &self,
@@ -993,17 +3371,22 @@ impl Database {
         thumb_h: u32,
         mtime_unix: u64,
     ) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            r#"INSERT OR REPLACE INTO photos (
-                library_id, path, filename, folder_rel, taken_at, modified_at, media_type,
+        let conn = self.lock_conn();
+        let columns = "library_id, path, filename, folder_rel, taken_at, modified_at, media_type,
                 size_bytes, width, height, thumb_path, date_modified_unix,
                 camera_make, camera_model, lens, iso, shutter_speed, aperture,
-                focal_length, gps_lat, gps_lon
-            ) VALUES (
-                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12,
-                ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21
-            )"#,
+                focal_length, gps_lat, gps_lon, orientation, exposure_bias, utc_offset_minutes, blurhash, category,
+                shutter_seconds, f_number, color_space, is_hdr, title, caption, frame_count, date_source, is_placeholder, is_animated";
+        let id: i64 = conn.query_row(
+            &format!(
+                "INSERT INTO photos ({}) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12,
+                    ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
+                    ?31, ?32, ?33, ?34, ?35, ?36
+                ) {} RETURNING id",
+                columns,
+                Self::upsert_clause(columns)
+            ),
             rusqlite::params![
                 library_id,
                 scanned.path,
@@ -1026,15 +3409,32 @@ impl Database {
                 scanned.focal_length,
                 scanned.gps_lat,
                 scanned.gps_lon,
+                scanned.orientation,
+                scanned.exposure_bias,
+                scanned.utc_offset_minutes,
+                scanned.blurhash,
+                scanned.category,
+                scanned.shutter_seconds,
+                scanned.f_number,
+                scanned.color_space,
+                scanned.is_hdr,
+                scanned.title,
+                scanned.caption,
+                scanned.frame_count,
+                scanned.date_source,
+                scanned.is_placeholder,
+                scanned.is_animated,
             ],
+            |row| row.get(0),
         )?;
+        Self::apply_keyword_tags(&conn, id, &scanned.keywords)?;
         Ok(())
     }
 
     // ── Edit Persistence ──
 
     pub fn save_edit_params(&self, photo_path: &str, params_json: &str) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         conn.execute(
             "UPDATE photos SET edit_params = ?1 WHERE path = ?2",
             rusqlite::params![params_json, photo_path],
@@ -1043,7 +3443,7 @@ impl Database {
     }
 
     pub fn load_edit_params(&self, photo_path: &str) -> SqlResult<Option<String>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn();
         let result = conn.query_row(
             "SELECT edit_params FROM photos WHERE path = ?1",
             [photo_path],
@@ -1055,4 +3455,415 @@ impl Database {
             Err(e) => Err(e),
         }
     }
+
+    // ── Recently viewed ──
+
+    /// Record that a photo was opened, keeping only the newest `cap` entries
+    pub fn mark_viewed(&self, photo_id: i64, cap: i64) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        let now = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        conn.execute(
+            "INSERT INTO recently_viewed (photo_id, viewed_at) VALUES (?1, ?2)
+             ON CONFLICT(photo_id) DO UPDATE SET viewed_at = ?2",
+            rusqlite::params![photo_id, now],
+        )?;
+        conn.execute(
+            "DELETE FROM recently_viewed WHERE photo_id NOT IN
+             (SELECT photo_id FROM recently_viewed ORDER BY viewed_at DESC LIMIT ?1)",
+            [cap],
+        )?;
+        Ok(())
+    }
+
+    /// Recently opened photos, newest first; trashed or removed photos are skipped
+    pub fn get_recently_viewed(&self) -> SqlResult<Vec<PhotoRecord>> {
+        let conn = self.lock_conn();
+        let sql = format!(
+            "SELECT {} FROM recently_viewed rv JOIN photos p ON p.id = rv.photo_id
+             WHERE p.is_deleted = 0 ORDER BY rv.viewed_at DESC",
+            Self::photo_select_cols_with("p")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::photo_from_row(row, String::new())?);
+        }
+        Ok(out)
+    }
+
+    // ── Settings ──
+
+    pub fn get_setting(&self, key: &str) -> SqlResult<Option<String>> {
+        let conn = self.lock_conn();
+        let result = conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [key],
+            |row| row.get::<_, String>(0),
+        );
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2) \
+             ON CONFLICT(key) DO UPDATE SET value = ?2",
+            rusqlite::params![key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_setting(&self, key: &str) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+        Ok(())
+    }
+
+    /// Typed read of a JSON-encoded setting; None when unset or not decodable as `T`
+    pub fn get_setting_json<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.get_setting(key)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+    }
+
+    /// Store `value` JSON-encoded under `key`
+    pub fn set_setting_json<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> SqlResult<()> {
+        let json = serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.set_setting(key, &json)
+    }
+
+    // ── Scan timeouts ──
+
+    /// Remember files that timed out during a scan of `library_id`
+    pub fn record_scan_timeouts(&self, library_id: i64, paths: &[String]) -> SqlResult<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let conn = self.lock_conn();
+        let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let mut stmt = conn.prepare(
+            "INSERT OR REPLACE INTO scan_timeouts (library_id, path, timed_out_at) VALUES (?1, ?2, ?3)",
+        )?;
+        for path in paths {
+            stmt.execute(rusqlite::params![library_id, path, now])?;
+        }
+        Ok(())
+    }
+
+    pub fn get_scan_timeouts(&self, library_id: i64) -> SqlResult<Vec<String>> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare("SELECT path FROM scan_timeouts WHERE library_id = ?1 ORDER BY path")?;
+        let rows = stmt.query_map([library_id], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Forget timeouts for `paths` (parsed since, or gone); `None` clears the whole library
+    pub fn clear_scan_timeouts(&self, library_id: i64, paths: Option<&[String]>) -> SqlResult<()> {
+        let conn = self.lock_conn();
+        match paths {
+            None => {
+                conn.execute("DELETE FROM scan_timeouts WHERE library_id = ?1", [library_id])?;
+            }
+            Some(paths) => {
+                let mut stmt = conn.prepare("DELETE FROM scan_timeouts WHERE library_id = ?1 AND path = ?2")?;
+                for path in paths {
+                    stmt.execute(rusqlite::params![library_id, path])?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::scan::ScannedFile;
+    use std::path::PathBuf;
+
+    /// A catalog in a fresh temp file, deleted on drop
+    pub(crate) struct TestDb {
+        db: Database,
+        path: PathBuf,
+    }
+
+    impl std::ops::Deref for TestDb {
+        type Target = Database;
+        fn deref(&self) -> &Database {
+            &self.db
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    pub(crate) fn test_db(name: &str) -> TestDb {
+        let path = std::env::temp_dir().join(format!("ifoto-test-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        TestDb { db: Database::new(&path).unwrap(), path }
+    }
+
+    /// A plain photo under `/library` with an EXIF capture time (UTC) and offset
+    pub(crate) fn scanned(name: &str, taken_at: Option<&str>, utc_offset_minutes: Option<i32>) -> ScannedFile {
+        ScannedFile {
+            path: format!("/library/{}", name),
+            filename: name.to_string(),
+            folder_rel: String::new(),
+            taken_at: taken_at.map(str::to_string),
+            modified_at: "2020-01-01T00:00:00Z".to_string(),
+            media_type: "photo".to_string(),
+            size_bytes: 1000,
+            width: Some(4000),
+            height: Some(3000),
+            camera_make: None,
+            camera_model: None,
+            lens: None,
+            iso: None,
+            shutter_speed: None,
+            aperture: None,
+            shutter_seconds: None,
+            f_number: None,
+            focal_length: None,
+            gps_lat: None,
+            gps_lon: None,
+            orientation: None,
+            exposure_bias: None,
+            utc_offset_minutes,
+            blurhash: None,
+            category: None,
+            color_space: None,
+            is_hdr: false,
+            title: None,
+            caption: None,
+            keywords: Vec::new(),
+            frame_count: None,
+            is_animated: false,
+            date_source: taken_at.map(|_| "exif".to_string()),
+            is_placeholder: false,
+            motion_path: None,
+            raw_path: None,
+            jpeg_path: None,
+        }
+    }
+
+    #[test]
+    fn reindexing_keeps_ids_favorites_tags_and_albums() {
+        let db = test_db("reindex");
+        let library = db.get_or_create_library("/library").unwrap();
+        let files = vec![
+            scanned("a.jpg", Some("2021-06-01T10:00:00Z"), None),
+            scanned("b.jpg", Some("2021-06-02T10:00:00Z"), None),
+        ];
+        let first = db.insert_photos_batch(library, &files).unwrap();
+        let id = first[0].id;
+        db.toggle_favorite(id).unwrap();
+        let tag = db.create_tag("beach", "#00aaff").unwrap();
+        db.tag_photos(&[id], tag.id).unwrap();
+        let album = db.create_album("Summer", None).unwrap();
+        db.add_photos_to_album(album.id, &[id]).unwrap();
+        db.set_photo_category(&[id], "screenshot").unwrap();
+
+        let second = db.insert_photos_batch(library, &files).unwrap();
+        assert_eq!(
+            second.iter().map(|p| p.id).collect::<Vec<_>>(),
+            first.iter().map(|p| p.id).collect::<Vec<_>>()
+        );
+        let photo = db.get_photo_by_id(id).unwrap().unwrap();
+        assert!(photo.is_favorite);
+        assert_eq!(photo.category.as_deref(), Some("screenshot"));
+        let tags: Vec<String> = db.get_tags_for_photo(id).unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(tags, vec!["beach"]);
+        let in_album: Vec<i64> = db.get_album_photos(album.id).unwrap().iter().map(|p| p.id).collect();
+        assert_eq!(in_album, vec![id]);
+    }
+
+    #[test]
+    fn trashing_a_photo_decrements_its_year() {
+        let db = test_db("trash-counts");
+        let library = db.get_or_create_library("/library").unwrap();
+        let photos = db
+            .insert_photos_batch(
+                library,
+                &[
+                    scanned("a.jpg", Some("2021-03-01T10:00:00Z"), None),
+                    scanned("b.jpg", Some("2021-04-01T10:00:00Z"), None),
+                    scanned("c.jpg", Some("2019-04-01T10:00:00Z"), None),
+                ],
+            )
+            .unwrap();
+        let count = |year: i32| {
+            db.get_years(library).unwrap().into_iter().find(|y| y.year == year).map_or(0, |y| y.count)
+        };
+        assert_eq!((count(2021), count(2019)), (2, 1));
+
+        db.soft_delete(&[photos[0].id]).unwrap();
+        assert_eq!((count(2021), count(2019)), (1, 1));
+        let months = db.get_months(library, 2021).unwrap();
+        assert_eq!(months.iter().map(|m| (m.month, m.count)).collect::<Vec<_>>(), vec![(4, 1)]);
+
+        // A year left with nothing but trash disappears
+        db.soft_delete(&[photos[2].id]).unwrap();
+        assert!(db.get_years(library).unwrap().iter().all(|y| y.year != 2019));
+    }
+
+    #[test]
+    fn like_patterns_escape_wildcards_and_the_escape_character() {
+        assert_eq!(Database::like_escape("50%"), "50\\%");
+        assert_eq!(Database::like_escape("a_b"), "a\\_b");
+        assert_eq!(Database::like_escape("c:\\x"), "c:\\\\x");
+        assert_eq!(Database::like_contains("a_b"), "%a\\_b%");
+    }
+
+    #[test]
+    fn search_matches_wildcard_characters_literally() {
+        let db = test_db("search-escape");
+        let library = db.get_or_create_library("/library").unwrap();
+        db.insert_photos_batch(
+            library,
+            &[
+                scanned("50%_off.jpg", None, None),
+                scanned("500_off.jpg", None, None),
+                scanned("a_b.jpg", None, None),
+                scanned("axb.jpg", None, None),
+            ],
+        )
+        .unwrap();
+        let names = |query: &str| {
+            let mut names: Vec<String> = db
+                .search_photos(&[library], &SearchQuery::parse(query), None, 50)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.photo.filename)
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names("50%"), vec!["50%_off.jpg"]);
+        assert_eq!(names("a_b"), vec!["a_b.jpg"]);
+        assert!(names("").is_empty());
+        assert!(names("   ").is_empty());
+    }
+
+    #[test]
+    fn paging_through_identical_dates_has_no_gaps_or_repeats() {
+        let db = test_db("paging-ties");
+        let library = db.get_or_create_library("/library").unwrap();
+        // A burst: every frame shares one capture second (and one path order with the ids
+        // reversed against it, so the tiebreak matters)
+        let files: Vec<ScannedFile> = (0..25)
+            .rev()
+            .map(|i| scanned(&format!("IMG_{:03}.jpg", i), Some("2021-07-04T12:00:00Z"), None))
+            .collect();
+        let inserted = db.insert_photos_batch(library, &files).unwrap();
+
+        let mut seen = Vec::new();
+        for page in 0..5 {
+            let photos = db.get_photos(&[library], 6, page * 6, &PhotoFilter::default()).unwrap();
+            seen.extend(photos.into_iter().map(|p| p.id));
+        }
+        let mut unique = seen.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(seen.len(), 25);
+        assert_eq!(unique.len(), 25);
+        let mut expected: Vec<i64> = inserted.iter().map(|p| p.id).collect();
+        expected.sort();
+        assert_eq!(unique, expected);
+    }
+
+    #[test]
+    fn photos_dated_only_by_mtime_appear_in_their_month() {
+        let db = test_db("mtime-only");
+        let library = db.get_or_create_library("/library").unwrap();
+        let mut undated = scanned("copied.jpg", None, None);
+        undated.modified_at = "2022-03-15T09:00:00Z".to_string();
+        let inserted = db.insert_photos_batch(library, &[undated]).unwrap();
+
+        let months = db.get_months(library, 2022).unwrap();
+        assert_eq!(months.iter().map(|m| (m.month, m.count)).collect::<Vec<_>>(), vec![(3, 1)]);
+        let filter = PhotoFilter { year: Some(2022), month: Some(3), ..Default::default() };
+        let photos = db.get_photos(&[library], 100, 0, &filter).unwrap();
+        assert_eq!(photos.iter().map(|p| p.id).collect::<Vec<_>>(), vec![inserted[0].id]);
+    }
+
+    #[test]
+    fn thumb_paths_are_returned_only_for_photos_that_have_one() {
+        let db = test_db("thumb-paths");
+        let library = db.get_or_create_library("/library").unwrap();
+        let inserted = db
+            .insert_photos_batch(library, &[scanned("a.jpg", None, None), scanned("b.jpg", None, None)])
+            .unwrap();
+        db.set_thumb_paths(&[("/library/a.jpg".to_string(), "/cache/a.jpg".to_string())]).unwrap();
+
+        let paths = db.get_thumb_paths(&[inserted[0].id, inserted[1].id]).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths.get(&inserted[0].id).map(String::as_str), Some("/cache/a.jpg"));
+    }
+
+    #[cfg(feature = "faces")]
+    #[test]
+    fn face_clusters_list_their_photos_until_deleted() {
+        let db = test_db("faces");
+        let library = db.get_or_create_library("/library").unwrap();
+        let photos = db
+            .insert_photos_batch(library, &[scanned("a.jpg", None, None), scanned("b.jpg", None, None)])
+            .unwrap();
+        let embedding: Vec<u8> = [0.5f32, 0.5].iter().flat_map(|x| x.to_le_bytes()).collect();
+        for photo in &photos {
+            db.lock_conn()
+                .execute(
+                    "INSERT INTO faces (photo_id, x, y, w, h, embedding) VALUES (?1, 0.1, 0.1, 0.2, 0.2, ?2)",
+                    rusqlite::params![photo.id, embedding],
+                )
+                .unwrap();
+        }
+
+        let faces = db.get_face_embeddings(&[library]).unwrap();
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces[0].1, vec![0.5, 0.5]);
+        db.set_face_clusters(&[(faces[0].0, 1), (faces[1].0, 1)]).unwrap();
+        let clusters = db.get_face_clusters(&[library]).unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].photo_count, 2);
+        assert_eq!(db.get_photos_for_face_cluster(1, 100, 0).unwrap().len(), 2);
+
+        db.hard_delete_photos(&[photos[0].id]).unwrap();
+        assert_eq!(db.get_face_embeddings(&[library]).unwrap().len(), 1);
+        assert_eq!(db.get_photos_for_face_cluster(1, 100, 0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn late_evening_photos_group_by_local_date() {
+        let db = test_db("local-date");
+        let library = db.get_or_create_library("/library").unwrap();
+        db.insert_photos_batch(
+            library,
+            &[
+                // 23:30 at +02:00 on Dec 31 is 21:30 UTC the same day
+                scanned("east.jpg", Some("2021-12-31T21:30:00Z"), Some(120)),
+                // 23:30 at -05:00 on Dec 31 is already Jan 1 in UTC
+                scanned("west.jpg", Some("2022-01-01T04:30:00Z"), Some(-300)),
+            ],
+        )
+        .unwrap();
+
+        let years = db.get_years(library).unwrap();
+        assert_eq!(years.len(), 1);
+        assert_eq!((years[0].year, years[0].count), (2021, 2));
+        let months = db.get_months(library, 2021).unwrap();
+        assert_eq!(months.len(), 1);
+        assert_eq!((months[0].month, months[0].count), (12, 2));
+        let days = db.get_days(library, 2021, 12).unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!((days[0].day, days[0].count), (31, 2));
+    }
 }
@@ -0,0 +1,109 @@
+//! Command error type. Serialized to the frontend as `{ code, message }` so the UI can
+//! tell "no library loaded" from a missing file or a database failure without parsing text.
+//! `FileExists` adds `existingPath` and `suggestedName` so a rename can offer the free name.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AppError {
+    /// No database/library is open yet
+    NoLibrary,
+    NotFound(String),
+    InvalidInput(String),
+    /// The target already exists or the operation's preconditions no longer hold
+    Conflict(String),
+    /// A file already sits at the requested name; `suggested_name` is a free alternative
+    FileExists { existing_path: String, suggested_name: String },
+    Database(String),
+    Io(String),
+    Other(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NoLibrary => "no_library",
+            AppError::NotFound(_) => "not_found",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::Conflict(_) | AppError::FileExists { .. } => "conflict",
+            AppError::Database(_) => "database",
+            AppError::Io(_) => "io",
+            AppError::Other(_) => "other",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NoLibrary => f.write_str("No library loaded"),
+            AppError::FileExists { existing_path, .. } => write!(f, "{} already exists", existing_path),
+            AppError::NotFound(msg)
+            | AppError::InvalidInput(msg)
+            | AppError::Conflict(msg)
+            | AppError::Database(msg)
+            | AppError::Io(msg)
+            | AppError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = if matches!(self, AppError::FileExists { .. }) { 4 } else { 2 };
+        let mut s = serializer.serialize_struct("AppError", fields)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        if let AppError::FileExists { existing_path, suggested_name } = self {
+            s.serialize_field("existingPath", existing_path)?;
+            s.serialize_field("suggestedName", suggested_name)?;
+        }
+        s.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound("Not found".to_string()),
+            e => AppError::Database(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound(e.to_string()),
+            _ => AppError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Other(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        AppError::Other(e.to_string())
+    }
+}
+
+/// Helpers still report plain strings; they surface uncategorized
+impl From<String> for AppError {
+    fn from(msg: String) -> Self {
+        AppError::Other(msg)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(msg: &str) -> Self {
+        AppError::Other(msg.to_string())
+    }
+}
@@ -0,0 +1,285 @@
+//! Minimal in-place EXIF editing for bulk metadata fixes. Only ASCII tags are rewritten, and
+//! only within the space the file already reserves for them, so no offsets move and the rest
+//! of the file is byte-for-byte untouched. Works on JPEG (APP1 "Exif") and TIFF-based files.
+
+use std::path::Path;
+
+pub const TAG_MAKE: u16 = 0x010F;
+pub const TAG_MODEL: u16 = 0x0110;
+pub const TAG_DATE_TIME: u16 = 0x0132;
+pub const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+pub const TAG_DATE_TIME_DIGITIZED: u16 = 0x9004;
+pub const TAG_LENS_MODEL: u16 = 0xA434;
+
+pub const DATE_TAGS: &[u16] = &[TAG_DATE_TIME, TAG_DATE_TIME_ORIGINAL, TAG_DATE_TIME_DIGITIZED];
+
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TYPE_ASCII: u16 = 2;
+
+/// Outcome for one file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RewriteOutcome {
+    /// At least one tag was rewritten
+    Written,
+    /// None of the requested tags are present (nothing to do)
+    NoTags,
+    /// A replacement didn't fit its slot, or the file has no readable EXIF; file untouched
+    Unsupported,
+}
+
+/// Byte order aware reader over the TIFF block
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, at: usize) -> Option<u16> {
+        let b: [u8; 2] = self.data.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let b: [u8; 4] = self.data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    }
+}
+
+/// Offset of the TIFF header within the file
+fn tiff_start(data: &[u8]) -> Option<usize> {
+    if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        return Some(0);
+    }
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        if marker == 0xDA {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if marker == 0xE1 && data.get(pos + 4..pos + 10) == Some(b"Exif\0\0") {
+            return Some(pos + 10);
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+/// Absolute (start, len) of every ASCII value slot for `tags` in IFD0 and the Exif IFD
+fn ascii_slots(data: &[u8], tags: &[u16]) -> Option<Vec<(u16, usize, usize)>> {
+    let base = tiff_start(data)?;
+    let tiff = Tiff { data: &data[base..], big_endian: data.get(base) == Some(&b'M') };
+    let mut slots = Vec::new();
+    let mut ifds = vec![tiff.u32(4)? as usize];
+    let mut seen = 0;
+    while let Some(ifd) = ifds.pop() {
+        // Guards against malformed files pointing IFDs at each other
+        seen += 1;
+        if seen > 4 {
+            break;
+        }
+        let count = tiff.u16(ifd)? as usize;
+        for i in 0..count {
+            let entry = ifd + 2 + i * 12;
+            let tag = tiff.u16(entry)?;
+            if tag == TAG_EXIF_IFD {
+                ifds.push(tiff.u32(entry + 8)? as usize);
+                continue;
+            }
+            if !tags.contains(&tag) || tiff.u16(entry + 2)? != TYPE_ASCII {
+                continue;
+            }
+            let len = tiff.u32(entry + 4)? as usize;
+            let at = if len <= 4 { entry + 8 } else { tiff.u32(entry + 8)? as usize };
+            if at + len > tiff.data.len() {
+                continue;
+            }
+            slots.push((tag, base + at, len));
+        }
+    }
+    Some(slots)
+}
+
+/// Rewrite the ASCII tags in `tags`. `edit` receives each tag and its current value and
+/// returns the replacement (None leaves it alone). The file is only written if every
+/// replacement fits its existing slot (values shorter than the slot are NUL-padded).
+pub fn rewrite_ascii_tags(
+    path: &Path,
+    tags: &[u16],
+    mut edit: impl FnMut(u16, &str) -> Option<String>,
+) -> std::io::Result<RewriteOutcome> {
+    let mut data = std::fs::read(path)?;
+    let Some(slots) = ascii_slots(&data, tags) else {
+        return Ok(RewriteOutcome::Unsupported);
+    };
+    let mut changed = false;
+    for (tag, at, len) in slots {
+        let slot = &data[at..at + len];
+        let current = String::from_utf8_lossy(slot).trim_end_matches('\0').to_string();
+        let Some(new) = edit(tag, &current) else { continue };
+        // Keep at least one terminating NUL
+        if new.len() + 1 > len {
+            return Ok(RewriteOutcome::Unsupported);
+        }
+        let slot = &mut data[at..at + len];
+        slot.fill(0);
+        slot[..new.len()].copy_from_slice(new.as_bytes());
+        changed = true;
+    }
+    if !changed {
+        return Ok(RewriteOutcome::NoTags);
+    }
+    // Write beside the original, then swap, so a failure can't leave a truncated photo.
+    // The copy takes the original's permissions, which a fresh file wouldn't have.
+    let permissions = std::fs::metadata(path)?.permissions();
+    let tmp = path.with_extension("ifoto-tmp");
+    std::fs::write(&tmp, &data)?;
+    if let Err(e) = std::fs::set_permissions(&tmp, permissions).and_then(|_| std::fs::rename(&tmp, path)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(RewriteOutcome::Written)
+}
+
+/// Shift an EXIF "YYYY:MM:DD HH:MM:SS" value by `offset_seconds`
+pub fn shift_exif_datetime(value: &str, offset_seconds: i64) -> Option<String> {
+    let dt = chrono::NaiveDateTime::parse_from_str(value.trim(), "%Y:%m:%d %H:%M:%S").ok()?;
+    let shifted = dt.checked_add_signed(chrono::Duration::seconds(offset_seconds))?;
+    Some(shifted.format("%Y:%m:%d %H:%M:%S").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const MAKE: &str = "Canon";
+    const TAKEN: &str = "2021:05:03 23:30:00";
+
+    /// A minimal JPEG: an APP1 Exif segment whose IFD0 holds Make and a pointer to an Exif
+    /// IFD holding DateTimeOriginal. Both values sit outside their entries (longer than 4
+    /// bytes), as in camera files.
+    fn fixture(big_endian: bool) -> Vec<u8> {
+        let u16b = |v: u16| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        let u32b = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        let entry = |tiff: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32| {
+            tiff.extend(u16b(tag));
+            tiff.extend(u16b(kind));
+            tiff.extend(u32b(count));
+            tiff.extend(u32b(value));
+        };
+        let make = format!("{}\0", MAKE);
+        let taken = format!("{}\0", TAKEN);
+        // Header (8) + IFD0 with two entries (2 + 24 + 4)
+        let make_at = 8 + 30;
+        let exif_ifd_at = make_at + make.len() as u32;
+        // Exif IFD with one entry (2 + 12 + 4)
+        let taken_at = exif_ifd_at + 18;
+
+        let mut tiff = Vec::new();
+        tiff.extend(if big_endian { b"MM" } else { b"II" });
+        tiff.extend(u16b(42));
+        tiff.extend(u32b(8));
+        tiff.extend(u16b(2));
+        entry(&mut tiff, TAG_MAKE, TYPE_ASCII, make.len() as u32, make_at);
+        entry(&mut tiff, TAG_EXIF_IFD, 4, 1, exif_ifd_at);
+        tiff.extend(u32b(0));
+        tiff.extend(make.as_bytes());
+        tiff.extend(u16b(1));
+        entry(&mut tiff, TAG_DATE_TIME_ORIGINAL, TYPE_ASCII, taken.len() as u32, taken_at);
+        tiff.extend(u32b(0));
+        tiff.extend(taken.as_bytes());
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend((2 + 6 + tiff.len() as u16).to_be_bytes());
+        jpeg.extend(b"Exif\0\0");
+        jpeg.extend(tiff);
+        jpeg.extend([0xFF, 0xD9]);
+        jpeg
+    }
+
+    /// Write `data` to a temp file named after the test
+    fn write_fixture(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ifoto-exif-{}-{}.jpg", name, std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Current values of `tags`, read through a rewrite that changes nothing
+    fn read_tags(path: &Path, tags: &[u16]) -> Vec<(u16, String)> {
+        let mut seen = Vec::new();
+        rewrite_ascii_tags(path, tags, |tag, value| {
+            seen.push((tag, value.to_string()));
+            None
+        })
+        .unwrap();
+        seen.sort();
+        seen
+    }
+
+    #[test]
+    fn rewrites_values_that_fit_in_either_byte_order() {
+        for big_endian in [false, true] {
+            let path = write_fixture(if big_endian { "fit-be" } else { "fit-le" }, &fixture(big_endian));
+            let outcome = rewrite_ascii_tags(&path, &[TAG_MAKE, TAG_DATE_TIME_ORIGINAL], |tag, value| match tag {
+                TAG_MAKE => Some("Sony".to_string()),
+                _ => shift_exif_datetime(value, 3600),
+            })
+            .unwrap();
+            assert_eq!(outcome, RewriteOutcome::Written);
+            assert_eq!(
+                read_tags(&path, &[TAG_MAKE, TAG_DATE_TIME_ORIGINAL]),
+                vec![(TAG_MAKE, "Sony".to_string()), (TAG_DATE_TIME_ORIGINAL, "2021:05:04 00:30:00".to_string())]
+            );
+            // Only the value bytes changed
+            assert_eq!(std::fs::read(&path).unwrap().len(), fixture(big_endian).len());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn leaves_the_file_alone_when_a_value_does_not_fit() {
+        let original = fixture(false);
+        let path = write_fixture("too-long", &original);
+        let outcome = rewrite_ascii_tags(&path, &[TAG_MAKE], |_, _| Some("Hasselblad".to_string())).unwrap();
+        assert_eq!(outcome, RewriteOutcome::Unsupported);
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reports_missing_tags() {
+        let path = write_fixture("no-tags", &fixture(true));
+        let outcome = rewrite_ascii_tags(&path, &[TAG_LENS_MODEL], |_, _| Some("50mm".to_string())).unwrap();
+        assert_eq!(outcome, RewriteOutcome::NoTags);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_ifd_offsets() {
+        // IFD0 pointing past the end of the TIFF block
+        let mut data = fixture(false);
+        let ifd0 = 4 + 2 + 6 + 4;
+        data[ifd0..ifd0 + 4].copy_from_slice(&0xFFFFu32.to_le_bytes());
+        let path = write_fixture("bad-ifd", &data);
+        let outcome = rewrite_ascii_tags(&path, &[TAG_MAKE], |_, _| Some("Sony".to_string())).unwrap();
+        assert_eq!(outcome, RewriteOutcome::Unsupported);
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_the_original_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = write_fixture("permissions", &fixture(false));
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let outcome = rewrite_ascii_tags(&path, &[TAG_MAKE], |_, _| Some("Sony".to_string())).unwrap();
+        assert_eq!(outcome, RewriteOutcome::Written);
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+//! Face grouping for the People view (`faces` feature). Faces are stored per photo with an
+//! embedding; `cluster_embeddings` groups embeddings of the same person by cosine similarity.
+//! Grouping is heavy, so it runs on its own small pool — never the scan pool — and checks a
+//! cancel flag between faces.
+
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Minimum cosine similarity between a face and a cluster's mean to count as the same person
+const SAME_PERSON_SIMILARITY: f32 = 0.6;
+
+static FACE_POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// Set by `cancel`; cleared when a job starts
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+/// Half the logical CPUs, so a grouping pass leaves room for indexing and thumbnails
+fn face_pool() -> &'static rayon::ThreadPool {
+    FACE_POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads((num_cpus::get() / 2).max(1))
+            .thread_name(|i| format!("faces-{}", i))
+            .build()
+            .expect("failed to build face thread pool")
+    })
+}
+
+/// Run `job` on the face pool. The job gets the cancel flag to poll.
+pub async fn run<T: Send + 'static>(job: impl FnOnce(&AtomicBool) -> T + Send + 'static) -> Result<T, String> {
+    CANCEL.store(false, Ordering::SeqCst);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    face_pool().spawn(move || {
+        let _ = tx.send(job(&CANCEL));
+    });
+    rx.await.map_err(|_| "Face grouping stopped unexpectedly".to_string())
+}
+
+/// Ask the running face job to stop at its next check
+pub fn cancel() {
+    CANCEL.store(true, Ordering::SeqCst);
+}
+
+/// Embeddings are stored as little-endian f32s; a trailing partial value is ignored
+pub fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn normalized(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / norm).collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Greedy clustering in input order: each face joins the cluster whose mean embedding is most
+/// similar, if at least `SAME_PERSON_SIMILARITY`, else starts a new cluster. Returns
+/// `(face_id, cluster_id)` with cluster ids from 1, or `None` when cancelled.
+pub fn cluster_embeddings(faces: &[(i64, Vec<f32>)], cancel: &AtomicBool) -> Option<Vec<(i64, i64)>> {
+    // Running sums of normalized members; their direction is the cluster mean
+    let mut sums: Vec<Vec<f32>> = Vec::new();
+    let mut assignments = Vec::with_capacity(faces.len());
+    for (face_id, embedding) in faces {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        let face = normalized(embedding);
+        let best = sums
+            .par_iter()
+            .enumerate()
+            .filter(|(_, sum)| sum.len() == face.len())
+            .map(|(i, sum)| (i, dot(&normalized(sum), &face)))
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+        let cluster = match best {
+            Some((i, similarity)) if similarity >= SAME_PERSON_SIMILARITY => {
+                sums[i].iter_mut().zip(&face).for_each(|(s, x)| *s += x);
+                i
+            }
+            _ => {
+                sums.push(face);
+                sums.len() - 1
+            }
+        };
+        assignments.push((*face_id, cluster as i64 + 1));
+    }
+    Some(assignments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embeddings_round_trip_through_bytes() {
+        let bytes: Vec<u8> = [1.5f32, -2.0].iter().flat_map(|x| x.to_le_bytes()).collect();
+        assert_eq!(decode_embedding(&bytes), vec![1.5, -2.0]);
+    }
+
+    #[test]
+    fn similar_faces_share_a_cluster() {
+        let faces = vec![
+            (10, vec![1.0, 0.0, 0.1]),
+            (11, vec![0.0, 1.0, 0.0]),
+            (12, vec![2.0, 0.1, 0.0]),
+            (13, vec![0.1, 3.0, 0.2]),
+        ];
+        let clusters = cluster_embeddings(&faces, &AtomicBool::new(false)).unwrap();
+        assert_eq!(clusters, vec![(10, 1), (11, 2), (12, 1), (13, 2)]);
+    }
+
+    #[test]
+    fn a_cancelled_pass_assigns_nothing() {
+        let faces = vec![(1, vec![1.0, 0.0])];
+        assert_eq!(cluster_embeddings(&faces, &AtomicBool::new(true)), None);
+    }
+}
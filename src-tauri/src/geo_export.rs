@@ -0,0 +1,117 @@
+//! GPX and KML export of geotagged photos, in capture order, so a trip can be replayed in
+//! mapping software. Every point names its photo and links back to the file.
+
+use crate::db::GeoPhoto;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoFormat {
+    Gpx,
+    Kml,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoExportResult {
+    pub included: i64,
+    /// Photos left out for having no GPS position
+    pub skipped: i64,
+}
+
+/// Write `photos` (already in capture order) to `out_path`
+pub fn write_geo(photos: &[GeoPhoto], name: &str, format: GeoFormat, out_path: &Path) -> Result<(), String> {
+    let file = File::create(out_path).map_err(|e| e.to_string())?;
+    let mut w = BufWriter::new(file);
+    let written = match format {
+        GeoFormat::Gpx => write_gpx(photos, name, &mut w),
+        GeoFormat::Kml => write_kml(photos, name, &mut w),
+    };
+    written.and_then(|_| w.flush()).map_err(|e| e.to_string())
+}
+
+/// GPX 1.1: a waypoint per photo, then the same points as one track
+fn write_gpx(photos: &[GeoPhoto], name: &str, w: &mut impl Write) -> std::io::Result<()> {
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(w, r#"<gpx version="1.1" creator="ifoto" xmlns="http://www.topografix.com/GPX/1/1">"#)?;
+    writeln!(w, "  <metadata><name>{}</name></metadata>", xml_escape(name))?;
+    for p in photos {
+        writeln!(w, r#"  <wpt lat="{}" lon="{}">"#, p.lat, p.lon)?;
+        write_gpx_point_body(p, w)?;
+        writeln!(w, "  </wpt>")?;
+    }
+    writeln!(w, "  <trk>\n    <name>{}</name>\n    <trkseg>", xml_escape(name))?;
+    for p in photos {
+        writeln!(w, r#"      <trkpt lat="{}" lon="{}">"#, p.lat, p.lon)?;
+        write_gpx_point_body(p, w)?;
+        writeln!(w, "      </trkpt>")?;
+    }
+    writeln!(w, "    </trkseg>\n  </trk>\n</gpx>")
+}
+
+fn write_gpx_point_body(p: &GeoPhoto, w: &mut impl Write) -> std::io::Result<()> {
+    if let Some(t) = &p.taken_at {
+        writeln!(w, "    <time>{}</time>", xml_escape(t))?;
+    }
+    writeln!(w, "    <name>{}</name>", xml_escape(&p.filename))?;
+    writeln!(
+        w,
+        r#"    <link href="{}"><text>{}</text></link>"#,
+        xml_escape(&file_url(&p.path)),
+        xml_escape(&p.filename)
+    )
+}
+
+/// KML 2.2: a placemark per photo and a line through them in order
+fn write_kml(photos: &[GeoPhoto], name: &str, w: &mut impl Write) -> std::io::Result<()> {
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(w, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(w, "<Document>\n  <name>{}</name>", xml_escape(name))?;
+    for p in photos {
+        writeln!(w, "  <Placemark>")?;
+        writeln!(w, "    <name>{}</name>", xml_escape(&p.filename))?;
+        if let Some(t) = &p.taken_at {
+            writeln!(w, "    <TimeStamp><when>{}</when></TimeStamp>", xml_escape(t))?;
+        }
+        writeln!(
+            w,
+            r#"    <description><![CDATA[<a href="{}">{}</a>]]></description>"#,
+            file_url(&p.path).replace("]]>", "]]]]><![CDATA[>"),
+            p.filename.replace("]]>", "]]]]><![CDATA[>")
+        )?;
+        // KML coordinates are lon,lat
+        writeln!(w, "    <Point><coordinates>{},{}</coordinates></Point>", p.lon, p.lat)?;
+        writeln!(w, "  </Placemark>")?;
+    }
+    if photos.len() > 1 {
+        writeln!(w, "  <Placemark>\n    <name>{}</name>\n    <LineString>\n      <tessellate>1</tessellate>\n      <coordinates>", xml_escape(name))?;
+        for p in photos {
+            writeln!(w, "        {},{}", p.lon, p.lat)?;
+        }
+        writeln!(w, "      </coordinates>\n    </LineString>\n  </Placemark>")?;
+    }
+    writeln!(w, "</Document>\n</kml>")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// `file://` URL for a local path, percent-encoding everything but unreserved characters
+fn file_url(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut url = String::from(if path.starts_with('/') { "file://" } else { "file:///" });
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => url.push(b as char),
+            _ => url.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    url
+}
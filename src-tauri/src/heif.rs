@@ -0,0 +1,214 @@
+//! HEIF/HEIC container structure: which item is the primary image, its size, and how many
+//! full images the file holds. iPhone bursts and sequences carry several; portrait shots add
+//! depth and gain maps, and large images are split into tiles, none of which count. Only the
+//! box structure is read — the pixels themselves need an HEVC decoder.
+
+use std::io::Read;
+use std::path::Path;
+
+/// How much of the file to inspect; the `meta` box sits ahead of the image data
+const HEADER_SCAN: u64 = 1024 * 1024;
+
+/// Item types that are images in their own right (as opposed to Exif/XMP payloads)
+const IMAGE_ITEM_TYPES: &[&[u8; 4]] = &[b"hvc1", b"av01", b"grid", b"iden", b"iovl", b"jpeg"];
+
+/// Item references whose source is a part of another image, not a frame: grid tiles are the
+/// target of `dimg`, thumbnails and auxiliary images (depth, alpha, gain maps) the source of
+/// `thmb`/`auxl`
+const DERIVED_FROM: &[&[u8; 4]] = &[b"thmb", b"auxl"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeifInfo {
+    /// Primary image size with `irot` applied; None for bare image sequences
+    pub dimensions: Option<(u32, u32)>,
+    /// Full images in the file (1 for an ordinary HEIC)
+    pub frame_count: u32,
+}
+
+/// Whether a path has a HEIF-family extension worth inspecting
+pub fn is_heif_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["heic", "heif", "hif", "avif"].contains(&e.to_ascii_lowercase().as_str()))
+}
+
+pub fn read_heif_info(path: &Path) -> Option<HeifInfo> {
+    let mut head = Vec::new();
+    std::fs::File::open(path).ok()?.take(HEADER_SCAN).read_to_end(&mut head).ok()?;
+    if head.get(4..8)? != b"ftyp" {
+        return None;
+    }
+    let mut info = None;
+    let mut samples = 0;
+    for (kind, body) in boxes(&head) {
+        match &kind {
+            b"meta" => info = body.get(4..).and_then(parse_meta),
+            b"moov" => samples = sample_count(body),
+            _ => {}
+        }
+    }
+    let mut info = info.unwrap_or(HeifInfo { dimensions: None, frame_count: 0 });
+    info.frame_count = info.frame_count.max(samples).max(1);
+    Some(info)
+}
+
+/// Child boxes as (type, payload). Stops at the first box running past the data.
+fn boxes(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut out = Vec::new();
+    while data.len() >= 8 {
+        let size = be_u32(data, 0).unwrap_or(0) as usize;
+        let kind: [u8; 4] = data[4..8].try_into().unwrap_or_default();
+        let (header, size) = match size {
+            0 => (8, data.len()),
+            1 => match be_u64(data, 8) {
+                Some(large) => (16, large as usize),
+                None => break,
+            },
+            _ => (8, size),
+        };
+        if size < header || size > data.len() {
+            break;
+        }
+        out.push((kind, &data[header..size]));
+        data = &data[size..];
+    }
+    out
+}
+
+/// Item bookkeeping from the `meta` box payload (after its version/flags)
+fn parse_meta(meta: &[u8]) -> Option<HeifInfo> {
+    let mut primary = None;
+    let mut items: Vec<(u32, [u8; 4])> = Vec::new();
+    let mut parts: Vec<u32> = Vec::new();
+    let mut properties: Vec<([u8; 4], &[u8])> = Vec::new();
+    let mut associations: Vec<(u32, Vec<usize>)> = Vec::new();
+
+    for (kind, body) in boxes(meta) {
+        // Full boxes lead with a version byte; unused for the rest
+        let version = body.first().copied().unwrap_or(0);
+        match &kind {
+            b"pitm" => primary = if version == 0 { be_u16(body, 4).map(u32::from) } else { be_u32(body, 4) },
+            b"iinf" => {
+                let entries = body.get(if version == 0 { 6 } else { 8 }..)?;
+                for (kind, infe) in boxes(entries) {
+                    if &kind != b"infe" {
+                        continue;
+                    }
+                    // Versions 0/1 predate item types and don't appear in HEIF files
+                    match infe.first() {
+                        Some(2) => items.extend(be_u16(infe, 4).map(u32::from).zip(fourcc(infe, 8))),
+                        Some(3) => items.extend(be_u32(infe, 4).zip(fourcc(infe, 10))),
+                        _ => {}
+                    }
+                }
+            }
+            b"iref" => {
+                let id_len = if version == 0 { 2 } else { 4 };
+                for (kind, r) in boxes(body.get(4..)?) {
+                    let id = |at: usize| if id_len == 2 { be_u16(r, at).map(u32::from) } else { be_u32(r, at) };
+                    let count = be_u16(r, id_len).unwrap_or(0) as usize;
+                    if &kind == b"dimg" {
+                        parts.extend((0..count).filter_map(|i| id(id_len + 2 + i * id_len)));
+                    } else if DERIVED_FROM.contains(&&kind) {
+                        parts.extend(id(0));
+                    }
+                }
+            }
+            b"iprp" => {
+                for (kind, child) in boxes(body) {
+                    match &kind {
+                        b"ipco" => properties = boxes(child),
+                        b"ipma" => associations = parse_ipma(child).unwrap_or_default(),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let frame_count = items
+        .iter()
+        .filter(|(id, kind)| IMAGE_ITEM_TYPES.contains(&kind) && !parts.contains(id))
+        .count() as u32;
+    let dimensions = primary.and_then(|primary| {
+        let props = &associations.iter().find(|(id, _)| *id == primary)?.1;
+        let prop = |want: &[u8; 4]| {
+            props
+                .iter()
+                .filter_map(|&i| properties.get(i.checked_sub(1)?))
+                .find(|(kind, _)| kind == want)
+                .map(|(_, body)| *body)
+        };
+        let ispe = prop(b"ispe")?;
+        let (w, h) = (be_u32(ispe, 4)?, be_u32(ispe, 8)?);
+        // irot counts quarter turns anticlockwise
+        let quarter_turned = prop(b"irot").and_then(|b| b.first()).is_some_and(|a| a & 1 == 1);
+        Some(if quarter_turned { (h, w) } else { (w, h) })
+    });
+    Some(HeifInfo { dimensions, frame_count })
+}
+
+/// Item → 1-based `ipco` property indices
+fn parse_ipma(ipma: &[u8]) -> Option<Vec<(u32, Vec<usize>)>> {
+    let version = *ipma.first()?;
+    let wide_index = ipma.get(3)? & 1 == 1;
+    let count = be_u32(ipma, 4)?;
+    let mut at = 8;
+    let mut out = Vec::new();
+    for _ in 0..count {
+        let id = if version < 1 {
+            at += 2;
+            u32::from(be_u16(ipma, at - 2)?)
+        } else {
+            at += 4;
+            be_u32(ipma, at - 4)?
+        };
+        let n = *ipma.get(at)? as usize;
+        at += 1;
+        let mut indices = Vec::with_capacity(n);
+        for _ in 0..n {
+            // High bit flags the property as essential
+            if wide_index {
+                indices.push((be_u16(ipma, at)? & 0x7FFF) as usize);
+                at += 2;
+            } else {
+                indices.push((ipma.get(at)? & 0x7F) as usize);
+                at += 1;
+            }
+        }
+        out.push((id, indices));
+    }
+    Some(out)
+}
+
+/// Largest sample count among the tracks of an image sequence's `moov` box
+fn sample_count(moov: &[u8]) -> u32 {
+    let mut best = 0;
+    for (kind, body) in boxes(moov) {
+        let count = match &kind {
+            b"trak" | b"mdia" | b"minf" | b"stbl" => sample_count(body),
+            // version/flags, default sample size, then the count
+            b"stsz" => be_u32(body, 8).unwrap_or(0),
+            _ => 0,
+        };
+        best = best.max(count);
+    }
+    best
+}
+
+fn fourcc(b: &[u8], at: usize) -> Option<[u8; 4]> {
+    b.get(at..at + 4)?.try_into().ok()
+}
+
+fn be_u16(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be_u64(b: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(b.get(at..at + 8)?.try_into().ok()?))
+}
@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
@@ -1375,7 +1376,7 @@ fn apply_crop_and_rotate(
 pub async fn load_editor_source(
     state: tauri::State<'_, EditorState>,
     image_path: String,
-) -> Result<(u32, u32), String> {
+) -> Result<(u32, u32), AppError> {
     let img = image::open(&image_path).map_err(|e| format!("Failed to open image: {}", e))?;
     let rgba = img.to_rgba8();
     let width = rgba.width();
@@ -1396,7 +1397,7 @@ pub async fn load_editor_source(
 #[tauri::command]
 pub async fn unload_editor_source(
     state: tauri::State<'_, EditorState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let mut cache = state.cached_source.lock().unwrap();
     *cache = None;
     Ok(())
@@ -1409,7 +1410,7 @@ pub async fn process_image(
     adjustments: AdjustmentPayload,
     preview: bool,
     #[allow(unused)] max_preview_edge: Option<u32>,
-) -> Result<ProcessResult, String> {
+) -> Result<ProcessResult, AppError> {
     // Increment request counter for stale-check
     let request_id = state.preview_counter.fetch_add(1, Ordering::SeqCst) + 1;
 
@@ -1611,7 +1612,7 @@ pub async fn compute_histogram(
     image_data: Vec<u8>,
     _width: u32,
     _height: u32,
-) -> Result<HistogramResult, String> {
+) -> Result<HistogramResult, AppError> {
     tokio::task::spawn_blocking(move || {
         let mut r_hist = vec![0u32; 256];
         let mut g_hist = vec![0u32; 256];
@@ -1658,7 +1659,7 @@ pub struct AutoEnhanceResult {
 #[tauri::command]
 pub async fn auto_enhance(
     image_path: String,
-) -> Result<AutoEnhanceResult, String> {
+) -> Result<AutoEnhanceResult, AppError> {
     let img = image::open(&image_path).map_err(|e| format!("Failed to open image: {}", e))?;
 
     // Use a small version for analysis
@@ -1771,7 +1772,7 @@ pub async fn export_image(
     adjustments: AdjustmentPayload,
     options: ExportOptions,
     output_path: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let img = image::open(&image_path).map_err(|e| format!("Failed to open: {}", e))?;
 
     // Optionally resize
@@ -0,0 +1,172 @@
+//! Descriptive metadata written by other photo tools: IPTC-IIM records from the JPEG APP13
+//! (Photoshop) segment and the XMP packet Lightroom and friends embed. Only title, caption and
+//! keywords are read; XMP wins where both are present since editors keep it current.
+
+use std::io::Read;
+use std::path::Path;
+
+/// How much of the file to inspect; metadata segments sit ahead of the image data
+const HEADER_SCAN: u64 = 1024 * 1024;
+
+/// Photoshop image resource holding the IPTC-IIM block
+const IRB_IPTC: u16 = 0x0404;
+
+#[derive(Debug, Clone, Default)]
+pub struct IptcData {
+    pub title: Option<String>,
+    pub caption: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+impl IptcData {
+    fn merge(self, fallback: IptcData) -> IptcData {
+        IptcData {
+            title: self.title.or(fallback.title),
+            caption: self.caption.or(fallback.caption),
+            keywords: if self.keywords.is_empty() { fallback.keywords } else { self.keywords },
+        }
+    }
+}
+
+/// Title, caption and keywords of an image; empty when it carries none
+pub fn read_iptc(path: &Path) -> IptcData {
+    let mut head = Vec::new();
+    let read = std::fs::File::open(path)
+        .and_then(|f| f.take(HEADER_SCAN).read_to_end(&mut head));
+    if read.is_err() {
+        return IptcData::default();
+    }
+    let iim = if head.starts_with(&[0xFF, 0xD8]) {
+        jpeg_app13(&head).and_then(photoshop_iptc).map(parse_iim).unwrap_or_default()
+    } else {
+        IptcData::default()
+    };
+    find_xmp(&head).map(parse_xmp).unwrap_or_default().merge(iim)
+}
+
+/// Payload of the first APP13 "Photoshop 3.0" segment
+fn jpeg_app13(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // Start of scan: no metadata segments follow
+        if marker == 0xDA {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let body = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xED && body.starts_with(b"Photoshop 3.0\0") {
+            return Some(&body[14..]);
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+/// The IPTC resource inside a Photoshop image resource block list
+fn photoshop_iptc(mut irb: &[u8]) -> Option<&[u8]> {
+    while irb.len() >= 12 && irb.starts_with(b"8BIM") {
+        let id = u16::from_be_bytes([irb[4], irb[5]]);
+        // Pascal name, padded to an even length including the length byte
+        let name_len = irb[6] as usize;
+        let name_total = (name_len + 2) & !1;
+        let size_at = 6 + name_total;
+        let size = u32::from_be_bytes(irb.get(size_at..size_at + 4)?.try_into().ok()?) as usize;
+        let data_at = size_at + 4;
+        let data = irb.get(data_at..data_at + size)?;
+        if id == IRB_IPTC {
+            return Some(data);
+        }
+        irb = irb.get(data_at + size + (size & 1)..)?;
+    }
+    None
+}
+
+/// Application records (2:xx) of an IPTC-IIM stream
+fn parse_iim(mut data: &[u8]) -> IptcData {
+    let mut out = IptcData::default();
+    while data.len() >= 5 && data[0] == 0x1C {
+        let (record, dataset) = (data[1], data[2]);
+        let len = u16::from_be_bytes([data[3], data[4]]) as usize;
+        // Extended lengths (high bit set) only occur for binary payloads we don't read
+        if len & 0x8000 != 0 {
+            break;
+        }
+        let Some(value) = data.get(5..5 + len) else { break };
+        if record == 2 {
+            let text = clean(&decode_text(value));
+            match dataset {
+                5 => out.title = out.title.or(text),
+                25 => out.keywords.extend(text),
+                120 => out.caption = out.caption.or(text),
+                _ => {}
+            }
+        }
+        data = &data[5 + len..];
+    }
+    out
+}
+
+/// IIM text is usually UTF-8 nowadays; older files use Latin-1
+fn decode_text(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(s) => s.to_string(),
+        Err(_) => value.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn find_xmp(data: &[u8]) -> Option<&str> {
+    let start = find_bytes(data, b"<x:xmpmeta")?;
+    let end = find_bytes(&data[start..], b"</x:xmpmeta>")? + start;
+    std::str::from_utf8(&data[start..end]).ok()
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn parse_xmp(xmp: &str) -> IptcData {
+    IptcData {
+        title: xmp_items(xmp, "dc:title").into_iter().next(),
+        caption: xmp_items(xmp, "dc:description").into_iter().next(),
+        keywords: xmp_items(xmp, "dc:subject"),
+    }
+}
+
+/// The `rdf:li` values of an XMP property (Alt for title/description, Bag for subject)
+fn xmp_items(xmp: &str, property: &str) -> Vec<String> {
+    let open = format!("<{}>", property);
+    let close = format!("</{}>", property);
+    let Some(start) = xmp.find(&open) else { return Vec::new() };
+    let body = &xmp[start + open.len()..];
+    let body = &body[..body.find(&close).unwrap_or(body.len())];
+    let mut items = Vec::new();
+    let mut rest = body;
+    while let Some(li) = rest.find("<rdf:li") {
+        let after = &rest[li..];
+        let Some(gt) = after.find('>') else { break };
+        let value_start = &after[gt + 1..];
+        let Some(end) = value_start.find("</rdf:li>") else { break };
+        items.extend(clean(&xml_unescape(&value_start[..end])));
+        rest = &value_start[end..];
+    }
+    items
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#xA;", "\n")
+        .replace("&amp;", "&")
+}
+
+/// Trimmed text, None when blank
+fn clean(s: &str) -> Option<String> {
+    let s = s.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    (!s.is_empty()).then(|| s.to_string())
+}
@@ -1,8 +1,21 @@
 pub mod commands;
 pub mod image_processing;
+mod animation;
+mod burst;
+mod catalog;
 mod db;
+pub mod error;
+mod exif_write;
+#[cfg(feature = "faces")]
+mod faces;
+mod geo_export;
+mod heif;
+mod iptc;
 mod scan;
+mod search;
+mod takeout;
 mod thumb;
+mod undo;
 mod watcher;
 
 use tauri::Manager;
@@ -24,32 +37,72 @@ pub fn run() {
             commands::select_and_index,
             commands::get_categories,
             commands::get_months,
+            commands::get_category_deltas,
+            commands::get_days,
+            commands::get_location_clusters,
+            commands::get_folder_cover,
+            commands::get_folder_covers,
+            commands::get_timeline_covers,
+            commands::get_photos_in_bounds,
+            commands::get_undated_photos,
             commands::get_photos,
+            commands::get_adjacent_photos,
+            commands::get_slideshow,
             commands::search_photos,
+            commands::search_all_photos,
             commands::get_thumbnail_path,
+            commands::thumbnail_exists,
+            commands::get_animated_thumbnail,
+            commands::get_thumbnail_data_url,
+            commands::get_preview,
+            commands::get_comparison,
+            commands::get_thumbnail_cache_size,
+            commands::clear_thumbnail_cache,
+            commands::prune_thumbnail_cache,
+            commands::migrate_thumbnail_cache,
             commands::get_index_progress,
             commands::get_current_library_path,
             commands::scan_default_directories,
             commands::get_all_photos,
             commands::get_photo_count,
+            commands::get_library_stats,
+            commands::get_library_breakdown,
             commands::get_libraries,
             commands::restore_session,
             commands::add_library_path,
+            commands::reindex_folder,
+            commands::get_scan_timeouts,
+            commands::retry_scan_timeouts,
+            commands::reconcile_library,
+            commands::purge_missing_photos,
             commands::remove_library_path,
+            commands::relocate_library,
             commands::get_library_paths,
             commands::toggle_favorite,
+            commands::set_photo_category,
+            commands::shift_dates,
+            commands::set_field,
+            commands::mark_viewed,
+            commands::get_recently_viewed,
             commands::soft_delete_photos,
+            commands::soft_delete_by_filter,
             commands::restore_photos,
             commands::get_photo_detail,
+            commands::get_photo_detail_full,
+            commands::get_photos_by_ids,
             // File operations
             commands::hard_delete_photos,
             commands::rename_photo,
+            commands::bulk_rename,
+            commands::undo_last_operation,
             // Tags
             commands::create_tag,
             commands::delete_tag,
+            commands::merge_tags,
             commands::get_tags,
             commands::tag_photos,
             commands::untag_photos,
+            commands::untag_by_filter,
             commands::get_photo_tags,
             // Albums
             commands::create_album,
@@ -57,8 +110,32 @@ pub fn run() {
             commands::rename_album,
             commands::get_albums,
             commands::add_to_album,
+            commands::add_photos_to_albums,
             commands::remove_from_album,
+            commands::reorder_album,
+            commands::export_album,
             commands::get_album_photos,
+            commands::get_album_stats,
+            commands::infer_dates,
+            commands::find_filename_collisions,
+            commands::find_small_images,
+            commands::get_burst_groups,
+            #[cfg(feature = "faces")]
+            commands::group_faces,
+            #[cfg(feature = "faces")]
+            commands::cancel_group_faces,
+            #[cfg(feature = "faces")]
+            commands::get_face_clusters,
+            #[cfg(feature = "faces")]
+            commands::get_photos_for_face_cluster,
+            commands::reparse_exif,
+            commands::cancel_reparse_exif,
+            commands::generate_blurhashes,
+            // Catalog
+            commands::export_catalog,
+            commands::export_gpx,
+            commands::export_kml,
+            commands::import_catalog,
             // Photo editor
             commands::save_edited_photo,
             commands::save_edit_params,
@@ -73,6 +150,28 @@ pub fn run() {
             // Performance & streaming
             commands::scan_directory,
             commands::get_system_info,
+            // Scan settings
+            commands::set_media_extensions,
+            commands::get_media_extensions,
+            commands::set_ignore_patterns,
+            commands::get_ignore_patterns,
+            commands::set_default_scan_dirs,
+            commands::get_default_scan_dirs,
+            commands::set_raw_preference,
+            commands::set_mtime_date_fallback,
+            commands::get_mtime_date_fallback,
+            commands::get_raw_preference,
+            commands::get_setting,
+            commands::set_setting,
+            commands::set_performance_options,
+            commands::get_performance_options,
+            commands::set_thumbnail_quality,
+            commands::get_thumbnail_quality,
+            commands::set_animated_thumbnails,
+            commands::get_animated_thumbnails,
+            // Catalog location
+            commands::get_database_location,
+            commands::set_database_location,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,15 +1,195 @@
 use rayon::prelude::*;
 use rexif::parse_file;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use std::time::SystemTime;
 use walkdir::WalkDir;
 
 const PHOTO_EXT: &[&str] = &[
     "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "heic", "heif", "raw", "arw", "cr2",
-    "nef", "dng",
+    "cr3", "nef", "dng", "raf", "orf", "rw2",
 ];
 const VIDEO_EXT: &[&str] = &["mp4", "mov", "avi", "mkv", "webm", "m4v", "wmv", "3gp"];
 
+/// Runtime extension lists — start as the built-in defaults, overridden from persisted settings
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaExtensions {
+    pub photo: Vec<String>,
+    pub video: Vec<String>,
+}
+
+impl Default for MediaExtensions {
+    fn default() -> Self {
+        MediaExtensions {
+            photo: PHOTO_EXT.iter().map(|e| e.to_string()).collect(),
+            video: VIDEO_EXT.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+static MEDIA_EXTENSIONS: OnceLock<RwLock<MediaExtensions>> = OnceLock::new();
+
+fn media_extensions() -> &'static RwLock<MediaExtensions> {
+    MEDIA_EXTENSIONS.get_or_init(|| RwLock::new(MediaExtensions::default()))
+}
+
+/// Lowercase and strip leading dots so ".JXL" and "jxl" are the same extension
+pub fn normalize_extensions(exts: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = exts
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Replace the runtime extension lists. `None` (or an empty list) restores the built-in defaults.
+pub fn set_media_extensions(photo: Option<Vec<String>>, video: Option<Vec<String>>) {
+    let defaults = MediaExtensions::default();
+    let photo = photo.map(|p| normalize_extensions(&p)).filter(|p| !p.is_empty());
+    let video = video.map(|v| normalize_extensions(&v)).filter(|v| !v.is_empty());
+    let mut exts = media_extensions().write().unwrap();
+    exts.photo = photo.unwrap_or(defaults.photo);
+    exts.video = video.unwrap_or(defaults.video);
+}
+
+pub fn current_media_extensions() -> MediaExtensions {
+    media_extensions().read().unwrap().clone()
+}
+
+/// Default ignore patterns — hidden entries, OS/NAS metadata folders and dev junk
+const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
+    ".*",
+    "@eaDir",
+    "#recycle",
+    "$RECYCLE.BIN",
+    "System Volume Information",
+    "Thumbs.db",
+    "desktop.ini",
+    "node_modules",
+];
+
+static IGNORE_PATTERNS: OnceLock<RwLock<Vec<String>>> = OnceLock::new();
+
+fn ignore_patterns() -> &'static RwLock<Vec<String>> {
+    IGNORE_PATTERNS.get_or_init(|| RwLock::new(default_ignore_patterns()))
+}
+
+pub fn default_ignore_patterns() -> Vec<String> {
+    DEFAULT_IGNORE_PATTERNS.iter().map(|p| p.to_string()).collect()
+}
+
+/// Trim and drop blank patterns; an empty result falls back to the defaults
+pub fn normalize_ignore_patterns(patterns: &[String]) -> Vec<String> {
+    let patterns: Vec<String> = patterns
+        .iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if patterns.is_empty() {
+        default_ignore_patterns()
+    } else {
+        patterns
+    }
+}
+
+/// Replace the runtime ignore list. `None` (or an empty list) restores the defaults.
+pub fn set_ignore_patterns(patterns: Option<Vec<String>>) {
+    *ignore_patterns().write().unwrap() = normalize_ignore_patterns(&patterns.unwrap_or_default());
+}
+
+pub fn current_ignore_patterns() -> Vec<String> {
+    ignore_patterns().read().unwrap().clone()
+}
+
+/// Case-insensitive glob match supporting `*` and `?`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Patterns without a `/` match the entry name; patterns with one match the path relative to the root
+fn is_ignored(rel_path: &Path, patterns: &[String]) -> bool {
+    let name = match rel_path.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n,
+        None => return false,
+    };
+    let rel = rel_path.to_string_lossy().replace('\\', "/");
+    patterns.iter().any(|pat| {
+        if pat.contains('/') {
+            glob_match(pat.trim_matches('/'), &rel)
+        } else {
+            glob_match(pat, name)
+        }
+    })
+}
+
+/// Dedicated rayon pool for metadata extraction — by default one thread per logical CPU;
+/// `set_scan_threads` lowers it to leave room for thumbnails. Resizing swaps in a new pool;
+/// work already running finishes on the old one.
+static SCAN_POOL: OnceLock<RwLock<Arc<rayon::ThreadPool>>> = OnceLock::new();
+
+pub fn default_scan_threads() -> usize {
+    num_cpus::get().max(1)
+}
+
+fn build_scan_pool(threads: usize) -> Arc<rayon::ThreadPool> {
+    eprintln!("  🔧 Scan pool: {} threads", threads);
+    Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("scan-{}", i))
+            .build()
+            .expect("failed to build scan thread pool"),
+    )
+}
+
+pub(crate) fn scan_pool() -> Arc<rayon::ThreadPool> {
+    SCAN_POOL
+        .get_or_init(|| RwLock::new(build_scan_pool(default_scan_threads())))
+        .read()
+        .unwrap()
+        .clone()
+}
+
+/// Resize the scan pool. `None` restores the default.
+pub fn set_scan_threads(threads: Option<usize>) {
+    let threads = threads.filter(|t| *t > 0).unwrap_or_else(default_scan_threads);
+    if SCAN_POOL.get().is_some() && scan_threads() == threads {
+        return;
+    }
+    let pool = build_scan_pool(threads);
+    let slot = SCAN_POOL.get_or_init(|| RwLock::new(pool.clone()));
+    *slot.write().unwrap() = pool;
+}
+
+pub fn scan_threads() -> usize {
+    scan_pool().current_num_threads()
+}
+
 fn get_extension(path: &Path) -> Option<String> {
     path.extension()
         .and_then(|e| e.to_str())
@@ -21,15 +201,21 @@ pub fn media_type_from_path(path: &Path) -> &'static str {
         Some(e) => e,
         None => return "other",
     };
-    if PHOTO_EXT.contains(&ext.as_str()) {
+    let exts = media_extensions().read().unwrap();
+    if exts.photo.contains(&ext) {
         "photo"
-    } else if VIDEO_EXT.contains(&ext.as_str()) {
+    } else if exts.video.contains(&ext) {
         "video"
     } else {
         "other"
     }
 }
 
+/// True for any path whose extension is in the runtime photo or video list
+pub fn is_media_path(path: &Path) -> bool {
+    media_type_from_path(path) != "other"
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ExifData {
     pub taken_at: Option<String>,
@@ -39,12 +225,112 @@ pub struct ExifData {
     pub iso: Option<i32>,
     pub shutter_speed: Option<String>,
     pub aperture: Option<String>,
+    /// Numeric forms of `shutter_speed` / `aperture`, for range filters
+    pub shutter_seconds: Option<f64>,
+    pub f_number: Option<f64>,
     pub focal_length: Option<String>,
     pub gps_lat: Option<f64>,
     pub gps_lon: Option<f64>,
+    pub orientation: Option<i32>,
+    pub exposure_bias: Option<f64>,
+    /// From EXIF OffsetTimeOriginal/OffsetTime; `taken_at` is already converted to UTC
+    pub utc_offset_minutes: Option<i32>,
+}
+
+/// EXIF "2021:05:03 23:30:00" (local wall clock); date-only values fall back to midnight
+fn parse_exif_datetime(s: &str) -> Option<chrono::NaiveDateTime> {
+    let s = s.trim().trim_matches('"');
+    chrono::NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S")
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(s.get(..10)?, "%Y:%m:%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+}
+
+/// EXIF offset "+09:00" / "-05:30" → minutes east of UTC
+fn parse_utc_offset(s: &str) -> Option<i32> {
+    let s = s.trim().trim_matches('"');
+    let (sign, rest) = match s.chars().next()? {
+        '+' => (1, &s[1..]),
+        '-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let (h, m) = rest.split_once(':')?;
+    let (h, m): (i32, i32) = (h.parse().ok()?, m.parse().ok()?);
+    (h <= 14 && m < 60).then_some(sign * (h * 60 + m))
+}
+
+// ── Exposure normalization ──
+// Raw EXIF values arrive as rationals ("250/1"), decimals ("0.004") or pre-formatted
+// strings ("1/250 s"), so everything is reduced to a number first, then formatted.
+
+/// Parse "a/b" or a decimal, ignoring an "f/" prefix and unit suffixes (s, mm, EV)
+pub fn parse_exif_number(raw: &str) -> Option<f64> {
+    let cleaned = raw
+        .trim()
+        .trim_start_matches("f/")
+        .trim_end_matches("EV")
+        .trim_end_matches("mm")
+        .trim_end_matches("sec")
+        .trim_end_matches('s')
+        .trim();
+    let value = match cleaned.split_once('/') {
+        Some((num, den)) => {
+            let num: f64 = num.trim().parse().ok()?;
+            let den: f64 = den.trim().parse().ok()?;
+            if den == 0.0 {
+                return None;
+            }
+            num / den
+        }
+        None => cleaned.parse().ok()?,
+    };
+    value.is_finite().then_some(value)
+}
+
+/// First numeric value of an entry, preferring the raw rational over the readable string
+fn exif_entry_number(entry: &rexif::ExifEntry) -> Option<f64> {
+    match entry.value {
+        rexif::TagValue::URational(ref v) if !v.is_empty() && v[0].denominator != 0 => {
+            Some(v[0].numerator as f64 / v[0].denominator as f64)
+        }
+        rexif::TagValue::IRational(ref v) if !v.is_empty() && v[0].denominator != 0 => {
+            Some(v[0].numerator as f64 / v[0].denominator as f64)
+        }
+        _ => parse_exif_number(&entry.value_more_readable),
+    }
+}
+
+/// Drop a trailing ".0" so 2.0 prints as "2"
+fn trim_float(v: f64) -> String {
+    let s = format!("{:.1}", v);
+    s.trim_end_matches(".0").to_string()
+}
+
+/// Exposure time in seconds → "1/250 s" (or "2 s" / "1.5 s" for long exposures)
+pub fn format_shutter_speed(seconds: f64) -> Option<String> {
+    if seconds <= 0.0 {
+        return None;
+    }
+    if seconds >= 1.0 {
+        return Some(format!("{} s", trim_float(seconds)));
+    }
+    Some(format!("1/{} s", (1.0 / seconds).round() as u64))
+}
+
+/// F-number → "f/2.8"
+pub fn format_aperture(f_number: f64) -> Option<String> {
+    (f_number > 0.0).then(|| format!("f/{:.1}", f_number))
+}
+
+/// Focal length in mm → "50mm"
+pub fn format_focal_length(mm: f64) -> Option<String> {
+    (mm > 0.0).then(|| format!("{}mm", mm.round() as u64))
 }
 
-fn parse_exif_data(path: &Path) -> ExifData {
+pub(crate) fn parse_exif_data(path: &Path) -> ExifData {
     let mut data = ExifData::default();
     let exif = match parse_file(path) {
         Ok(e) => e,
@@ -55,15 +341,14 @@ fn parse_exif_data(path: &Path) -> ExifData {
     let mut gps_lat_ref: Option<String> = None;
     let mut gps_lon_vals: Option<Vec<f64>> = None;
     let mut gps_lon_ref: Option<String> = None;
+    let mut local_taken: Option<chrono::NaiveDateTime> = None;
+    let mut offset_minutes: Option<i32> = None;
 
     for entry in &exif.entries {
         match entry.tag {
             rexif::ExifTag::DateTimeOriginal | rexif::ExifTag::DateTime => {
-                if data.taken_at.is_none() {
-                    let s = entry.value_more_readable.to_string().replace(':', "-");
-                    if s.len() >= 10 {
-                        data.taken_at = Some(format!("{}T00:00:00Z", &s[..10]));
-                    }
+                if local_taken.is_none() {
+                    local_taken = parse_exif_datetime(&entry.value_more_readable);
                 }
             }
             rexif::ExifTag::Make => {
@@ -85,18 +370,19 @@ fn parse_exif_data(path: &Path) -> ExifData {
                 }
             }
             rexif::ExifTag::ExposureTime => {
-                let v = entry.value_more_readable.to_string().trim().to_string();
-                if !v.is_empty() { data.shutter_speed = Some(v); }
+                data.shutter_seconds = exif_entry_number(entry).filter(|s| *s > 0.0);
+                data.shutter_speed = data.shutter_seconds.and_then(format_shutter_speed);
             }
             rexif::ExifTag::FNumber => {
-                let v = entry.value_more_readable.to_string().trim().to_string();
-                if !v.is_empty() {
-                    data.aperture = Some(format!("f/{}", v.trim_start_matches("f/")));
-                }
+                data.f_number = exif_entry_number(entry).filter(|f| *f > 0.0);
+                data.aperture = data.f_number.and_then(format_aperture);
             }
             rexif::ExifTag::FocalLength => {
-                let v = entry.value_more_readable.to_string().trim().to_string();
-                if !v.is_empty() { data.focal_length = Some(v); }
+                data.focal_length = exif_entry_number(entry).and_then(format_focal_length);
+            }
+            rexif::ExifTag::ExposureBiasValue => {
+                // Stored in EV, rounded to the 1/100 step cameras actually use
+                data.exposure_bias = exif_entry_number(entry).map(|ev| (ev * 100.0).round() / 100.0);
             }
             rexif::ExifTag::GPSLatitude => {
                 if let rexif::TagValue::URational(ref vals) = entry.value {
@@ -116,10 +402,35 @@ fn parse_exif_data(path: &Path) -> ExifData {
             rexif::ExifTag::GPSLongitudeRef => {
                 gps_lon_ref = Some(entry.value_more_readable.to_string().trim().to_string());
             }
+            rexif::ExifTag::Orientation => {
+                if let rexif::TagValue::U16(ref vals) = entry.value {
+                    data.orientation = vals.first().map(|v| *v as i32).filter(|v| (1..=8).contains(v));
+                }
+            }
+            // OffsetTimeOriginal (0x9011) wins over OffsetTime (0x9010); rexif has no named tags for them
+            _ if entry.ifd.tag == 0x9011 => {
+                offset_minutes = parse_utc_offset(&entry.value_more_readable).or(offset_minutes);
+            }
+            _ if entry.ifd.tag == 0x9010 => {
+                if offset_minutes.is_none() {
+                    offset_minutes = parse_utc_offset(&entry.value_more_readable);
+                }
+            }
             _ => {}
         }
     }
 
+    // EXIF capture time is local wall clock; store UTC when the offset is known,
+    // otherwise keep the wall clock as-is (grouping then treats it as local)
+    if let Some(local) = local_taken {
+        let utc = match offset_minutes {
+            Some(off) => local - chrono::Duration::minutes(off as i64),
+            None => local,
+        };
+        data.taken_at = Some(utc.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+        data.utc_offset_minutes = offset_minutes;
+    }
+
     // Convert GPS DMS to decimal
     if let Some(ref vals) = gps_lat_vals {
         let mut lat = vals[0] + vals[1] / 60.0 + vals[2] / 3600.0;
@@ -135,7 +446,41 @@ fn parse_exif_data(path: &Path) -> ExifData {
     data
 }
 
-fn modified_time_string(path: &Path) -> String {
+/// Windows attributes set on cloud files whose data isn't local (OneDrive, iCloud for Windows)
+#[cfg(windows)]
+const OFFLINE_ATTRIBUTES: u32 = 0x1000 | 0x40000 | 0x400000; // OFFLINE | RECALL_ON_OPEN | RECALL_ON_DATA_ACCESS
+
+/// macOS `st_flags` bit for "dataless" files evicted by iCloud Drive / File Provider
+#[cfg(target_os = "macos")]
+const SF_DATALESS: u32 = 0x4000_0000;
+
+/// A cloud-sync placeholder for a file that hasn't been downloaded: empty, or flagged
+/// offline by the OS. Reading one would either fail or trigger a download.
+pub fn is_placeholder(path: &Path) -> bool {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return false;
+    };
+    if meta.len() == 0 {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        if meta.file_attributes() & OFFLINE_ATTRIBUTES != 0 {
+            return true;
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::MetadataExt;
+        if meta.st_flags() & SF_DATALESS != 0 {
+            return true;
+        }
+    }
+    false
+}
+
+pub fn modified_time_string(path: &Path) -> String {
     let meta = match std::fs::metadata(path) {
         Ok(m) => m,
         Err(_) => return "1970-01-01T00:00:00Z".to_string(),
@@ -153,23 +498,93 @@ fn modified_time_string(path: &Path) -> String {
         .unwrap_or_else(|| "1970-01-01T00:00:00Z".to_string())
 }
 
+/// Per-scan traversal options
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Descend into symlinked directories. Each real directory is visited at most once,
+    /// so symlink loops and duplicate links can't hang the scan or double-index files.
+    pub follow_links: bool,
+    /// Decode each photo to compute a BlurHash placeholder (adds a full decode per file)
+    pub blurhash: bool,
+    /// Look for Google Takeout `{filename}.json` sidecars (one extra file probe per item)
+    pub takeout_sidecars: bool,
+    /// Group by the immediate parent folder's name instead of the full relative path
+    /// (a per-library setting, see `folder_rel_for`)
+    pub flatten_folders: bool,
+}
+
+/// `folder_rel` for a file: its parent's path relative to `root`, or with `flatten` just the
+/// parent folder's name. Files directly in the root get "" either way.
+pub fn folder_rel_for(path: &Path, root: &Path, flatten: bool) -> String {
+    let Some(rel) = path.parent().and_then(|p| p.strip_prefix(root).ok()) else {
+        return String::new();
+    };
+    if flatten {
+        rel.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    } else {
+        rel.to_string_lossy().to_string()
+    }
+}
+
 /// Phase 1: collect media file paths only (fast).
-pub fn collect_media_paths(root: &Path) -> Vec<PathBuf> {
+/// Entries matching the ignore list are pruned in `filter_entry`, so ignored
+/// directories are never descended into.
+pub fn collect_media_paths(root: &Path, opts: &ScanOptions) -> Vec<PathBuf> {
+    collect_media_files(root, opts)
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Same traversal as `collect_media_paths`, also returning each file's size in bytes
+/// (read from the walk entry's metadata, so no extra stat per file).
+pub fn collect_media_files(root: &Path, opts: &ScanOptions) -> Vec<(PathBuf, u64)> {
+    collect_media_files_reporting(root, opts).0
+}
+
+/// Like `collect_media_files`, also returning files skipped because their path isn't valid
+/// UTF-8 (in lossy form, for display only). Paths are stored as text, so such a name would
+/// be mangled and could never be reopened for thumbnails, renames or deletion.
+pub fn collect_media_files_reporting(root: &Path, opts: &ScanOptions) -> (Vec<(PathBuf, u64)>, Vec<String>) {
     let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
-    WalkDir::new(&root)
-        .follow_links(false)
+    let patterns = current_ignore_patterns();
+    let mut visited_dirs: HashSet<PathBuf> = HashSet::new();
+    let mut skipped: Vec<String> = Vec::new();
+    let files = WalkDir::new(&root)
+        .follow_links(opts.follow_links)
         .into_iter()
+        .filter_entry(|e| {
+            if e.depth() > 0
+                && is_ignored(e.path().strip_prefix(&root).unwrap_or(e.path()), &patterns)
+            {
+                return false;
+            }
+            if opts.follow_links && e.file_type().is_dir() {
+                let canonical = e.path().canonicalize().unwrap_or_else(|_| e.path().to_path_buf());
+                if !visited_dirs.insert(canonical) {
+                    eprintln!("  ↩ Skipping already-visited directory: {}", e.path().display());
+                    return false;
+                }
+            }
+            true
+        })
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
-        .map(|e| e.into_path())
-        .filter(|p| {
-            let ext = get_extension(p);
-            ext.map(|e| {
-                PHOTO_EXT.contains(&e.as_str()) || VIDEO_EXT.contains(&e.as_str())
-            })
-            .unwrap_or(false)
+        .filter(|e| is_media_path(e.path()))
+        .filter(|e| {
+            if e.path().to_str().is_some() {
+                return true;
+            }
+            eprintln!("  ⚠ Skipping non-UTF-8 path: {}", e.path().display());
+            skipped.push(e.path().to_string_lossy().to_string());
+            false
         })
-        .collect()
+        .map(|e| {
+            let size = e.metadata().map(|m| m.len()).unwrap_or(0);
+            (e.into_path(), size)
+        })
+        .collect();
+    (files, skipped)
 }
 
 #[derive(Debug, Clone)]
@@ -190,49 +605,450 @@ pub struct ScannedFile {
     pub iso: Option<i32>,
     pub shutter_speed: Option<String>,
     pub aperture: Option<String>,
+    pub shutter_seconds: Option<f64>,
+    pub f_number: Option<f64>,
     pub focal_length: Option<String>,
     pub gps_lat: Option<f64>,
     pub gps_lon: Option<f64>,
+    pub orientation: Option<i32>,
+    pub exposure_bias: Option<f64>,
+    pub utc_offset_minutes: Option<i32>,
+    pub blurhash: Option<String>,
+    pub category: Option<String>,
+    /// See `ColorInfo`; None for videos and light scans
+    pub color_space: Option<String>,
+    pub is_hdr: bool,
+    /// IPTC/XMP title, caption and keywords (keywords become tags on insert)
+    pub title: Option<String>,
+    pub caption: Option<String>,
+    pub keywords: Vec<String>,
+    /// Full images in a HEIF container (bursts, sequences) or frames of a GIF/WebP;
+    /// None for other formats
+    pub frame_count: Option<i32>,
+    /// Animated GIF or WebP (more than one frame)
+    pub is_animated: bool,
+    /// `DATE_SOURCE_*` for `taken_at`; None when undated
+    pub date_source: Option<String>,
+    /// Cloud-sync stub not downloaded yet (see `is_placeholder`); metadata wasn't read
+    pub is_placeholder: bool,
+    /// Live Photo video paired with this still (set by `pair_live_photos`)
+    pub motion_path: Option<String>,
+    /// RAW+JPEG pair linkage (set by `pair_raw_jpeg`)
+    pub raw_path: Option<String>,
+    pub jpeg_path: Option<String>,
+}
+
+// ── Live Photos ──
+
+/// Still formats iPhones pair with a motion clip
+const LIVE_STILL_EXT: &[&str] = &["heic", "heif", "jpg", "jpeg"];
+const LIVE_MOTION_EXT: &[&str] = &["mov", "mp4"];
+
+/// Link Live Photo pairs (IMG_1234.HEIC + IMG_1234.MOV in the same folder): the still gets
+/// `motion_path` and the clip is dropped so it isn't listed as its own item.
+/// Input order of the remaining files is preserved.
+pub fn pair_live_photos(files: &mut Vec<ScannedFile>) {
+    let key = |f: &ScannedFile| -> Option<(String, String, bool)> {
+        let p = Path::new(&f.path);
+        let ext = p.extension()?.to_str()?.to_lowercase();
+        let stem = p.file_stem()?.to_str()?.to_lowercase();
+        let dir = p.parent()?.to_string_lossy().to_string();
+        if LIVE_STILL_EXT.contains(&ext.as_str()) {
+            Some((dir, stem, true))
+        } else if LIVE_MOTION_EXT.contains(&ext.as_str()) {
+            Some((dir, stem, false))
+        } else {
+            None
+        }
+    };
+
+    let mut motion: HashMap<(String, String), usize> = HashMap::new();
+    for (i, f) in files.iter().enumerate() {
+        if let Some((dir, stem, false)) = key(f) {
+            motion.insert((dir, stem), i);
+        }
+    }
+    if motion.is_empty() {
+        return;
+    }
+
+    let mut paired: HashSet<usize> = HashSet::new();
+    for i in 0..files.len() {
+        if let Some((dir, stem, true)) = key(&files[i]) {
+            if let Some(&m) = motion.get(&(dir, stem)) {
+                if paired.insert(m) {
+                    files[i].motion_path = Some(files[m].path.clone());
+                }
+            }
+        }
+    }
+
+    let mut idx = 0;
+    files.retain(|_| {
+        let keep = !paired.contains(&idx);
+        idx += 1;
+        keep
+    });
 }
 
-fn build_scanned_file(path: &Path, root: &Path) -> Option<ScannedFile> {
+// ── RAW+JPEG pairs ──
+
+const RAW_EXT: &[&str] = &["raw", "arw", "cr2", "cr3", "nef", "dng", "raf", "orf", "rw2"];
+const JPEG_EXT: &[&str] = &["jpg", "jpeg"];
+
+/// Link RAW+JPEG shots (same folder and base name). Both rows stay indexed and both carry
+/// `raw_path`/`jpeg_path`, so listings can collapse the pair to either member.
+pub fn pair_raw_jpeg(files: &mut [ScannedFile]) {
+    let key = |f: &ScannedFile| -> Option<(String, String, String)> {
+        let p = Path::new(&f.path);
+        let ext = p.extension()?.to_str()?.to_lowercase();
+        let stem = p.file_stem()?.to_str()?.to_lowercase();
+        Some((p.parent()?.to_string_lossy().to_string(), stem, ext))
+    };
+
+    let mut raws: HashMap<(String, String), usize> = HashMap::new();
+    for (i, f) in files.iter().enumerate() {
+        if let Some((dir, stem, ext)) = key(f) {
+            if RAW_EXT.contains(&ext.as_str()) {
+                raws.insert((dir, stem), i);
+            }
+        }
+    }
+    if raws.is_empty() {
+        return;
+    }
+
+    for j in 0..files.len() {
+        let Some((dir, stem, ext)) = key(&files[j]) else { continue };
+        if !JPEG_EXT.contains(&ext.as_str()) {
+            continue;
+        }
+        if let Some(&r) = raws.get(&(dir, stem)) {
+            if files[r].jpeg_path.is_some() {
+                continue; // already paired (e.g. both .jpg and .jpeg present)
+            }
+            let (raw, jpeg) = (files[r].path.clone(), files[j].path.clone());
+            for k in [r, j] {
+                files[k].raw_path = Some(raw.clone());
+                files[k].jpeg_path = Some(jpeg.clone());
+            }
+        }
+    }
+}
+
+// ── Category heuristic ──
+
+pub const PHOTO_CATEGORIES: &[&str] = &["photo", "screenshot", "document"];
+
+/// Native resolutions of common phones, tablets and monitors (portrait or landscape)
+const SCREEN_SIZES: &[(u32, u32)] = &[
+    (1920, 1080), (2560, 1440), (3840, 2160), (1366, 768), (1440, 900), (1536, 864),
+    (1680, 1050), (2560, 1600), (2880, 1800), (3024, 1964), (3456, 2234),
+    (750, 1334), (1080, 1920), (1125, 2436), (1170, 2532), (1179, 2556), (1242, 2688),
+    (1284, 2778), (1290, 2796), (828, 1792), (1080, 2340), (1080, 2400), (1440, 3200),
+    (1536, 2048), (1620, 2160), (1668, 2388), (2048, 2732),
+];
+
+/// Classify a still image as "screenshot", "document" or "photo". Anything with camera
+/// EXIF is a photo; otherwise the filename, then exact screen dimensions or a paper aspect
+/// ratio (A4/Letter) decide. Deliberately simple — users can override per photo.
+fn classify_photo(filename: &str, dims: Option<(i32, i32)>, exif: &ExifData) -> String {
+    if exif.camera_make.is_some() || exif.camera_model.is_some() {
+        return "photo".to_string();
+    }
+    let name = filename.to_lowercase();
+    if name.contains("screenshot") || name.contains("screen shot") || name.starts_with("scr_") {
+        return "screenshot".to_string();
+    }
+    if ["scan", "receipt", "invoice", "document"].iter().any(|k| name.contains(k)) {
+        return "document".to_string();
+    }
+    if let Some((w, h)) = dims {
+        let (w, h) = (w.max(0) as u32, h.max(0) as u32);
+        if SCREEN_SIZES.iter().any(|&(a, b)| (w, h) == (a, b) || (w, h) == (b, a)) {
+            return "screenshot".to_string();
+        }
+        let ratio = w.max(h) as f64 / w.min(h).max(1) as f64;
+        // A4 is √2 ≈ 1.414, US Letter 11/8.5 ≈ 1.294
+        if (ratio - 1.414).abs() < 0.01 || (ratio - 1.294).abs() < 0.01 {
+            return "document".to_string();
+        }
+    }
+    "photo".to_string()
+}
+
+/// Whether files without an EXIF date take their mtime as `taken_at` (the default) or are
+/// left undated, keeping mtime guesses out of the timeline
+static MTIME_DATE_FALLBACK: AtomicBool = AtomicBool::new(true);
+
+/// Where a `taken_at` came from
+pub const DATE_SOURCE_EXIF: &str = "exif";
+/// A Google Takeout sidecar's photoTakenTime
+pub const DATE_SOURCE_SIDECAR: &str = "sidecar";
+pub const DATE_SOURCE_MTIME: &str = "mtime";
+/// Inferred from neighbouring photos (`infer_dates`)
+pub const DATE_SOURCE_ESTIMATED: &str = "estimated";
+/// Set or corrected by the user
+pub const DATE_SOURCE_MANUAL: &str = "manual";
+
+/// `None` restores the default (fall back to mtime). Affects files indexed from now on.
+pub fn set_mtime_date_fallback(enabled: Option<bool>) {
+    MTIME_DATE_FALLBACK.store(enabled.unwrap_or(true), Ordering::Relaxed);
+}
+
+pub fn mtime_date_fallback() -> bool {
+    MTIME_DATE_FALLBACK.load(Ordering::Relaxed)
+}
+
+/// Pick the capture time: EXIF when present, else the file mtime (unless that fallback is
+/// off, see `set_mtime_date_fallback`). The mtime is a real UTC instant, so it gets this
+/// machine's local offset for that moment. Also returns the `DATE_SOURCE_*` used.
+fn resolve_taken_at(exif: &ExifData, modified_at: &str) -> (Option<String>, Option<i32>, Option<&'static str>) {
+    match &exif.taken_at {
+        Some(t) => (Some(t.clone()), exif.utc_offset_minutes, Some(DATE_SOURCE_EXIF)),
+        None if !mtime_date_fallback() => (None, None, None),
+        None => (
+            Some(modified_at.to_string()),
+            local_offset_minutes(modified_at),
+            Some(DATE_SOURCE_MTIME),
+        ),
+    }
+}
+
+fn local_offset_minutes(utc: &str) -> Option<i32> {
+    let dt = chrono::DateTime::parse_from_rfc3339(utc).ok()?;
+    let local = dt.with_timezone(&chrono::Local);
+    Some(local.offset().local_minus_utc() / 60)
+}
+
+// ── Color space ──
+// Wide-gamut and HDR detection from the embedded ICC profile (JPEG, PNG, WebP, TIFF) or the
+// HEIF/AVIF `colr` box. No profile means the file is treated as sRGB.
+
+/// Gamuts wider than sRGB
+pub const WIDE_GAMUT_SPACES: &[&str] = &["display_p3", "adobe_rgb", "prophoto", "rec2020"];
+
+#[derive(Debug, Clone)]
+pub struct ColorInfo {
+    /// "srgb", "display_p3", "adobe_rgb", "prophoto", "rec2020" or "other"
+    pub color_space: String,
+    /// PQ/HLG transfer or more than 8 bits per channel
+    pub is_hdr: bool,
+}
+
+impl Default for ColorInfo {
+    fn default() -> Self {
+        ColorInfo { color_space: "srgb".to_string(), is_hdr: false }
+    }
+}
+
+/// Classify a profile by its description ("Display P3", "Adobe RGB (1998)", ...)
+fn color_space_from_name(desc: &str) -> &'static str {
+    let d = desc.to_lowercase();
+    if d.contains("p3") {
+        "display_p3"
+    } else if d.contains("adobe rgb") || d.contains("adobergb") {
+        "adobe_rgb"
+    } else if d.contains("prophoto") || d.contains("romm") {
+        "prophoto"
+    } else if d.contains("2020") || d.contains("2100") {
+        "rec2020"
+    } else if d.contains("srgb") || d.contains("61966") {
+        "srgb"
+    } else {
+        "other"
+    }
+}
+
+/// ITU-T H.273 colour primaries code point
+fn color_space_from_primaries(primaries: u16) -> &'static str {
+    match primaries {
+        1 | 2 => "srgb",
+        9 => "rec2020",
+        11 | 12 => "display_p3",
+        _ => "other",
+    }
+}
+
+/// H.273 transfer characteristics: 16 = PQ (SMPTE 2084), 18 = HLG
+fn is_hdr_transfer(transfer: u16) -> bool {
+    transfer == 16 || transfer == 18
+}
+
+fn be_u16(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Find an ICC tag's data by signature
+fn icc_tag<'a>(icc: &'a [u8], sig: &[u8; 4]) -> Option<&'a [u8]> {
+    let count = be_u32(icc, 128)? as usize;
+    (0..count.min(256)).find_map(|i| {
+        let entry = 132 + i * 12;
+        if icc.get(entry..entry + 4)? != sig {
+            return None;
+        }
+        let offset = be_u32(icc, entry + 4)? as usize;
+        let size = be_u32(icc, entry + 8)? as usize;
+        icc.get(offset..offset.checked_add(size)?)
+    })
+}
+
+/// Profile description: v2 `desc` (ASCII) or v4 `mluc` (first record, UTF-16BE)
+fn icc_description(icc: &[u8]) -> Option<String> {
+    let tag = icc_tag(icc, b"desc")?;
+    match tag.get(0..4)? {
+        b"desc" => {
+            let len = be_u32(tag, 8)? as usize;
+            let text = tag.get(12..12 + len)?;
+            Some(String::from_utf8_lossy(text).trim_end_matches('\0').to_string())
+        }
+        b"mluc" => {
+            let len = be_u32(tag, 20)? as usize;
+            let offset = be_u32(tag, 24)? as usize;
+            let units: Vec<u16> = tag
+                .get(offset..offset + len)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&units))
+        }
+        _ => None,
+    }
+}
+
+fn icc_color_info(icc: &[u8]) -> ColorInfo {
+    // ICC v4.4 `cicp` carries H.273 code points directly (HDR profiles use it)
+    if let Some(cicp) = icc_tag(icc, b"cicp") {
+        if let (Some(&primaries), Some(&transfer)) = (cicp.get(8), cicp.get(9)) {
+            return ColorInfo {
+                color_space: color_space_from_primaries(primaries as u16).to_string(),
+                is_hdr: is_hdr_transfer(transfer as u16),
+            };
+        }
+    }
+    let desc = icc_description(icc).unwrap_or_default();
+    let lower = desc.to_lowercase();
+    ColorInfo {
+        color_space: color_space_from_name(&desc).to_string(),
+        is_hdr: lower.contains(" pq") || lower.contains("hlg"),
+    }
+}
+
+/// How far into a HEIF/AVIF file to look for the `colr` box (it sits in the `meta` box up front)
+const HEIF_HEADER_SCAN: u64 = 512 * 1024;
+
+/// Colour info from a HEIF/AVIF `colr` box: `nclx` code points or an embedded `prof` ICC
+fn heif_color_info(path: &Path) -> Option<ColorInfo> {
+    use std::io::Read;
+    let mut head = Vec::new();
+    std::fs::File::open(path).ok()?.take(HEIF_HEADER_SCAN).read_to_end(&mut head).ok()?;
+    if head.get(4..8)? != b"ftyp" {
+        return None;
+    }
+    let at = head.windows(4).position(|w| w == b"colr")?;
+    let box_start = at.checked_sub(4)?;
+    let box_end = box_start + be_u32(&head, box_start)? as usize;
+    match head.get(at + 4..at + 8)? {
+        b"nclx" => Some(ColorInfo {
+            color_space: color_space_from_primaries(be_u16(&head, at + 8)?).to_string(),
+            is_hdr: is_hdr_transfer(be_u16(&head, at + 10)?),
+        }),
+        b"prof" | b"rICC" => Some(icc_color_info(head.get(at + 8..box_end)?)),
+        _ => None,
+    }
+}
+
+/// Dimensions and colour info from the image header, without decoding pixels
+fn read_image_header(path: &Path) -> Option<(u32, u32, ColorInfo)> {
+    use image::ImageDecoder;
+    let mut decoder = image::ImageReader::open(path).ok()?.into_decoder().ok()?;
+    let (w, h) = decoder.dimensions();
+    let color_type = decoder.color_type();
+    let deep = color_type.bytes_per_pixel() / color_type.channel_count().max(1) > 1;
+    let mut info = decoder
+        .icc_profile()
+        .ok()
+        .flatten()
+        .map(|icc| icc_color_info(&icc))
+        .unwrap_or_default();
+    info.is_hdr |= deep;
+    Some((w, h, info))
+}
+
+fn build_scanned_file(path: &Path, root: &Path, opts: &ScanOptions) -> Option<ScannedFile> {
     let path_str = path.to_string_lossy().to_string();
     let filename = path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("")
         .to_string();
-    let folder_rel = path
-        .parent()
-        .and_then(|p| p.strip_prefix(root).ok())
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
+    let folder_rel = folder_rel_for(path, root, opts.flatten_folders);
 
     let media_type = media_type_from_path(path).to_string();
     let size_bytes = std::fs::metadata(path).map(|m| m.len() as i64).unwrap_or(0);
     let modified_at = modified_time_string(path);
+    // Cloud placeholders aren't read at all: opening one would start a download
+    let is_placeholder = is_placeholder(path);
+    let inspect = media_type == "photo" && !is_placeholder;
 
-    let exif = if media_type == "photo" {
+    let mut exif = if inspect {
         parse_exif_data(path)
     } else {
         ExifData::default()
     };
+    let mut iptc = if inspect {
+        crate::iptc::read_iptc(path)
+    } else {
+        crate::iptc::IptcData::default()
+    };
+    let had_exif_date = exif.taken_at.is_some();
+    if opts.takeout_sidecars {
+        if let Some(sidecar) = crate::takeout::read_sidecar(path) {
+            apply_takeout_sidecar(sidecar, &mut exif, &mut iptc);
+        }
+    }
+    let (taken_at, utc_offset_minutes, mut date_source) = resolve_taken_at(&exif, &modified_at);
+    if !had_exif_date && exif.taken_at.is_some() {
+        date_source = Some(DATE_SOURCE_SIDECAR);
+    }
+
+    let heif = (inspect && crate::heif::is_heif_path(path))
+        .then(|| crate::heif::read_heif_info(path))
+        .flatten();
+    let animation_frames = (inspect && crate::animation::is_animatable_path(path))
+        .then(|| crate::animation::frame_count(path))
+        .flatten();
+    let (width, height, color) = if inspect {
+        match read_image_header(path) {
+            Some((w, h, color)) => (Some(w as i32), Some(h as i32), Some(color)),
+            None => (None, None, Some(heif_color_info(path).unwrap_or_default())),
+        }
+    } else {
+        (None, None, None)
+    };
+    // A decoder would report the first coded frame; the container names the real primary image
+    let (width, height) = match heif.and_then(|h| h.dimensions) {
+        Some((w, h)) => (Some(w as i32), Some(h as i32)),
+        None => (width, height),
+    };
 
-    let (width, height) = if media_type == "photo" {
-        image::ImageReader::open(path)
-            .ok()
-            .and_then(|r| r.into_dimensions().ok())
-            .map(|(w, h)| (Some(w as i32), Some(h as i32)))
-            .unwrap_or((None, None))
+    let blurhash = if opts.blurhash && inspect {
+        compute_blurhash(path)
     } else {
-        (None, None)
+        None
     };
+    let category = inspect.then(|| classify_photo(&filename, width.zip(height), &exif));
 
     Some(ScannedFile {
         path: path_str,
         filename,
         folder_rel,
-        taken_at: exif.taken_at.or_else(|| Some(modified_at.clone())),
+        taken_at,
+        date_source: date_source.map(str::to_string),
         modified_at,
         media_type,
         size_bytes,
@@ -244,52 +1060,276 @@ fn build_scanned_file(path: &Path, root: &Path) -> Option<ScannedFile> {
         iso: exif.iso,
         shutter_speed: exif.shutter_speed,
         aperture: exif.aperture,
+        shutter_seconds: exif.shutter_seconds,
+        f_number: exif.f_number,
         focal_length: exif.focal_length,
         gps_lat: exif.gps_lat,
         gps_lon: exif.gps_lon,
+        orientation: exif.orientation,
+        exposure_bias: exif.exposure_bias,
+        utc_offset_minutes,
+        blurhash,
+        category,
+        is_hdr: color.as_ref().is_some_and(|c| c.is_hdr),
+        color_space: color.map(|c| c.color_space),
+        title: iptc.title,
+        caption: iptc.caption,
+        keywords: iptc.keywords,
+        frame_count: heif.map(|h| h.frame_count).or(animation_frames).map(|n| n as i32),
+        is_animated: animation_frames.is_some_and(|n| n > 1),
+        is_placeholder,
+        motion_path: None,
+        raw_path: None,
+        jpeg_path: None,
     })
 }
 
+/// Fill gaps in the embedded metadata from a Takeout sidecar. Embedded values win; the
+/// sidecar mainly rescues dates, which Takeout otherwise leaves as the export time.
+fn apply_takeout_sidecar(sidecar: crate::takeout::TakeoutSidecar, exif: &mut ExifData, iptc: &mut crate::iptc::IptcData) {
+    if exif.taken_at.is_none() {
+        if let Some(t) = sidecar.taken_at {
+            exif.taken_at = Some(t);
+            // photoTakenTime is UTC with no zone attached
+            exif.utc_offset_minutes = None;
+        }
+    }
+    if exif.gps_lat.is_none() || exif.gps_lon.is_none() {
+        if let Some((lat, lon)) = sidecar.gps {
+            exif.gps_lat = Some(lat);
+            exif.gps_lon = Some(lon);
+        }
+    }
+    if iptc.caption.is_none() {
+        iptc.caption = sidecar.description;
+    }
+    for person in sidecar.people {
+        if !iptc.keywords.iter().any(|k| k.eq_ignore_ascii_case(&person)) {
+            iptc.keywords.push(person);
+        }
+    }
+}
+
+/// BlurHash (4×3 components) of the decoded image. The hash doesn't depend on resolution,
+/// so encoding a 32px downscale gives the same placeholder for a fraction of the cost.
+pub fn compute_blurhash(path: &Path) -> Option<String> {
+    let img = image::open(path).ok()?;
+    let small = img.thumbnail(32, 32).to_rgba8();
+    blurhash::encode(4, 3, small.width(), small.height(), small.as_raw()).ok()
+}
+
 /// Process a batch of paths into ScannedFile (for chunked progress).
-pub fn process_paths_batch(paths: &[PathBuf], root: &Path) -> Vec<ScannedFile> {
+/// EXIF parsing and dimension reads run in parallel on the scan pool; output order matches `paths`.
+/// Also returns the paths that hit `FILE_TIMEOUT`, so they can be recorded and retried.
+pub fn process_paths_batch(paths: &[PathBuf], root: &Path, opts: &ScanOptions) -> (Vec<ScannedFile>, Vec<String>) {
     let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
-    paths
-        .iter()
-        .filter_map(|path| build_scanned_file(path, &root))
-        .collect()
+    let outcomes: Vec<FileOutcome> = scan_pool().install(|| {
+        paths
+            .par_iter()
+            .map(|path| build_scanned_file_with_deadline(path, &root, opts))
+            .collect()
+    });
+    let mut files = Vec::with_capacity(outcomes.len());
+    let mut timed_out = Vec::new();
+    for outcome in outcomes {
+        match outcome {
+            FileOutcome::Parsed(f) => files.push(f),
+            FileOutcome::TimedOut(path) => timed_out.push(path),
+            FileOutcome::Skipped => {}
+        }
+    }
+    (files, timed_out)
+}
+
+/// Longest one file may take to parse (EXIF, dimensions, optional decode). A stalled
+/// network read can block indefinitely, so past this the scan moves on without it.
+const FILE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+enum FileOutcome {
+    Parsed(ScannedFile),
+    Skipped,
+    TimedOut(String),
+}
+
+/// Most parse workers left blocked past `FILE_TIMEOUT` at once. Each one holds a thread
+/// until its read returns; past this, overrunning files wait for a free worker instead of
+/// getting a fresh thread.
+const MAX_STUCK_WORKERS: usize = 16;
+
+/// `FileJob::state` values
+const JOB_PENDING: u8 = 0;
+const JOB_DONE: u8 = 1;
+const JOB_ABANDONED: u8 = 2;
+
+/// One file for a parse worker. Whichever of worker and caller moves `state` off
+/// `JOB_PENDING` first decides whether the result counts.
+struct FileJob {
+    path: PathBuf,
+    root: PathBuf,
+    opts: ScanOptions,
+    reply: std::sync::mpsc::Sender<Option<ScannedFile>>,
+    state: Arc<AtomicU8>,
+}
+
+/// Long-lived threads that parse files for the scan pool, so a read that never returns
+/// blocks one of these rather than a rayon worker. Workers are started on demand, up to the
+/// scan thread count plus `MAX_STUCK_WORKERS`, and then wait on the queue for more files.
+struct FileWorkers {
+    jobs: Mutex<std::sync::mpsc::Sender<FileJob>>,
+    queue: Arc<Mutex<std::sync::mpsc::Receiver<FileJob>>>,
+    /// Workers started and not yet retired
+    live: Arc<AtomicUsize>,
+    /// Workers waiting on the queue
+    idle: Arc<AtomicUsize>,
+    /// Workers running a job their caller gave up on
+    stuck: Arc<AtomicUsize>,
+}
+
+static FILE_WORKERS: OnceLock<FileWorkers> = OnceLock::new();
+
+fn file_workers() -> &'static FileWorkers {
+    FILE_WORKERS.get_or_init(|| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        FileWorkers {
+            jobs: Mutex::new(tx),
+            queue: Arc::new(Mutex::new(rx)),
+            live: Arc::new(AtomicUsize::new(0)),
+            idle: Arc::new(AtomicUsize::new(0)),
+            stuck: Arc::new(AtomicUsize::new(0)),
+        }
+    })
+}
+
+impl FileWorkers {
+    /// Queue a job, starting a worker first if none is free and the cap allows
+    fn submit(&self, job: FileJob) {
+        let cap = scan_threads() + MAX_STUCK_WORKERS;
+        if self.idle.load(Ordering::SeqCst) == 0 && self.live.load(Ordering::SeqCst) < cap {
+            self.spawn_worker();
+        }
+        let _ = self.jobs.lock().unwrap().send(job);
+    }
+
+    fn spawn_worker(&self) {
+        let (queue, live, idle, stuck) =
+            (self.queue.clone(), self.live.clone(), self.idle.clone(), self.stuck.clone());
+        live.fetch_add(1, Ordering::SeqCst);
+        let spawned = std::thread::Builder::new()
+            .name("scan-file".to_string())
+            .spawn(move || loop {
+                idle.fetch_add(1, Ordering::SeqCst);
+                let job = queue.lock().unwrap().recv();
+                idle.fetch_sub(1, Ordering::SeqCst);
+                let Ok(job) = job else { break };
+                // Given up on while still queued: it was never stuck here
+                if job.state.load(Ordering::SeqCst) == JOB_ABANDONED {
+                    stuck.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                }
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    build_scanned_file(&job.path, &job.root, &job.opts)
+                }))
+                .ok()
+                .flatten();
+                let _ = job.reply.send(result);
+                // The caller timed out and a replacement may have started; retire
+                if job.state.compare_exchange(JOB_PENDING, JOB_DONE, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+                    stuck.fetch_sub(1, Ordering::SeqCst);
+                    live.fetch_sub(1, Ordering::SeqCst);
+                    break;
+                }
+            });
+        if spawned.is_err() {
+            self.live.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// `build_scanned_file` on a parse worker with a deadline. A worker that overruns can't be
+/// cancelled; it is abandoned and retires on its own if the read ever returns.
+fn build_scanned_file_with_deadline(path: &Path, root: &Path, opts: &ScanOptions) -> FileOutcome {
+    let workers = file_workers();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let state = Arc::new(AtomicU8::new(JOB_PENDING));
+    workers.submit(FileJob {
+        path: path.to_path_buf(),
+        root: root.to_path_buf(),
+        opts: opts.clone(),
+        reply: tx,
+        state: state.clone(),
+    });
+    let outcome = |result: Option<Option<ScannedFile>>| match result {
+        Some(Some(file)) => FileOutcome::Parsed(file),
+        // No file, or the worker panicked on it
+        _ => FileOutcome::Skipped,
+    };
+    match rx.recv_timeout(FILE_TIMEOUT) {
+        Ok(result) => outcome(Some(result)),
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+            if state.compare_exchange(JOB_PENDING, JOB_ABANDONED, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+                // Finished just as the deadline passed
+                return outcome(rx.try_recv().ok());
+            }
+            let stuck = workers.stuck.fetch_add(1, Ordering::SeqCst) + 1;
+            eprintln!(
+                "  ⏱ Timed out after {}s, skipping: {} ({} parse workers stuck)",
+                FILE_TIMEOUT.as_secs(),
+                path.display(),
+                stuck
+            );
+            FileOutcome::TimedOut(path.to_string_lossy().to_string())
+        }
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => FileOutcome::Skipped,
+    }
 }
 
 /// Light version of build_scanned_file — skips expensive image dimension reading.
 /// Dimensions come from thumbnail generation instead.
-pub fn build_scanned_file_light(path: &Path, root: &Path) -> Option<ScannedFile> {
+pub fn build_scanned_file_light(path: &Path, root: &Path, flatten_folders: bool) -> Option<ScannedFile> {
     let path_str = path.to_string_lossy().to_string();
     let filename = path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("")
         .to_string();
-    let folder_rel = path
-        .parent()
-        .and_then(|p| p.strip_prefix(root).ok())
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
+    let folder_rel = folder_rel_for(path, root, flatten_folders);
 
     let media_type = media_type_from_path(path).to_string();
     let size_bytes = std::fs::metadata(path).map(|m| m.len() as i64).unwrap_or(0);
     let modified_at = modified_time_string(path);
+    let is_placeholder = is_placeholder(path);
+    let inspect = media_type == "photo" && !is_placeholder;
 
-    let exif = if media_type == "photo" {
+    let exif = if inspect {
         parse_exif_data(path)
     } else {
         ExifData::default()
     };
+    let iptc = if inspect {
+        crate::iptc::read_iptc(path)
+    } else {
+        crate::iptc::IptcData::default()
+    };
+    let (taken_at, utc_offset_minutes, date_source) = resolve_taken_at(&exif, &modified_at);
 
     // Skip dimensions — they'll come from thumbnail generation
+    let animation_frames = (inspect && crate::animation::is_animatable_path(path))
+        .then(|| crate::animation::frame_count(path))
+        .flatten();
+    let frame_count = (inspect && crate::heif::is_heif_path(path))
+        .then(|| crate::heif::read_heif_info(path))
+        .flatten()
+        .map(|h| h.frame_count)
+        .or(animation_frames)
+        .map(|n| n as i32);
+    let category = inspect.then(|| classify_photo(&filename, None, &exif));
+
     Some(ScannedFile {
         path: path_str,
         filename,
         folder_rel,
-        taken_at: exif.taken_at.or_else(|| Some(modified_at.clone())),
+        taken_at,
+        date_source: date_source.map(str::to_string),
         modified_at,
         media_type,
         size_bytes,
@@ -301,19 +1341,119 @@ pub fn build_scanned_file_light(path: &Path, root: &Path) -> Option<ScannedFile>
         iso: exif.iso,
         shutter_speed: exif.shutter_speed,
         aperture: exif.aperture,
+        shutter_seconds: exif.shutter_seconds,
+        f_number: exif.f_number,
         focal_length: exif.focal_length,
         gps_lat: exif.gps_lat,
         gps_lon: exif.gps_lon,
+        orientation: exif.orientation,
+        exposure_bias: exif.exposure_bias,
+        utc_offset_minutes,
+        blurhash: None,
+        category,
+        color_space: None,
+        is_hdr: false,
+        title: iptc.title,
+        caption: iptc.caption,
+        keywords: iptc.keywords,
+        frame_count,
+        is_animated: animation_frames.is_some_and(|n| n > 1),
+        is_placeholder,
+        motion_path: None,
+        raw_path: None,
+        jpeg_path: None,
     })
 }
 
 #[allow(dead_code)]
 pub fn scan_directory(root: &Path) -> Vec<ScannedFile> {
-    let paths = collect_media_paths(root);
+    let opts = ScanOptions::default();
+    let paths = collect_media_paths(root, &opts);
     let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
-    paths
-        .par_iter()
-        .filter_map(|path| build_scanned_file(path, &root))
-        .collect()
+    scan_pool().install(|| {
+        paths
+            .par_iter()
+            .filter_map(|path| build_scanned_file(path, &root, &opts))
+            .collect()
+    })
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rational_exif_numbers() {
+        assert_eq!(parse_exif_number("250/1"), Some(250.0));
+        assert_eq!(parse_exif_number("1/250"), Some(0.004));
+        assert_eq!(parse_exif_number(" 28/10 "), Some(2.8));
+        assert_eq!(parse_exif_number("1/0"), None);
+    }
+
+    #[test]
+    fn parses_decimal_exif_numbers() {
+        assert_eq!(parse_exif_number("0.004"), Some(0.004));
+        assert_eq!(parse_exif_number("2.8"), Some(2.8));
+        assert_eq!(parse_exif_number("-0.7 EV"), Some(-0.7));
+        assert_eq!(parse_exif_number("35 mm"), Some(35.0));
+        assert_eq!(parse_exif_number("fast"), None);
+        assert_eq!(parse_exif_number(""), None);
+    }
+
+    #[test]
+    fn parses_exif_datetimes() {
+        let dt = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(parse_exif_datetime("2021:05:03 23:30:00"), Some(dt("2021-05-03 23:30:00")));
+        assert_eq!(parse_exif_datetime("\"2021:05:03 23:30:00\""), Some(dt("2021-05-03 23:30:00")));
+        assert_eq!(parse_exif_datetime("2021:05:03"), Some(dt("2021-05-03 00:00:00")));
+        assert_eq!(parse_exif_datetime("0000:00:00 00:00:00"), None);
+        assert_eq!(parse_exif_datetime(""), None);
+    }
+
+    #[test]
+    fn parses_utc_offsets() {
+        assert_eq!(parse_utc_offset("+02:00"), Some(120));
+        assert_eq!(parse_utc_offset("-05:30"), Some(-330));
+        assert_eq!(parse_utc_offset("\"+09:00\""), Some(540));
+        assert_eq!(parse_utc_offset("+15:00"), None);
+        assert_eq!(parse_utc_offset("02:00"), None);
+        assert_eq!(parse_utc_offset(""), None);
+    }
+
+    #[test]
+    fn parses_exposure_strings() {
+        assert_eq!(parse_exif_number("1/1000"), Some(0.001));
+        assert_eq!(parse_exif_number("1/1000 s"), Some(0.001));
+        assert_eq!(parse_exif_number("30"), Some(30.0));
+        assert_eq!(parse_exif_number("30 sec"), Some(30.0));
+        assert_eq!(parse_exif_number("f/1.8"), Some(1.8));
+    }
+
+    #[test]
+    fn normalizes_exposure_round_trip() {
+        let shutter = |raw: &str| parse_exif_number(raw).and_then(format_shutter_speed);
+        let aperture = |raw: &str| parse_exif_number(raw).and_then(format_aperture);
+        assert_eq!(shutter("1/1000").as_deref(), Some("1/1000 s"));
+        assert_eq!(shutter("0.001").as_deref(), Some("1/1000 s"));
+        assert_eq!(shutter("30").as_deref(), Some("30 s"));
+        assert_eq!(aperture("f/1.8").as_deref(), Some("f/1.8"));
+        assert_eq!(aperture("18/10").as_deref(), Some("f/1.8"));
+    }
+
+    #[test]
+    fn formats_shutter_speed() {
+        assert_eq!(format_shutter_speed(0.004).as_deref(), Some("1/250 s"));
+        assert_eq!(format_shutter_speed(2.0).as_deref(), Some("2 s"));
+        assert_eq!(format_shutter_speed(1.5).as_deref(), Some("1.5 s"));
+        assert_eq!(format_shutter_speed(0.0), None);
+    }
+
+    #[test]
+    fn formats_aperture_and_focal_length() {
+        assert_eq!(format_aperture(2.8).as_deref(), Some("f/2.8"));
+        assert_eq!(format_aperture(0.0), None);
+        assert_eq!(format_focal_length(49.6).as_deref(), Some("50mm"));
+        assert_eq!(format_focal_length(-1.0), None);
+    }
+}
@@ -0,0 +1,200 @@
+//! Search query parsing: `camera:canon iso:>800 year:2021 tag:beach fav:true sunset`
+//! becomes structured filters plus free-text terms, each AND-ed together.
+
+/// Fewest characters a free-text-only query needs; shorter ones match nearly everything
+pub const MIN_TEXT_QUERY_LEN: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cmp {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Cmp {
+    pub fn sql(self) -> &'static str {
+        match self {
+            Cmp::Eq => "=",
+            Cmp::Gt => ">",
+            Cmp::Ge => ">=",
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchFilter {
+    /// Matches make or model
+    Camera(String),
+    Lens(String),
+    Iso(Cmp, i32),
+    /// Exposure time in seconds (`shutter:>1`, `shutter:<=1/1000`)
+    Shutter(Cmp, f64),
+    /// F-number (`aperture:<2.8`, `f:1.8`)
+    Aperture(Cmp, f64),
+    Year(i32),
+    Month(i32),
+    Tag(String),
+    Favorite(bool),
+    MediaType(String),
+    Folder(String),
+    Extension(String),
+    /// Unqualified word — matches filename, folder, path, date, camera or tag
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub filters: Vec<SearchFilter>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Self {
+        let filters = tokenize(query)
+            .into_iter()
+            .map(|token| parse_token(&token))
+            .collect();
+        SearchQuery { filters }
+    }
+
+    /// Whether the query narrows anything down: an empty or whitespace-only query doesn't,
+    /// nor does free text shorter than `MIN_TEXT_QUERY_LEN`. Any qualifier does.
+    pub fn is_searchable(&self) -> bool {
+        let terms = self.text_terms();
+        if terms.len() < self.filters.len() {
+            return true;
+        }
+        terms.iter().map(|t| t.chars().count()).sum::<usize>() >= MIN_TEXT_QUERY_LEN
+    }
+
+    /// Free-text terms only (used for relevance scoring)
+    pub fn text_terms(&self) -> Vec<&str> {
+        self.filters
+            .iter()
+            .filter_map(|f| match f {
+                SearchFilter::Text(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Split on whitespace, keeping double-quoted runs together (`camera:"eos r5"`)
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_cmp(value: &str) -> (Cmp, &str) {
+    for (prefix, cmp) in [(">=", Cmp::Ge), ("<=", Cmp::Le), (">", Cmp::Gt), ("<", Cmp::Lt), ("=", Cmp::Eq)] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (cmp, rest);
+        }
+    }
+    (Cmp::Eq, value)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// A qualifier with an unknown key or an unparseable value falls back to free text
+fn parse_token(token: &str) -> SearchFilter {
+    let text = || SearchFilter::Text(token.to_string());
+    let (key, value) = match token.split_once(':') {
+        Some((k, v)) if !v.is_empty() => (k.to_lowercase(), v),
+        _ => return text(),
+    };
+    match key.as_str() {
+        "camera" | "make" | "model" => SearchFilter::Camera(value.to_string()),
+        "lens" => SearchFilter::Lens(value.to_string()),
+        "iso" => {
+            let (cmp, num) = parse_cmp(value);
+            num.parse().map(|n| SearchFilter::Iso(cmp, n)).unwrap_or_else(|_| text())
+        }
+        "shutter" | "exposure" => {
+            let (cmp, num) = parse_cmp(value);
+            crate::scan::parse_exif_number(num)
+                .map(|s| SearchFilter::Shutter(cmp, s))
+                .unwrap_or_else(text)
+        }
+        "aperture" | "f" => {
+            let (cmp, num) = parse_cmp(value);
+            crate::scan::parse_exif_number(num)
+                .map(|f| SearchFilter::Aperture(cmp, f))
+                .unwrap_or_else(text)
+        }
+        "year" => value.parse().map(SearchFilter::Year).unwrap_or_else(|_| text()),
+        "month" => value
+            .parse()
+            .ok()
+            .filter(|m| (1..=12).contains(m))
+            .map(SearchFilter::Month)
+            .unwrap_or_else(text),
+        "tag" => SearchFilter::Tag(value.to_string()),
+        "fav" | "favorite" => parse_bool(value).map(SearchFilter::Favorite).unwrap_or_else(text),
+        "type" => match value.to_lowercase().as_str() {
+            "photo" | "photos" | "image" => SearchFilter::MediaType("photo".to_string()),
+            "video" | "videos" => SearchFilter::MediaType("video".to_string()),
+            _ => text(),
+        },
+        "folder" | "in" => SearchFilter::Folder(value.to_string()),
+        "ext" => SearchFilter::Extension(value.trim_start_matches('.').to_lowercase()),
+        _ => text(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn searchable(query: &str) -> bool {
+        SearchQuery::parse(query).is_searchable()
+    }
+
+    #[test]
+    fn empty_and_short_queries_are_not_searchable() {
+        assert!(!searchable(""));
+        assert!(!searchable("   "));
+        assert!(!searchable("a"));
+        assert!(!searchable(" x "));
+    }
+
+    #[test]
+    fn text_and_qualifiers_are_searchable() {
+        assert!(searchable("50%"));
+        assert!(searchable("a_b"));
+        assert!(searchable("ab"));
+        assert!(searchable("fav:true"));
+        assert!(searchable("year:2021 x"));
+    }
+
+    #[test]
+    fn special_characters_stay_plain_text() {
+        assert_eq!(SearchQuery::parse("50%").filters, vec![SearchFilter::Text("50%".to_string())]);
+        assert_eq!(SearchQuery::parse("a_b").filters, vec![SearchFilter::Text("a_b".to_string())]);
+    }
+}
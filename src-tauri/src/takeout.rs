@@ -0,0 +1,119 @@
+//! Google Takeout sidecars. Takeout strips or rewrites much of the original metadata and
+//! stamps every file with the export time, but ships a `{filename}.json` next to each item
+//! holding the real capture time, location, people and description.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Takeout truncates sidecar names to this many characters before ".json"
+const MAX_SIDECAR_STEM: usize = 46;
+
+/// Suffix newer exports insert before ".json" (itself subject to truncation)
+const SUPPLEMENTAL_SUFFIX: &str = ".supplemental-metadata";
+
+#[derive(Debug, Clone, Default)]
+pub struct TakeoutSidecar {
+    /// Capture time, UTC, in the stored `taken_at` format
+    pub taken_at: Option<String>,
+    pub gps: Option<(f64, f64)>,
+    pub description: Option<String>,
+    pub people: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSidecar {
+    description: Option<String>,
+    photo_taken_time: Option<RawTime>,
+    geo_data: Option<RawGeo>,
+    geo_data_exif: Option<RawGeo>,
+    #[serde(default)]
+    people: Vec<RawPerson>,
+}
+
+#[derive(Deserialize)]
+struct RawTime {
+    /// Unix seconds as a string
+    timestamp: String,
+}
+
+#[derive(Deserialize)]
+struct RawGeo {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Deserialize)]
+struct RawPerson {
+    name: String,
+}
+
+/// Sidecar for a media file, if one sits next to it
+pub fn read_sidecar(path: &Path) -> Option<TakeoutSidecar> {
+    let sidecar = find_sidecar(path)?;
+    let raw: RawSidecar = serde_json::from_slice(&std::fs::read(sidecar).ok()?).ok()?;
+
+    let taken_at = raw
+        .photo_taken_time
+        .and_then(|t| t.timestamp.trim().parse::<i64>().ok())
+        .filter(|&ts| ts > 0)
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+    // Takeout writes 0,0 when it has no location
+    let gps = [raw.geo_data, raw.geo_data_exif]
+        .into_iter()
+        .flatten()
+        .find(|g| g.latitude != 0.0 || g.longitude != 0.0)
+        .map(|g| (g.latitude, g.longitude));
+    let description = raw
+        .description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+    let people = raw
+        .people
+        .into_iter()
+        .map(|p| p.name.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .collect();
+    Some(TakeoutSidecar { taken_at, gps, description, people })
+}
+
+/// Locate the sidecar, allowing for Takeout's naming quirks: name truncation, the
+/// `.supplemental-metadata` infix, duplicates (`IMG(1).jpg` → `IMG.jpg(1).json`) and edited
+/// copies (`IMG-edited.jpg` shares `IMG.jpg.json`)
+fn find_sidecar(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_str()?;
+    candidate_names(name)
+        .into_iter()
+        .map(|n| dir.join(n))
+        .find(|p| p.is_file())
+}
+
+fn candidate_names(name: &str) -> Vec<String> {
+    let mut bases = vec![name.to_string()];
+    let (stem, ext) = match name.rfind('.') {
+        Some(i) => (&name[..i], &name[i..]),
+        None => (name, ""),
+    };
+    if let Some(original) = stem.strip_suffix("-edited") {
+        bases.push(format!("{}{}", original, ext));
+    }
+    let mut names = Vec::new();
+    for base in &bases {
+        for full in [base.clone(), format!("{}{}", base, SUPPLEMENTAL_SUFFIX)] {
+            names.push(format!("{}.json", full));
+            let truncated: String = full.chars().take(MAX_SIDECAR_STEM).collect();
+            if truncated != full {
+                names.push(format!("{}.json", truncated));
+            }
+        }
+    }
+    // "IMG(1).jpg" → "IMG.jpg(1).json"
+    if let Some(open) = stem.rfind('(').filter(|_| stem.ends_with(')')) {
+        let counter = &stem[open..];
+        names.push(format!("{}{}{}.json", &stem[..open], ext, counter));
+        names.push(format!("{}{}{}{}.json", &stem[..open], ext, SUPPLEMENTAL_SUFFIX, counter));
+    }
+    names
+}
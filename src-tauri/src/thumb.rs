@@ -1,29 +1,123 @@
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::io;
-use std::sync::OnceLock;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use sha2::{Sha256, Digest};
 use tokio::sync::Semaphore;
 
-/// Thumbnail spec: max 240×240px, JPEG quality 75, aspect ratio preserved
+/// Thumbnail spec: max 240×240px, aspect ratio preserved; encoding per `ThumbQuality`
 const THUMB_SIZE: u32 = 240;
-const THUMB_QUALITY: u8 = 75;
 
-/// Max concurrent thumbnail generations — capped based on CPU count at init
-const MAX_CONCURRENT_THUMBS_LOW: usize = 2; // ≤4 logical CPUs
-const MAX_CONCURRENT_THUMBS_HIGH: usize = 4; // >4 logical CPUs
+/// Permit count and the semaphore enforcing it. Resizing swaps in a new semaphore; permits
+/// already held are returned to the old one, so running decodes finish undisturbed.
+static THUMB_SEMAPHORE: OnceLock<RwLock<(usize, Arc<Semaphore>)>> = OnceLock::new();
 
-static THUMB_SEMAPHORE: OnceLock<Semaphore> = OnceLock::new();
+/// Default max concurrent thumbnail generations: one per logical CPU
+pub fn default_thumb_concurrency() -> usize {
+    num_cpus::get().max(1)
+}
 
-fn semaphore() -> &'static Semaphore {
+fn thumb_semaphore_slot() -> &'static RwLock<(usize, Arc<Semaphore>)> {
     THUMB_SEMAPHORE.get_or_init(|| {
-        let cpus = num_cpus::get();
-        let permits = if cpus <= 4 { MAX_CONCURRENT_THUMBS_LOW } else { MAX_CONCURRENT_THUMBS_HIGH };
-        eprintln!("  🔧 Thumbnail semaphore: {} permits (detected {} logical CPUs)", permits, cpus);
-        Semaphore::new(permits)
+        let permits = default_thumb_concurrency();
+        eprintln!("  🔧 Thumbnail semaphore: {} permits (detected {} logical CPUs)", permits, num_cpus::get());
+        RwLock::new((permits, Arc::new(Semaphore::new(permits))))
     })
 }
 
+fn semaphore() -> Arc<Semaphore> {
+    thumb_semaphore_slot().read().unwrap().1.clone()
+}
+
+/// Change how many thumbnails/previews decode at once. `None` restores the default.
+pub fn set_thumb_concurrency(permits: Option<usize>) {
+    let permits = permits.filter(|p| *p > 0).unwrap_or_else(default_thumb_concurrency);
+    let mut slot = thumb_semaphore_slot().write().unwrap();
+    if slot.0 != permits {
+        eprintln!("  🔧 Thumbnail semaphore: {} permits", permits);
+        *slot = (permits, Arc::new(Semaphore::new(permits)));
+    }
+}
+
+pub fn thumb_concurrency() -> usize {
+    thumb_semaphore_slot().read().unwrap().0
+}
+
+/// How thumbnails are rendered. Encoded in the cache file name, so changing any of it
+/// regenerates thumbnails on demand instead of serving ones made the old way.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbQuality {
+    /// JPEG quality, 1–100
+    pub jpeg_quality: u8,
+    /// Finish the downscale with Lanczos3 (crisper, slower) instead of the fast filter alone
+    pub lanczos: bool,
+    /// Unsharp-mask radius (sigma) applied after resizing; 0 disables it
+    pub sharpen: f32,
+}
+
+impl ThumbQuality {
+    pub const DEFAULT: ThumbQuality = ThumbQuality { jpeg_quality: 75, lanczos: false, sharpen: 0.0 };
+
+    /// Cache name suffix. The defaults keep the original `240` so existing caches stay valid.
+    fn cache_suffix(&self) -> String {
+        let mut suffix = THUMB_SIZE.to_string();
+        if *self != Self::DEFAULT {
+            suffix.push_str(&format!("q{}", self.jpeg_quality));
+            if self.lanczos {
+                suffix.push('l');
+            }
+            if self.sharpen > 0.0 {
+                suffix.push_str(&format!("s{}", (self.sharpen * 10.0).round() as u32));
+            }
+        }
+        suffix
+    }
+}
+
+impl Default for ThumbQuality {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static THUMB_QUALITY: RwLock<ThumbQuality> = RwLock::new(ThumbQuality::DEFAULT);
+
+/// Change how thumbnails are rendered. `None` restores the defaults.
+pub fn set_thumb_quality(quality: Option<ThumbQuality>) {
+    *THUMB_QUALITY.write().unwrap() = quality.unwrap_or_default();
+}
+
+pub fn thumb_quality() -> ThumbQuality {
+    *THUMB_QUALITY.read().unwrap()
+}
+
+/// Thumbnails currently being written. Cache pruning skips these so a file is never
+/// deleted out from under its encoder.
+static IN_FLIGHT: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+
+fn in_flight() -> &'static Mutex<HashSet<PathBuf>> {
+    IN_FLIGHT.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Marks a thumbnail path as in-flight for as long as it lives
+struct InFlightGuard(PathBuf);
+
+impl InFlightGuard {
+    fn new(path: PathBuf) -> Self {
+        in_flight().lock().unwrap().insert(path.clone());
+        InFlightGuard(path)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        in_flight().lock().unwrap().remove(&self.0);
+    }
+}
+
 /// Shared struct returned to frontend with all thumbnail info
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -50,13 +144,72 @@ pub fn thumbnail_cache_dir() -> io::Result<PathBuf> {
     Ok(thumb_dir)
 }
 
-/// SHA-256 hash of the absolute file path string, hex-encoded
+/// SHA-256 hash of the absolute file path string, hex-encoded.
+/// This was the cache key before content keys; kept for migrating old cache files.
 fn hash_path(path: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(path.as_bytes());
     hex::encode(hasher.finalize())
 }
 
+/// Bytes sampled from each end of a file for its content key
+const CONTENT_SAMPLE: u64 = 64 * 1024;
+
+/// Content keys memoized by path, valid while (mtime, size) are unchanged. Entries are
+/// dropped when a photo is renamed or deleted (`forget_content_keys`).
+static CONTENT_KEYS: OnceLock<Mutex<HashMap<String, (u64, u64, String)>>> = OnceLock::new();
+
+/// Memoized keys kept before the memo starts over; roughly 150 bytes each
+const CONTENT_KEY_LIMIT: usize = 100_000;
+
+/// Drop memoized content keys for paths that were renamed away or deleted
+pub fn forget_content_keys<'a>(source_paths: impl IntoIterator<Item = &'a str>) {
+    if let Some(memo) = CONTENT_KEYS.get() {
+        let mut memo = memo.lock().unwrap();
+        for path in source_paths {
+            memo.remove(path);
+        }
+    }
+}
+
+/// SHA-256 over the file size plus its first and last 64 KiB, hex-encoded.
+/// Renaming or moving a photo keeps its key (and thumbnail); rewriting it changes the key.
+fn content_key(source_path: &str) -> io::Result<String> {
+    let path = Path::new(source_path);
+    let size = fs::metadata(path)?.len();
+    let mtime = file_mtime(path);
+    let memo = CONTENT_KEYS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((m, s, key)) = memo.lock().unwrap().get(source_path) {
+        if *m == mtime && *s == size {
+            return Ok(key.clone());
+        }
+    }
+
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+    // Small files are hashed whole; larger ones by head and tail
+    let whole = size <= CONTENT_SAMPLE * 2;
+    let mut buf = Vec::with_capacity(CONTENT_SAMPLE as usize);
+    (&mut file).take(if whole { size } else { CONTENT_SAMPLE }).read_to_end(&mut buf)?;
+    hasher.update(&buf);
+    if !whole {
+        buf.clear();
+        file.seek(SeekFrom::End(-(CONTENT_SAMPLE as i64)))?;
+        file.take(CONTENT_SAMPLE).read_to_end(&mut buf)?;
+        hasher.update(&buf);
+    }
+    let key = hex::encode(hasher.finalize());
+
+    let mut memo = memo.lock().unwrap();
+    // Bounds the memo across very large libraries; keys are cheap to recompute
+    if memo.len() >= CONTENT_KEY_LIMIT {
+        memo.clear();
+    }
+    memo.insert(source_path.to_string(), (mtime, size, key.clone()));
+    Ok(key)
+}
+
 /// Get file modification time as Unix timestamp (u64)
 pub fn file_mtime(path: &Path) -> u64 {
     fs::metadata(path)
@@ -67,11 +220,36 @@ pub fn file_mtime(path: &Path) -> u64 {
         .unwrap_or(0)
 }
 
-/// Compute the expected thumbnail path without creating it
+/// Compute the expected thumbnail path without creating it.
+/// Keyed by file content; an unreadable file falls back to the path key.
 pub fn thumbnail_path_for(source_path: &str) -> Result<PathBuf, String> {
     let cache_dir = thumbnail_cache_dir().map_err(|e| e.to_string())?;
-    let key = hash_path(source_path);
-    Ok(cache_dir.join(format!("{}_240.jpg", key)))
+    let key = content_key(source_path).unwrap_or_else(|_| hash_path(source_path));
+    Ok(cache_dir.join(format!("{}_{}.jpg", key, thumb_quality().cache_suffix())))
+}
+
+/// Whether the grid thumbnail for `source_path` is already cached, without generating it
+pub fn thumbnail_exists(source_path: &str) -> bool {
+    thumbnail_path_for(source_path).is_ok_and(|p| p.exists())
+}
+
+/// Move a thumbnail cached under the old path-hash name to its content-key name.
+/// Returns the new thumbnail path if a legacy file was found.
+pub fn migrate_legacy_thumbnail(source_path: &str) -> io::Result<Option<PathBuf>> {
+    let legacy = thumbnail_cache_dir()?.join(format!("{}_240.jpg", hash_path(source_path)));
+    if !legacy.exists() {
+        return Ok(None);
+    }
+    let current = thumbnail_path_for(source_path).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    if current == legacy {
+        return Ok(None);
+    }
+    if current.exists() {
+        fs::remove_file(&legacy)?;
+    } else {
+        fs::rename(&legacy, &current)?;
+    }
+    Ok(Some(current))
 }
 
 /// Check if a cached thumbnail is still valid (mtime matches)
@@ -90,22 +268,30 @@ pub fn is_thumb_valid(thumb_path: &Path, expected_mtime: u64) -> bool {
 /// Generate a thumbnail for a single file. Returns ThumbnailInfo.
 /// This function is blocking and should be called from spawn_blocking.
 fn generate_thumbnail_blocking(source_path: &str, thumb_path: &Path) -> Result<(u32, u32), String> {
+    let quality = thumb_quality();
     let img = image::ImageReader::open(source_path)
         .map_err(|e| format!("Failed to open image {}: {}", source_path, e))?
         .decode()
         .map_err(|e| format!("Failed to decode image {}: {}", source_path, e))?;
 
-    let thumb = img.thumbnail(THUMB_SIZE, THUMB_SIZE);
+    let mut thumb = if quality.lanczos {
+        // Fast shrink to twice the target first, so Lanczos3 only runs over a small image
+        let coarse = img.thumbnail(THUMB_SIZE * 2, THUMB_SIZE * 2);
+        drop(img);
+        coarse.resize(THUMB_SIZE, THUMB_SIZE, image::imageops::FilterType::Lanczos3)
+    } else {
+        img.thumbnail(THUMB_SIZE, THUMB_SIZE)
+    };
+    if quality.sharpen > 0.0 {
+        thumb = thumb.unsharpen(quality.sharpen, 1);
+    }
     let (tw, th) = (thumb.width(), thumb.height());
-    // Explicitly drop the full image to free memory immediately
-    drop(img);
 
-    // Save as JPEG with quality 75
     let output_file = fs::File::create(thumb_path)
         .map_err(|e| format!("Failed to create thumbnail file: {}", e))?;
     let mut writer = io::BufWriter::new(output_file);
 
-    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, THUMB_QUALITY);
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, quality.jpeg_quality);
     thumb.write_with_encoder(encoder)
         .map_err(|e| format!("Failed to encode JPEG thumbnail: {}", e))?;
 
@@ -161,7 +347,7 @@ pub async fn get_or_create_thumbnail_info(
     }
 
     // Acquire semaphore permit — limits concurrent decode operations
-    let permit = match semaphore().acquire().await {
+    let permit = match semaphore().acquire_owned().await {
         Ok(p) => p,
         Err(e) => {
             eprintln!("  ⚠ Semaphore error: {}", e);
@@ -197,10 +383,12 @@ pub async fn get_or_create_thumbnail_info(
     // Generate thumbnail in a blocking thread
     let source = source_path.to_string();
     let out_path = thumb_path.clone();
+    let guard = InFlightGuard::new(thumb_path.clone());
     let result = tokio::task::spawn_blocking(move || {
         generate_thumbnail_blocking(&source, &out_path)
     }).await;
 
+    drop(guard);
     drop(permit);
 
     match result {
@@ -261,3 +449,319 @@ fn read_thumb_dimensions(path: &Path) -> Option<(u32, u32)> {
         .into_dimensions()
         .ok()
 }
+
+// ── Animated thumbnails ──
+
+/// Frames kept from an animation; the rest are dropped to bound the file size
+const MAX_ANIMATED_FRAMES: usize = 60;
+
+/// Animated thumbnails are opt-in: a looping GIF is many times the size of a still JPEG
+static ANIMATED_THUMBS: AtomicBool = AtomicBool::new(false);
+
+/// Turn animated thumbnails on or off. `None` restores the default (off).
+pub fn set_animated_thumbs(enabled: Option<bool>) {
+    ANIMATED_THUMBS.store(enabled.unwrap_or(false), Ordering::Relaxed);
+}
+
+pub fn animated_thumbs() -> bool {
+    ANIMATED_THUMBS.load(Ordering::Relaxed)
+}
+
+/// Animated thumbnails share the cache (and its pruning) with the stills, keyed by content
+fn animated_thumbnail_path_for(source_path: &str) -> Result<PathBuf, String> {
+    let cache_dir = thumbnail_cache_dir().map_err(|e| e.to_string())?;
+    let key = content_key(source_path).unwrap_or_else(|_| hash_path(source_path));
+    Ok(cache_dir.join(format!("{}_{}a.gif", key, THUMB_SIZE)))
+}
+
+/// Downscale every frame of an animated GIF/WebP into a looping GIF (the image crate has
+/// no animated WebP encoder). Frames keep their original timing.
+fn generate_animated_thumbnail_blocking(source_path: &str, out_path: &Path) -> Result<(u32, u32), String> {
+    use image::AnimationDecoder;
+    let reader = io::BufReader::new(
+        fs::File::open(source_path).map_err(|e| format!("Failed to open image {}: {}", source_path, e))?,
+    );
+    let is_gif = Path::new(source_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("gif"));
+    let frames = if is_gif {
+        image::codecs::gif::GifDecoder::new(reader).map(|d| d.into_frames())
+    } else {
+        image::codecs::webp::WebPDecoder::new(reader).map(|d| d.into_frames())
+    }
+    .map_err(|e| format!("Failed to decode animation {}: {}", source_path, e))?;
+
+    let mut dims = (0, 0);
+    let mut small_frames = Vec::new();
+    for frame in frames.take(MAX_ANIMATED_FRAMES) {
+        let frame = frame.map_err(|e| format!("Failed to decode frame of {}: {}", source_path, e))?;
+        let delay = frame.delay();
+        let small = image::DynamicImage::ImageRgba8(frame.into_buffer())
+            .thumbnail(THUMB_SIZE, THUMB_SIZE)
+            .to_rgba8();
+        dims = small.dimensions();
+        small_frames.push(image::Frame::from_parts(small, 0, 0, delay));
+    }
+    if small_frames.is_empty() {
+        return Err(format!("No frames in {}", source_path));
+    }
+
+    let output_file = fs::File::create(out_path)
+        .map_err(|e| format!("Failed to create animated thumbnail file: {}", e))?;
+    let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(io::BufWriter::new(output_file), 10);
+    encoder
+        .set_repeat(image::codecs::gif::Repeat::Infinite)
+        .and_then(|_| encoder.encode_frames(small_frames))
+        .map_err(|e| format!("Failed to encode animated thumbnail: {}", e))?;
+    Ok(dims)
+}
+
+/// Looping GIF thumbnail of an animated GIF/WebP when animated thumbnails are enabled;
+/// otherwise (or for a still image) the ordinary thumbnail
+pub async fn get_or_create_animated_thumbnail(source_path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(source_path);
+    if !animated_thumbs() || !crate::animation::is_animatable_path(path) {
+        return get_or_create_thumbnail(source_path).await;
+    }
+    let out_path = animated_thumbnail_path_for(source_path)?;
+    if out_path.exists() {
+        return Ok(out_path);
+    }
+    if !crate::animation::frame_count(path).is_some_and(|n| n > 1) {
+        return get_or_create_thumbnail(source_path).await;
+    }
+
+    let permit = semaphore().acquire_owned().await.map_err(|e| e.to_string())?;
+    // Another request may have finished it while we waited
+    if out_path.exists() {
+        return Ok(out_path);
+    }
+    let source = source_path.to_string();
+    let out = out_path.clone();
+    let guard = InFlightGuard::new(out_path.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        generate_animated_thumbnail_blocking(&source, &out)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    drop(guard);
+    drop(permit);
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&out_path);
+        return Err(e);
+    }
+    Ok(out_path)
+}
+
+// ── Detail-view previews ──
+
+/// Preview long edge when the caller doesn't ask for one
+pub const DEFAULT_PREVIEW_SIZE: u32 = 2048;
+const PREVIEW_QUALITY: u8 = 85;
+
+/// A cached medium-resolution rendition for the detail viewer
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewInfo {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Previews share the thumbnail cache (and its pruning), keyed by content and size
+fn preview_path_for(source_path: &str, max_dimension: u32) -> Result<PathBuf, String> {
+    let cache_dir = thumbnail_cache_dir().map_err(|e| e.to_string())?;
+    let key = content_key(source_path).unwrap_or_else(|_| hash_path(source_path));
+    Ok(cache_dir.join(format!("{}_p{}.jpg", key, max_dimension)))
+}
+
+/// Turn decoded pixels upright according to the EXIF orientation recorded at scan time
+fn apply_orientation(img: image::DynamicImage, orientation: Option<i32>) -> image::DynamicImage {
+    match orientation {
+        Some(2) => img.fliph(),
+        Some(3) => img.rotate180(),
+        Some(4) => img.flipv(),
+        Some(5) => img.rotate90().fliph(),
+        Some(6) => img.rotate90(),
+        Some(7) => img.rotate270().fliph(),
+        Some(8) => img.rotate270(),
+        _ => img,
+    }
+}
+
+fn generate_preview_blocking(
+    source_path: &str,
+    preview_path: &Path,
+    max_dimension: u32,
+    orientation: Option<i32>,
+) -> Result<(u32, u32), String> {
+    let img = image::ImageReader::open(source_path)
+        .map_err(|e| format!("Failed to open image {}: {}", source_path, e))?
+        .decode()
+        .map_err(|e| format!("Failed to decode image {}: {}", source_path, e))?;
+
+    // Never upscale: small originals are just re-encoded upright
+    let resized = if img.width() > max_dimension || img.height() > max_dimension {
+        img.resize(max_dimension, max_dimension, image::imageops::FilterType::CatmullRom)
+    } else {
+        img
+    };
+    let preview = apply_orientation(resized, orientation).to_rgb8();
+    let (w, h) = preview.dimensions();
+
+    let output_file = fs::File::create(preview_path)
+        .map_err(|e| format!("Failed to create preview file: {}", e))?;
+    let mut writer = io::BufWriter::new(output_file);
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, PREVIEW_QUALITY);
+    preview.write_with_encoder(encoder)
+        .map_err(|e| format!("Failed to encode JPEG preview: {}", e))?;
+    Ok((w, h))
+}
+
+/// Get or create a preview whose long edge is at most `max_dimension`, sharing the
+/// thumbnail semaphore so previews and thumbnails don't oversubscribe the CPU
+pub async fn get_or_create_preview(
+    source_path: &str,
+    max_dimension: u32,
+    orientation: Option<i32>,
+) -> Result<PreviewInfo, String> {
+    let preview_path = preview_path_for(source_path, max_dimension)?;
+    let info = |(width, height): (u32, u32)| PreviewInfo {
+        path: preview_path.to_string_lossy().to_string(),
+        width,
+        height,
+    };
+    if let Some(dims) = read_thumb_dimensions(&preview_path) {
+        return Ok(info(dims));
+    }
+
+    let permit = semaphore().acquire_owned().await.map_err(|e| e.to_string())?;
+    // Another request may have finished it while we waited
+    if let Some(dims) = read_thumb_dimensions(&preview_path) {
+        return Ok(info(dims));
+    }
+
+    let source = source_path.to_string();
+    let out_path = preview_path.clone();
+    let guard = InFlightGuard::new(preview_path.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        generate_preview_blocking(&source, &out_path, max_dimension, orientation)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    drop(guard);
+    drop(permit);
+
+    if result.is_err() {
+        let _ = fs::remove_file(&preview_path);
+    }
+    result.map(info)
+}
+
+// ── Cache management ──
+
+/// File count and total bytes, for the whole cache or for what an operation removed
+#[derive(serde::Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Cached thumbnails with size and last-use time (atime where the filesystem tracks it,
+/// otherwise mtime)
+fn cache_entries() -> io::Result<Vec<(PathBuf, u64, std::time::SystemTime)>> {
+    let mut out = Vec::new();
+    for entry in fs::read_dir(thumbnail_cache_dir()?)? {
+        let entry = entry?;
+        let meta = match entry.metadata() {
+            Ok(m) if m.is_file() => m,
+            _ => continue,
+        };
+        let last_used = meta
+            .accessed()
+            .or_else(|_| meta.modified())
+            .unwrap_or(std::time::UNIX_EPOCH);
+        out.push((entry.path(), meta.len(), last_used));
+    }
+    Ok(out)
+}
+
+pub fn thumbnail_cache_size() -> io::Result<CacheStats> {
+    let entries = cache_entries()?;
+    Ok(CacheStats {
+        files: entries.len() as u64,
+        bytes: entries.iter().map(|(_, len, _)| len).sum(),
+    })
+}
+
+/// Delete the given entries, skipping in-flight ones. The in-flight set stays locked for
+/// the whole pass so no generation can start on a path mid-delete.
+fn remove_entries(entries: &[(PathBuf, u64, std::time::SystemTime)]) -> CacheStats {
+    let busy = in_flight().lock().unwrap();
+    let mut removed = CacheStats::default();
+    for (path, len, _) in entries {
+        if busy.contains(path) {
+            continue;
+        }
+        if fs::remove_file(path).is_ok() {
+            removed.files += 1;
+            removed.bytes += len;
+        }
+    }
+    removed
+}
+
+/// Remove every cached thumbnail (they regenerate on demand). Returns what was removed.
+pub fn clear_thumbnail_cache() -> io::Result<CacheStats> {
+    Ok(remove_entries(&cache_entries()?))
+}
+
+/// Delete the cached thumbnails and previews of photos being removed from the catalog.
+/// Cache names are keyed by content, with the path hash as fallback, so call while the files
+/// are still on disk; `stored_thumbs` (the rows' `thumb_path`s) supply the key of files that
+/// are already gone. A byte-identical copy elsewhere shares the key and simply regenerates.
+pub fn remove_renditions_for(source_paths: &[String], stored_thumbs: &[String]) -> io::Result<CacheStats> {
+    let keys: HashSet<String> = source_paths
+        .iter()
+        .flat_map(|p| [content_key(p).ok(), Some(hash_path(p))])
+        .flatten()
+        .chain(stored_thumbs.iter().filter_map(|t| cache_key_of(Path::new(t)).map(str::to_string)))
+        .collect();
+    forget_content_keys(source_paths.iter().map(String::as_str));
+    if keys.is_empty() {
+        return Ok(CacheStats::default());
+    }
+    let victims: Vec<_> = cache_entries()?
+        .into_iter()
+        .filter(|(path, _, _)| cache_key_of(path).is_some_and(|key| keys.contains(key)))
+        .collect();
+    Ok(remove_entries(&victims))
+}
+
+/// The source key a cache file is named after (`{key}_{variant}.jpg`)
+fn cache_key_of(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()?.split_once('_').map(|(key, _)| key)
+}
+
+/// Delete least-recently-used thumbnails until the cache fits in `max_bytes`.
+/// Returns what was removed.
+pub fn prune_thumbnail_cache(max_bytes: u64) -> io::Result<CacheStats> {
+    let mut entries = cache_entries()?;
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return Ok(CacheStats::default());
+    }
+    entries.sort_by_key(|(_, _, last_used)| *last_used);
+    let mut victims = Vec::new();
+    for entry in entries {
+        if total <= max_bytes {
+            break;
+        }
+        total = total.saturating_sub(entry.1);
+        victims.push(entry);
+    }
+    Ok(remove_entries(&victims))
+}
@@ -0,0 +1,50 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// How many destructive operations are kept for undo
+const MAX_UNDO_OPS: usize = 50;
+
+/// A file rename/move recorded so it can be reversed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMove {
+    pub photo_id: i64,
+    pub from_path: String,
+    pub to_path: String,
+}
+
+/// A recorded operation. Undoing applies its inverse.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum UndoOp {
+    /// Photos moved to the in-app trash — undo restores them
+    SoftDelete { photo_ids: Vec<i64> },
+    /// Photos restored from the in-app trash — undo trashes them again
+    Restore { photo_ids: Vec<i64> },
+    /// Files renamed or moved on disk — undo moves each back, newest first
+    Move { moves: Vec<FileMove> },
+}
+
+/// Bounded in-memory log of recent operations (oldest dropped first)
+#[derive(Debug, Default)]
+pub struct UndoLog {
+    ops: VecDeque<UndoOp>,
+}
+
+impl UndoLog {
+    pub fn push(&mut self, op: UndoOp) {
+        if self.ops.len() >= MAX_UNDO_OPS {
+            self.ops.pop_front();
+        }
+        self.ops.push_back(op);
+    }
+
+    pub fn pop(&mut self) -> Option<UndoOp> {
+        self.ops.pop_back()
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+}
@@ -26,18 +26,8 @@ pub fn start_watcher(
 }
 
 fn handle_fs_event(app: &tauri::AppHandle, event: &Event, _watched_dir: &str) {
-    let media_extensions = [
-        "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif",
-        "heic", "heif", "raw", "arw", "cr2", "nef", "dng",
-        "mp4", "mov", "avi", "mkv", "webm", "m4v", "wmv", "3gp",
-    ];
-
-    let is_media = |path: &std::path::Path| -> bool {
-        path.extension()
-            .and_then(|e| e.to_str())
-            .map(|ext| media_extensions.contains(&ext.to_lowercase().as_str()))
-            .unwrap_or(false)
-    };
+    // Non-UTF-8 names are never indexed (see `scan::collect_media_files_reporting`)
+    let is_media = |p: &Path| p.to_str().is_some() && crate::scan::is_media_path(p);
 
     match event.kind {
         EventKind::Create(_) => {
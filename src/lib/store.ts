@@ -462,7 +462,7 @@ export async function selectLibrary() {
         }
     } catch (err) {
         console.error('Failed to select library:', err)
-        await message(String(err), { title: 'Import Error', kind: 'error' })
+        await message(errorMessage(err), { title: 'Import Error', kind: 'error' })
     }
 }
 
@@ -491,7 +491,7 @@ export async function indexLibrary() {
         } catch { /* ignore */ }
     } catch (err) {
         console.error('Failed to index library:', err)
-        await message(String(err), { title: 'Indexing Error', kind: 'error' })
+        await message(errorMessage(err), { title: 'Indexing Error', kind: 'error' })
     } finally {
         isIndexing.set(false)
     }
@@ -521,6 +521,20 @@ export async function invokeCommand<T>(cmd: string, args?: any): Promise<T> {
     return invoke<T>(cmd, args)
 }
 
+/** Commands reject with `{ code, message }` (see AppError in the backend) */
+export interface CommandError {
+    code: 'no_library' | 'not_found' | 'invalid_input' | 'conflict' | 'database' | 'io' | 'other'
+    message: string
+    /** Set on a rename conflict: the file in the way and a free name to offer instead */
+    existingPath?: string
+    suggestedName?: string
+}
+
+export function errorMessage(err: unknown): string {
+    if (err && typeof err === 'object' && 'message' in err) return String((err as CommandError).message)
+    return String(err)
+}
+
 export async function searchPhotos(query: string) {
     searchQuery.set(query)
 }